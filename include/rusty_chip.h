@@ -0,0 +1,90 @@
+/*
+ * C interface to the Rusty CHIP interpreter, built as the `rusty_chip`
+ * shared library by `cargo build --release`. Keep in step with src/ffi.rs;
+ * its tests compare every prototype and status value against this file.
+ *
+ * Every function returns a status rather than unwinding; a null handle
+ * gives RUSTY_CHIP_NULL_POINTER. A handle must not be used from two
+ * threads at once.
+ */
+
+#ifndef RUSTY_CHIP_H
+#define RUSTY_CHIP_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct RustyChip RustyChip;
+
+typedef enum RustyChipStatus {
+    RUSTY_CHIP_OK = 0,
+    RUSTY_CHIP_NULL_POINTER = 1,
+    RUSTY_CHIP_BAD_PLATFORM = 2,
+    RUSTY_CHIP_BAD_KEY = 3,
+    RUSTY_CHIP_ROM_TOO_LARGE = 4,
+    RUSTY_CHIP_FAULT = 5,
+    RUSTY_CHIP_BAD_STATE = 6,
+    RUSTY_CHIP_BUFFER_TOO_SMALL = 7,
+    RUSTY_CHIP_PANIC = 8
+} RustyChipStatus;
+
+enum {
+    RUSTY_CHIP_PLATFORM_CHIP8 = 0,
+    RUSTY_CHIP_PLATFORM_SUPER_CHIP = 1,
+    RUSTY_CHIP_PLATFORM_XO_CHIP = 2,
+    RUSTY_CHIP_PLATFORM_CHIP8X = 3
+};
+
+/* Creates a machine with no program loaded and the platform's default quirks. */
+RustyChipStatus rusty_chip_create(uint8_t platform, RustyChip **out);
+
+/* Frees a machine. Null is ignored. */
+void rusty_chip_destroy(RustyChip *chip);
+
+/* Loads a ROM and restarts. On error the old program keeps running. */
+RustyChipStatus rusty_chip_load_rom(RustyChip *chip, const uint8_t *rom, size_t len);
+
+/* Restarts the loaded ROM from power on. */
+RustyChipStatus rusty_chip_reset(RustyChip *chip);
+
+/* Runs up to n instructions without ticking the timers; ran may be null. */
+RustyChipStatus rusty_chip_step(RustyChip *chip, uint32_t n, uint32_t *ran);
+
+/* Runs one 60 Hz frame: the configured instructions and a timer tick. */
+RustyChipStatus rusty_chip_run_frame(RustyChip *chip);
+
+/*
+ * width * height bytes, row by row, with bit 0 set for plane 0 and bit 1 for
+ * plane 1. Valid until the next call on the machine.
+ */
+RustyChipStatus rusty_chip_framebuffer(RustyChip *chip, const uint8_t **pixels, uint32_t *width, uint32_t *height);
+
+/* Presses or releases a key, 0x0 to 0xF. */
+RustyChipStatus rusty_chip_set_key(RustyChip *chip, uint8_t key, bool pressed);
+
+/* Whether the sound timer is running. */
+RustyChipStatus rusty_chip_beep(RustyChip *chip, bool *beep);
+
+/*
+ * Stores the state's size in len and copies it into buffer. Returns
+ * RUSTY_CHIP_BUFFER_TOO_SMALL without copying if buffer is null or smaller,
+ * so pass a null buffer first to size one.
+ */
+RustyChipStatus rusty_chip_save_state(RustyChip *chip, uint8_t *buffer, size_t capacity, size_t *len);
+
+/* Restores a saved state. On error the machine is unchanged. */
+RustyChipStatus rusty_chip_load_state(RustyChip *chip, const uint8_t *state, size_t len);
+
+/* A static description of a status. */
+const char *rusty_chip_status_message(int32_t status);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,997 @@
+use std::collections::HashMap;
+
+use cpu::Register;
+use error::{Error, Result};
+use {Address, Byte};
+
+const DEFAULT_ORIGIN: Address = 0x200;
+const MAX_ADDR: Address = 0x10000;
+/// Bound on macro expansions, so a macro that invokes itself fails
+/// instead of expanding forever.
+const MAX_EXPANSIONS: usize = 0x10000;
+
+/// Assembles Octo source into a ROM image for `Cpu::new`. The image starts
+/// at 0x200, or at the first `:org` if it comes before any code. As in
+/// Octo, a program that does not begin with `: main` gets a jump to it.
+pub fn assemble(source: &str) -> Result<Vec<Byte>> {
+    Assembler::new(source).run()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Token {
+    text: String,
+    line: usize,
+    column: usize
+}
+
+impl Token {
+    fn error(&self, message: String) -> Error {
+        Error::Assembly { line: self.line, column: self.column, message }
+    }
+}
+
+/// Splits source into whitespace-separated tokens, dropping `#` comments.
+fn tokenize(source: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    for (n, line) in source.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        let mut start = None;
+        for (i, c) in line.char_indices().chain(Some((line.len(), ' '))) {
+            match (c.is_whitespace(), start) {
+                (true, Some(s)) => {
+                    tokens.push(Token {
+                        text: line[s..i].to_string(),
+                        line: n + 1,
+                        column: line[..s].chars().count() + 1
+                    });
+                    start = None;
+                },
+                (false, None) => start = Some(i),
+                _ => {}
+            }
+        }
+    }
+    tokens
+}
+
+fn number(text: &str) -> Option<i64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text)
+    };
+    let value = if let Some(hex) = digits.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()?
+    } else if let Some(binary) = digits.strip_prefix("0b") {
+        i64::from_str_radix(binary, 2).ok()?
+    } else if digits.starts_with(|c: char| c.is_ascii_digit()) {
+        digits.parse().ok()?
+    } else {
+        return None;
+    };
+    Some(if negative { -value } else { value })
+}
+
+/// Where a label's address is patched in once it is known.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Field {
+    Nnn,
+    Long,
+    UnpackHigh(Byte),
+    Low
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Fixup {
+    addr: Address,
+    field: Field,
+    token: Token
+}
+
+/// An open `if ... begin`, `else` or `loop`, with the address of the jump
+/// it still has to patch.
+#[derive(Clone, Debug, PartialEq)]
+enum Control {
+    If(Token, Address),
+    Else(Token, Address),
+    Loop(Token, Address, Vec<Address>)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Macro {
+    params: Vec<String>,
+    body: Vec<Token>
+}
+
+#[derive(Debug)]
+struct Assembler {
+    /// Tokens still to read, last first, so macro bodies can be pushed on.
+    tokens: Vec<Token>,
+    last: Token,
+    expansions: usize,
+    base: Address,
+    here: Address,
+    started: bool,
+    rom: Vec<Byte>,
+    labels: HashMap<String, Address>,
+    constants: HashMap<String, i64>,
+    aliases: HashMap<String, Register>,
+    macros: HashMap<String, Macro>,
+    fixups: Vec<Fixup>,
+    next: Option<Token>,
+    controls: Vec<Control>
+}
+
+impl Assembler {
+    fn new(source: &str) -> Assembler {
+        let mut tokens = tokenize(source);
+        tokens.reverse();
+        Assembler {
+            tokens,
+            last: Token { text: String::new(), line: 1, column: 1 },
+            expansions: 0,
+            base: DEFAULT_ORIGIN,
+            here: DEFAULT_ORIGIN,
+            started: false,
+            rom: Vec::new(),
+            labels: HashMap::new(),
+            constants: HashMap::new(),
+            aliases: HashMap::new(),
+            macros: HashMap::new(),
+            fixups: Vec::new(),
+            next: None,
+            controls: Vec::new()
+        }
+    }
+
+    fn run(mut self) -> Result<Vec<Byte>> {
+        while let Some(token) = self.tokens.pop() {
+            self.last = token.clone();
+            self.statement(token)?;
+        }
+        match self.controls.last() {
+            Some(Control::If(token, _)) | Some(Control::Else(token, _)) => {
+                return Err(token.error("'if ... begin' without 'end'".to_string()));
+            },
+            Some(Control::Loop(token, _, _)) => return Err(token.error("'loop' without 'again'".to_string())),
+            None => {}
+        }
+        if let Some(ref token) = self.next {
+            return Err(token.error("':next' without a following instruction".to_string()));
+        }
+        self.resolve_fixups()?;
+        Ok(self.rom)
+    }
+
+    fn next_token(&mut self) -> Result<Token> {
+        match self.tokens.pop() {
+            Some(token) => {
+                self.last = token.clone();
+                Ok(token)
+            },
+            None => Err(self.last.error("unexpected end of input".to_string()))
+        }
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.last().map(|token| token.text.as_str())
+    }
+
+    fn expect(&mut self, text: &str) -> Result<Token> {
+        let token = self.next_token()?;
+        if token.text != text {
+            return Err(token.error(format!("expected '{}', found '{}'", text, token.text)));
+        }
+        Ok(token)
+    }
+
+    fn statement(&mut self, token: Token) -> Result<()> {
+        match token.text.as_str() {
+            ":" => {
+                let name = self.next_token()?;
+                let here = self.here;
+                self.define_label(&name, here)
+            },
+            ":const" => {
+                let name = self.name()?;
+                let value = self.value(i64::MIN, i64::MAX)?;
+                self.constants.insert(name, value);
+                Ok(())
+            },
+            ":alias" => {
+                let name = self.name()?;
+                let register = self.register()?;
+                self.aliases.insert(name, register);
+                Ok(())
+            },
+            ":macro" => self.define_macro(),
+            ":calc" => {
+                let name = self.name()?;
+                let value = self.calc()?;
+                self.constants.insert(name, value);
+                Ok(())
+            },
+            ":byte" => {
+                let value = if self.peek() == Some("{") { self.calc()? } else { self.value(-0x80, 0xFF)? };
+                self.emit(value as Byte, &token)
+            },
+            ":org" => {
+                let addr = self.value(0x0, MAX_ADDR as i64 - 1)? as Address;
+                if !self.started { self.base = addr; }
+                self.here = addr;
+                Ok(())
+            },
+            ":next" => {
+                self.next = Some(self.next_token()?);
+                Ok(())
+            },
+            ":call" => self.address(0x2000, &token),
+            ":unpack" => {
+                let nibble = self.value(0x0, 0xF)? as Byte;
+                let label = self.next_token()?;
+                match self.resolve(&label.text) {
+                    Some(addr) => {
+                        self.emit_word(0x6000 | (nibble as u16) << 4 | (addr as u16 >> 8 & 0xF), &token)?;
+                        self.emit_word(0x6100 | (addr as u16 & 0xFF), &token)
+                    },
+                    None => {
+                        self.start(&token)?;
+                        self.fixup(self.here + 1, Field::UnpackHigh(nibble), &label);
+                        self.emit_word(0x6000, &token)?;
+                        self.fixup(self.here + 1, Field::Low, &label);
+                        self.emit_word(0x6100, &token)
+                    }
+                }
+            },
+            ":breakpoint" => self.next_token().map(|_| ()),
+            ":monitor" => {
+                self.next_token()?;
+                self.next_token().map(|_| ())
+            },
+            "clear" => self.emit_word(0x00E0, &token),
+            "return" | ";" => self.emit_word(0x00EE, &token),
+            "scroll-down" => {
+                let n = self.value(0x0, 0xF)? as u16;
+                self.emit_word(0x00C0 | n, &token)
+            },
+            "scroll-up" => {
+                let n = self.value(0x0, 0xF)? as u16;
+                self.emit_word(0x00D0 | n, &token)
+            },
+            "scroll-right" => self.emit_word(0x00FB, &token),
+            "scroll-left" => self.emit_word(0x00FC, &token),
+            "exit" => self.emit_word(0x00FD, &token),
+            "lores" => self.emit_word(0x00FE, &token),
+            "hires" => self.emit_word(0x00FF, &token),
+            "jump" => self.address(0x1000, &token),
+            "jump0" => self.address(0xB000, &token),
+            "save" | "load" => {
+                let x = self.register()?;
+                if self.peek() == Some("-") {
+                    self.next_token()?;
+                    let y = self.register()?;
+                    let n = if token.text == "save" { 0x2 } else { 0x3 };
+                    self.emit_word(0x5000 | xy(x, y) | n, &token)
+                } else {
+                    let kk = if token.text == "save" { 0x55 } else { 0x65 };
+                    self.emit_word(0xF000 | xy(x, 0) | kk, &token)
+                }
+            },
+            "saveflags" => self.register_op(0xF075, &token),
+            "loadflags" => self.register_op(0xF085, &token),
+            "bcd" => self.register_op(0xF033, &token),
+            "sprite" => {
+                let x = self.register()?;
+                let y = self.register()?;
+                let n = self.value(0x0, 0xF)? as u16;
+                self.emit_word(0xD000 | xy(x, y) | n, &token)
+            },
+            "plane" => {
+                let n = self.value(0x0, 0xF)? as Register;
+                self.emit_word(0xF001 | xy(n, 0), &token)
+            },
+            "audio" => self.emit_word(0xF002, &token),
+            "delay" | "buzzer" | "pitch" => {
+                self.expect(":=")?;
+                let kk = match token.text.as_str() {
+                    "delay" => 0x15,
+                    "buzzer" => 0x18,
+                    _ => 0x3A
+                };
+                self.register_op(0xF000 | kk, &token)
+            },
+            "i" => self.i_statement(&token),
+            "if" => self.if_statement(&token),
+            "else" => {
+                let jump = match self.controls.pop() {
+                    Some(Control::If(_, jump)) => jump,
+                    _ => return Err(token.error("'else' without 'if ... begin'".to_string()))
+                };
+                let end_jump = self.here;
+                self.emit_word(0x1000, &token)?;
+                self.patch(jump, &token)?;
+                self.controls.push(Control::Else(token, end_jump));
+                Ok(())
+            },
+            "end" => {
+                match self.controls.pop() {
+                    Some(Control::If(_, jump)) | Some(Control::Else(_, jump)) => self.patch(jump, &token),
+                    _ => Err(token.error("'end' without 'if ... begin'".to_string()))
+                }
+            },
+            "loop" => {
+                self.start(&token)?;
+                let here = self.here;
+                self.controls.push(Control::Loop(token, here, Vec::new()));
+                Ok(())
+            },
+            "while" => {
+                let skip = self.condition(true)?;
+                self.emit_word(skip, &token)?;
+                let jump = self.here;
+                self.emit_word(0x1000, &token)?;
+                match self.controls.iter_mut().rev().find_map(|control| match *control {
+                    Control::Loop(_, _, ref mut breaks) => Some(breaks),
+                    _ => None
+                }) {
+                    Some(breaks) => breaks.push(jump),
+                    None => return Err(token.error("'while' outside 'loop'".to_string()))
+                }
+                Ok(())
+            },
+            "again" => {
+                let (start, breaks) = match self.controls.pop() {
+                    Some(Control::Loop(_, start, breaks)) => (start, breaks),
+                    _ => return Err(token.error("'again' without 'loop'".to_string()))
+                };
+                if start > 0xFFF {
+                    return Err(token.error(format!("address {:#x} does not fit in 12 bits", start)));
+                }
+                self.emit_word(0x1000 | start as u16, &token)?;
+                for jump in breaks {
+                    self.patch(jump, &token)?;
+                }
+                Ok(())
+            },
+            text => {
+                if let Some(x) = self.parse_register(text) {
+                    return self.register_statement(x, &token);
+                }
+                if let Some(value) = number(text).or_else(|| self.constants.get(text).cloned()) {
+                    if !(-0x80..=0xFF).contains(&value) {
+                        return Err(token.error(format!("value {} does not fit in a byte", value)));
+                    }
+                    return self.emit(value as Byte, &token);
+                }
+                if self.macros.contains_key(text) {
+                    return self.expand(&token);
+                }
+                if text.starts_with(':') {
+                    return Err(token.error(format!("unknown directive '{}'", text)));
+                }
+                self.address_of(0x2000, token.clone(), &token)
+            }
+        }
+    }
+
+    fn i_statement(&mut self, token: &Token) -> Result<()> {
+        let op = self.next_token()?;
+        match op.text.as_str() {
+            ":=" => {
+                let rhs = self.next_token()?;
+                match rhs.text.as_str() {
+                    "long" => {
+                        self.emit_word(0xF000, token)?;
+                        let label = self.next_token()?;
+                        match self.resolve(&label.text) {
+                            Some(addr) if (0x0..MAX_ADDR as i64).contains(&addr) => self.emit_word(addr as u16, token),
+                            Some(addr) => Err(label.error(format!("address {:#x} out of range", addr))),
+                            None => {
+                                self.fixup(self.here, Field::Long, &label);
+                                self.emit_word(0x0, token)
+                            }
+                        }
+                    },
+                    "hex" => self.register_op(0xF029, token),
+                    "bighex" => self.register_op(0xF030, token),
+                    _ => self.address_of(0xA000, rhs, token)
+                }
+            },
+            "+=" => self.register_op(0xF01E, token),
+            _ => Err(op.error(format!("expected ':=' or '+=', found '{}'", op.text)))
+        }
+    }
+
+    fn if_statement(&mut self, token: &Token) -> Result<()> {
+        let cond = self.conditional_parts()?;
+        let word = self.next_token()?;
+        match word.text.as_str() {
+            "then" => self.emit_word(cond, token),
+            "begin" => {
+                self.emit_word(invert(cond), token)?;
+                let jump = self.here;
+                self.emit_word(0x1000, token)?;
+                self.controls.push(Control::If(token.clone(), jump));
+                Ok(())
+            },
+            _ => Err(word.error(format!("expected 'then' or 'begin', found '{}'", word.text)))
+        }
+    }
+
+    /// The skip that runs the next instruction only if the condition holds,
+    /// or only if it fails when `negate` is set.
+    fn condition(&mut self, negate: bool) -> Result<u16> {
+        let skip = self.conditional_parts()?;
+        Ok(if negate { invert(skip) } else { skip })
+    }
+
+    fn conditional_parts(&mut self) -> Result<u16> {
+        let x = self.register()?;
+        let op = self.next_token()?;
+        match op.text.as_str() {
+            "key" => Ok(0xE0A1 | xy(x, 0)),
+            "-key" => Ok(0xE09E | xy(x, 0)),
+            "==" | "!=" => {
+                let rhs = self.next_token()?;
+                let equal = op.text == "==";
+                match self.parse_register(&rhs.text) {
+                    Some(y) => Ok(if equal { 0x9000 } else { 0x5000 } | xy(x, y)),
+                    None => {
+                        let kk = self.byte_of(&rhs)? as u16;
+                        Ok(if equal { 0x4000 } else { 0x3000 } | xy(x, 0) | kk)
+                    }
+                }
+            },
+            // As in Octo, VF takes the difference and its borrow flag decides.
+            "<" | ">" | "<=" | ">=" => {
+                let rhs = self.next_token()?;
+                let load = match self.parse_register(&rhs.text) {
+                    Some(y) => 0x8000 | xy(0xF, y),
+                    None => 0x6000 | xy(0xF, 0) | self.byte_of(&rhs)? as u16
+                };
+                self.emit_word(load, &op)?;
+                let (subtract, skip) = match op.text.as_str() {
+                    ">" => (0x8005, 0x3F01),
+                    "<" => (0x8007, 0x3F01),
+                    ">=" => (0x8007, 0x4F01),
+                    _ => (0x8005, 0x4F01)
+                };
+                self.emit_word(subtract | xy(0xF, x), &op)?;
+                Ok(skip)
+            },
+            _ => Err(op.error(format!("expected a condition, found '{}'", op.text)))
+        }
+    }
+
+    fn register_statement(&mut self, x: Register, token: &Token) -> Result<()> {
+        let op = self.next_token()?;
+        let rhs = self.next_token()?;
+        let y = self.parse_register(&rhs.text);
+        let word = match (op.text.as_str(), y) {
+            (":=", Some(y)) => 0x8000 | xy(x, y),
+            (":=", None) => {
+                match rhs.text.as_str() {
+                    "random" => {
+                        let kk = self.value(-0x80, 0xFF)? as Byte;
+                        0xC000 | xy(x, 0) | kk as u16
+                    },
+                    "key" => 0xF00A | xy(x, 0),
+                    "delay" => 0xF007 | xy(x, 0),
+                    _ => 0x6000 | xy(x, 0) | self.byte_of(&rhs)? as u16
+                }
+            },
+            ("+=", Some(y)) => 0x8004 | xy(x, y),
+            ("+=", None) => 0x7000 | xy(x, 0) | self.byte_of(&rhs)? as u16,
+            ("-=", Some(y)) => 0x8005 | xy(x, y),
+            ("-=", None) => 0x7000 | xy(x, 0) | (self.byte_of(&rhs)?.wrapping_neg()) as u16,
+            ("|=", Some(y)) => 0x8001 | xy(x, y),
+            ("&=", Some(y)) => 0x8002 | xy(x, y),
+            ("^=", Some(y)) => 0x8003 | xy(x, y),
+            (">>=", Some(y)) => 0x8006 | xy(x, y),
+            ("=-", Some(y)) => 0x8007 | xy(x, y),
+            ("<<=", Some(y)) => 0x800E | xy(x, y),
+            ("|=", None) | ("&=", None) | ("^=", None) | (">>=", None) | ("=-", None) | ("<<=", None) => {
+                return Err(rhs.error(format!("expected a register, found '{}'", rhs.text)));
+            },
+            _ => return Err(op.error(format!("unknown operator '{}'", op.text)))
+        };
+        self.emit_word(word, token)
+    }
+
+    fn register_op(&mut self, word: u16, token: &Token) -> Result<()> {
+        let x = self.register()?;
+        self.emit_word(word | xy(x, 0), token)
+    }
+
+    fn address(&mut self, prefix: u16, token: &Token) -> Result<()> {
+        let target = self.next_token()?;
+        self.address_of(prefix, target, token)
+    }
+
+    /// Emits `prefix` with a 12-bit address, patched later if `target` is
+    /// a label not yet defined.
+    fn address_of(&mut self, prefix: u16, target: Token, token: &Token) -> Result<()> {
+        match self.resolve(&target.text) {
+            Some(addr) if (0x0..=0xFFF).contains(&addr) => self.emit_word(prefix | addr as u16, token),
+            Some(addr) => Err(target.error(format!("address {:#x} does not fit in 12 bits", addr))),
+            None => {
+                if self.parse_register(&target.text).is_some() || target.text.starts_with(':') {
+                    return Err(target.error(format!("expected an address, found '{}'", target.text)));
+                }
+                self.start(token)?;
+                self.fixup(self.here, Field::Nnn, &target);
+                self.emit_word(prefix, token)
+            }
+        }
+    }
+
+    fn fixup(&mut self, addr: Address, field: Field, token: &Token) {
+        self.fixups.push(Fixup { addr, field, token: token.clone() });
+    }
+
+    fn resolve_fixups(&mut self) -> Result<()> {
+        for fixup in &self.fixups {
+            let value = match self.resolve(&fixup.token.text) {
+                Some(value) => value,
+                None => return Err(fixup.token.error(format!("undefined name '{}'", fixup.token.text)))
+            };
+            let offset = fixup.addr - self.base;
+            match fixup.field {
+                Field::Nnn => {
+                    if !(0x0..=0xFFF).contains(&value) {
+                        return Err(fixup.token.error(format!("address {:#x} does not fit in 12 bits", value)));
+                    }
+                    self.rom[offset] = self.rom[offset] & 0xF0 | (value >> 8) as Byte;
+                    self.rom[offset + 1] = value as Byte;
+                },
+                Field::Long => {
+                    self.rom[offset] = (value >> 8) as Byte;
+                    self.rom[offset + 1] = value as Byte;
+                },
+                Field::UnpackHigh(nibble) => self.rom[offset] = nibble << 4 | (value >> 8 & 0xF) as Byte,
+                Field::Low => self.rom[offset] = value as Byte
+            }
+        }
+        Ok(())
+    }
+
+    /// Points the jump at `addr` to the current address.
+    fn patch(&mut self, addr: Address, token: &Token) -> Result<()> {
+        if self.here > 0xFFF {
+            return Err(token.error(format!("address {:#x} does not fit in 12 bits", self.here)));
+        }
+        let offset = addr - self.base;
+        self.rom[offset] = self.rom[offset] & 0xF0 | (self.here >> 8) as Byte;
+        self.rom[offset + 1] = self.here as Byte;
+        Ok(())
+    }
+
+    fn define_label(&mut self, name: &Token, addr: Address) -> Result<()> {
+        self.check_name(name)?;
+        if self.labels.contains_key(&name.text) {
+            return Err(name.error(format!("label '{}' is already defined", name.text)));
+        }
+        self.labels.insert(name.text.clone(), addr);
+        Ok(())
+    }
+
+    fn check_name(&self, name: &Token) -> Result<()> {
+        if number(&name.text).is_some() || self.parse_register(&name.text).is_some() {
+            return Err(name.error(format!("'{}' is not a valid name", name.text)));
+        }
+        Ok(())
+    }
+
+    fn name(&mut self) -> Result<String> {
+        let name = self.next_token()?;
+        self.check_name(&name)?;
+        Ok(name.text)
+    }
+
+    fn define_macro(&mut self) -> Result<()> {
+        let name = self.name()?;
+        let mut params = Vec::new();
+        loop {
+            let token = self.next_token()?;
+            if token.text == "{" { break; }
+            params.push(token.text);
+        }
+        let (body, _) = self.block()?;
+        self.macros.insert(name, Macro { params, body });
+        Ok(())
+    }
+
+    fn expand(&mut self, token: &Token) -> Result<()> {
+        self.expansions += 1;
+        if self.expansions > MAX_EXPANSIONS {
+            return Err(token.error(format!("too many macro expansions in '{}'", token.text)));
+        }
+        let m = self.macros[&token.text].clone();
+        let mut args = HashMap::new();
+        for param in &m.params {
+            args.insert(param.clone(), self.next_token()?.text);
+        }
+        for body_token in m.body.iter().rev() {
+            let mut body_token = body_token.clone();
+            if let Some(arg) = args.get(&body_token.text) { body_token.text = arg.clone(); }
+            self.tokens.push(body_token);
+        }
+        Ok(())
+    }
+
+    /// Reads tokens up to the `}` closing an already-read `{`, returning
+    /// them and the closing brace.
+    fn block(&mut self) -> Result<(Vec<Token>, Token)> {
+        let mut body = Vec::new();
+        let mut depth = 0;
+        loop {
+            let token = self.next_token()?;
+            match token.text.as_str() {
+                "{" => depth += 1,
+                "}" if depth == 0 => return Ok((body, token)),
+                "}" => depth -= 1,
+                _ => {}
+            }
+            body.push(token);
+        }
+    }
+
+    fn calc(&mut self) -> Result<i64> {
+        self.expect("{")?;
+        let (tokens, close) = self.block()?;
+        let mut pos = 0;
+        let value = self.expression(&tokens, &mut pos, &close)?;
+        match tokens.get(pos) {
+            Some(token) => Err(token.error(format!("unexpected '{}' in expression", token.text))),
+            None => Ok(value)
+        }
+    }
+
+    /// Octo evaluates expressions right to left with no precedence between
+    /// operators, so `1 + 2 * 3` is `1 + (2 * 3)` and `2 * 3 + 1` is 8.
+    fn expression(&self, tokens: &[Token], pos: &mut usize, close: &Token) -> Result<i64> {
+        let left = self.term(tokens, pos, close)?;
+        let op = match tokens.get(*pos) {
+            Some(op) if op.text != ")" => op,
+            _ => return Ok(left)
+        };
+        *pos += 1;
+        let right = self.expression(tokens, pos, close)?;
+        let value = match op.text.as_str() {
+            "+" => left.wrapping_add(right),
+            "-" => left.wrapping_sub(right),
+            "*" => left.wrapping_mul(right),
+            "/" | "%" if right == 0 => return Err(op.error("division by zero".to_string())),
+            "/" => left / right,
+            "%" => left % right,
+            "&" => left & right,
+            "|" => left | right,
+            "^" => left ^ right,
+            "<<" => left.wrapping_shl(right as u32),
+            ">>" => left.wrapping_shr(right as u32),
+            "min" => left.min(right),
+            "max" => left.max(right),
+            "pow" => left.wrapping_pow(right as u32),
+            "<" => (left < right) as i64,
+            ">" => (left > right) as i64,
+            "<=" => (left <= right) as i64,
+            ">=" => (left >= right) as i64,
+            "==" => (left == right) as i64,
+            "!=" => (left != right) as i64,
+            _ => return Err(op.error(format!("unknown operator '{}'", op.text)))
+        };
+        Ok(value)
+    }
+
+    fn term(&self, tokens: &[Token], pos: &mut usize, close: &Token) -> Result<i64> {
+        let token = match tokens.get(*pos) {
+            Some(token) => token,
+            None => return Err(close.error("unexpected end of expression".to_string()))
+        };
+        *pos += 1;
+        match token.text.as_str() {
+            "(" => {
+                let value = self.expression(tokens, pos, close)?;
+                match tokens.get(*pos) {
+                    Some(token) if token.text == ")" => {
+                        *pos += 1;
+                        Ok(value)
+                    },
+                    _ => Err(token.error("unclosed '('".to_string()))
+                }
+            },
+            "-" => self.term(tokens, pos, close).map(|value| value.wrapping_neg()),
+            "~" => self.term(tokens, pos, close).map(|value| !value),
+            "!" => self.term(tokens, pos, close).map(|value| (value == 0) as i64),
+            "HERE" => Ok(self.here as i64),
+            text => self.resolve(text).ok_or_else(|| token.error(format!("undefined name '{}'", text)))
+        }
+    }
+
+    fn resolve(&self, text: &str) -> Option<i64> {
+        number(text)
+            .or_else(|| self.constants.get(text).cloned())
+            .or_else(|| self.labels.get(text).map(|addr| *addr as i64))
+    }
+
+    fn value(&mut self, min: i64, max: i64) -> Result<i64> {
+        let token = self.next_token()?;
+        self.value_of(&token, min, max)
+    }
+
+    fn value_of(&self, token: &Token, min: i64, max: i64) -> Result<i64> {
+        match self.resolve(&token.text) {
+            Some(value) if value >= min && value <= max => Ok(value),
+            Some(value) => Err(token.error(format!("value {} out of range {}..{}", value, min, max))),
+            None => Err(token.error(format!("undefined name '{}'", token.text)))
+        }
+    }
+
+    fn byte_of(&self, token: &Token) -> Result<Byte> {
+        self.value_of(token, -0x80, 0xFF).map(|value| value as Byte)
+    }
+
+    fn parse_register(&self, text: &str) -> Option<Register> {
+        let mut chars = text.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some('v'), Some(digit), None) | (Some('V'), Some(digit), None) => {
+                digit.to_digit(16).map(|digit| digit as Register)
+            },
+            _ => self.aliases.get(text).cloned()
+        }
+    }
+
+    fn register(&mut self) -> Result<Register> {
+        let token = self.next_token()?;
+        self.parse_register(&token.text)
+            .ok_or_else(|| token.error(format!("expected a register, found '{}'", token.text)))
+    }
+
+    /// Reserves the jump to `main` when code begins anywhere else. Labels
+    /// already placed at the start move past it.
+    fn start(&mut self, token: &Token) -> Result<()> {
+        if self.started { return Ok(()); }
+        self.started = true;
+        if self.here == self.base && !self.labels.contains_key("main") {
+            let main = Token { text: "main".to_string(), ..token.clone() };
+            let base = self.base;
+            self.fixup(base, Field::Nnn, &main);
+            self.put(0x10, token)?;
+            self.put(0x00, token)?;
+            for addr in self.labels.values_mut().filter(|addr| **addr == base) {
+                *addr = base + 2;
+            }
+        }
+        Ok(())
+    }
+
+    fn emit(&mut self, byte: Byte, token: &Token) -> Result<()> {
+        self.start(token)?;
+        self.put(byte, token)
+    }
+
+    fn emit_word(&mut self, word: u16, token: &Token) -> Result<()> {
+        self.start(token)?;
+        if let Some(name) = self.next.take() {
+            let operand = self.here + 1;
+            self.define_label(&name, operand)?;
+        }
+        self.put((word >> 8) as Byte, token)?;
+        self.put(word as Byte, token)
+    }
+
+    fn put(&mut self, byte: Byte, token: &Token) -> Result<()> {
+        if self.here < self.base || self.here >= MAX_ADDR {
+            return Err(token.error(format!("address {:#x} is outside the program", self.here)));
+        }
+        let offset = self.here - self.base;
+        if self.rom.len() <= offset { self.rom.resize(offset + 1, 0x0); }
+        self.rom[offset] = byte;
+        self.here += 1;
+        Ok(())
+    }
+}
+
+fn xy(x: Register, y: Register) -> u16 {
+    (x as u16) << 8 | (y as u16) << 4
+}
+
+/// Swaps a skip for the one that skips in the opposite case.
+fn invert(skip: u16) -> u16 {
+    match skip >> 12 {
+        0x3 | 0x4 => skip ^ 0x7000,
+        0x5 | 0x9 => skip ^ 0xC000,
+        _ => skip ^ 0x003F
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use asm::disassembler::disassemble;
+    use cpu::platform::Platform;
+    use load_rom;
+
+    fn error_at(source: &str) -> (usize, usize, String) {
+        match assemble(source) {
+            Err(Error::Assembly { line, column, message }) => (line, column, message),
+            other => panic!("expected an assembly error, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn disassembled_roms_reassemble_identically() {
+        for name in &["ibm.ch8", "logo.ch8"] {
+            let rom = load_rom("rom", name).unwrap();
+            for platform in &[Platform::Chip8, Platform::SuperChip, Platform::XoChip, Platform::Chip8X] {
+                let listing = disassemble(&rom, *platform);
+                assert_eq!(rom, assemble(&listing).unwrap(), "{} on {:?}", name, platform);
+            }
+        }
+    }
+
+    #[test]
+    fn assemble_instructions() {
+        let source = ": main
+            clear
+            v0 := 0x12  v1 := v2  v3 += 4  v4 -= 1  v5 := random 0xFF
+            v6 |= v7  v8 >>= v9  va =- vb  vc <<= vd
+            i := 0x300  i += v1  i := hex v2
+            sprite v0 v1 5
+            delay := v3  buzzer := v4  v5 := delay  v6 := key
+            save v7  load v8  bcd v9
+            return";
+        assert_eq!(vec![
+            0x00, 0xE0,
+            0x60, 0x12, 0x81, 0x20, 0x73, 0x04, 0x74, 0xFF, 0xC5, 0xFF,
+            0x86, 0x71, 0x88, 0x96, 0x8A, 0xB7, 0x8C, 0xDE,
+            0xA3, 0x00, 0xF1, 0x1E, 0xF2, 0x29,
+            0xD0, 0x15,
+            0xF3, 0x15, 0xF4, 0x18, 0xF5, 0x07, 0xF6, 0x0A,
+            0xF7, 0x55, 0xF8, 0x65, 0xF9, 0x33,
+            0x00, 0xEE
+        ], assemble(source).unwrap());
+    }
+
+    #[test]
+    fn assemble_extended_instructions() {
+        let source = ": main hires scroll-down 3 scroll-up 2 plane 3 audio pitch := v1
+            save v2 - v5 load v1 - v3 i := long 0x1234 saveflags v7 i := bighex v0 exit";
+        assert_eq!(vec![
+            0x00, 0xFF, 0x00, 0xC3, 0x00, 0xD2, 0xF3, 0x01, 0xF0, 0x02, 0xF1, 0x3A,
+            0x52, 0x52, 0x51, 0x33, 0xF0, 0x00, 0x12, 0x34, 0xF7, 0x75, 0xF0, 0x30, 0x00, 0xFD
+        ], assemble(source).unwrap());
+    }
+
+    #[test]
+    fn labels_resolve_forward_and_backward() {
+        let source = ": main
+              sub
+              jump main
+            : sub
+              i := sprite
+              return
+            : sprite
+              0x3C 0x42";
+        assert_eq!(vec![0x22, 0x04, 0x12, 0x00, 0xA2, 0x08, 0x00, 0xEE, 0x3C, 0x42], assemble(source).unwrap());
+    }
+
+    #[test]
+    fn code_before_main_gets_jump() {
+        let source = ": sub return : main sub";
+        assert_eq!(vec![0x12, 0x04, 0x00, 0xEE, 0x22, 0x02], assemble(source).unwrap());
+    }
+
+    #[test]
+    fn const_alias_and_calc() {
+        let source = ":const SPEED 3
+            :alias x v4
+            :calc DOUBLE { SPEED * 2 + 1 }
+            :calc RIGHT { 2 * 3 + 1 }
+            : main
+            x := SPEED
+            x += DOUBLE
+            x += RIGHT
+            :byte { ( 1 + 2 ) * 3 }";
+        assert_eq!(vec![0x64, 0x03, 0x74, 0x09, 0x74, 0x08, 0x09], assemble(source).unwrap());
+    }
+
+    #[test]
+    fn macros_substitute_arguments() {
+        let source = ":macro set reg val { reg := val }
+            : main
+            set v1 5
+            set v2 0x10";
+        assert_eq!(vec![0x61, 0x05, 0x62, 0x10], assemble(source).unwrap());
+    }
+
+    #[test]
+    fn if_then_and_if_else() {
+        let source = ": main
+            if v0 == 1 then v1 := 2
+            if v0 != v1 begin
+              v2 := 3
+            else
+              v2 := 4
+            end
+            if v3 key then clear";
+        assert_eq!(vec![
+            0x40, 0x01, 0x61, 0x02,
+            0x90, 0x10, 0x12, 0x0C, 0x62, 0x03, 0x12, 0x0E, 0x62, 0x04,
+            0xE3, 0xA1, 0x00, 0xE0
+        ], assemble(source).unwrap());
+    }
+
+    #[test]
+    fn loop_while_again() {
+        let source = ": main
+            loop
+              v0 += 1
+              while v0 != 10
+            again";
+        assert_eq!(vec![0x70, 0x01, 0x40, 0x0A, 0x12, 0x08, 0x12, 0x00], assemble(source).unwrap());
+    }
+
+    #[test]
+    fn loop_above_12_bits_is_an_error() {
+        let source = ": main
+            :org 0x1000
+            loop
+              v0 += 1
+            again";
+        assert_eq!((5, 13, "address 0x1000 does not fit in 12 bits".to_string()), error_at(source));
+    }
+
+    #[test]
+    fn next_labels_operand() {
+        let source = ": main
+            :next target
+            v0 := 0
+            i := target";
+        assert_eq!(vec![0x60, 0x00, 0xA2, 0x01], assemble(source).unwrap());
+    }
+
+    #[test]
+    fn unpack_splits_address() {
+        let source = ": main :unpack 0xA data : data 0x01";
+        assert_eq!(vec![0x60, 0xA2, 0x61, 0x04, 0x01], assemble(source).unwrap());
+    }
+
+    #[test]
+    fn org_sets_start_of_image() {
+        assert_eq!(vec![0x00, 0xE0], assemble(":org 0x300\n: main\n  clear").unwrap());
+    }
+
+    #[test]
+    fn errors_report_line_and_column() {
+        assert_eq!((2, 8, "undefined name 'nowhere'".to_string()), error_at(": main\n  jump nowhere"));
+        assert_eq!((1, 14, "expected a register, found 'v'".to_string()), error_at(": main  save v"));
+        assert_eq!((3, 3, "value 300 out of range -128..255".to_string()), error_at(": main\n  v0 :=\n  300"));
+        assert_eq!((1, 8, "'loop' without 'again'".to_string()), error_at(": main loop clear"));
+        assert_eq!((2, 3, "label 'main' is already defined".to_string()), error_at(": main\n: main"));
+    }
+
+    #[test]
+    fn missing_main_is_an_error() {
+        assert_eq!((1, 1, "undefined name 'main'".to_string()), error_at("clear"));
+    }
+
+    #[test]
+    fn comparisons_subtract_into_vf() {
+        assert_eq!(vec![0x8F, 0x10, 0x8F, 0x05, 0x3F, 0x01, 0x00, 0xE0], assemble(": main if v0 > v1 then clear").unwrap());
+        assert_eq!(vec![0x8F, 0x10, 0x8F, 0x07, 0x3F, 0x01, 0x00, 0xE0], assemble(": main if v0 < v1 then clear").unwrap());
+        assert_eq!(vec![0x6F, 0x05, 0x8F, 0x27, 0x4F, 0x01, 0x00, 0xE0], assemble(": main if v2 >= 5 then clear").unwrap());
+        assert_eq!(vec![0x6F, 0x05, 0x8F, 0x25, 0x4F, 0x01, 0x00, 0xE0], assemble(": main if v2 <= 5 then clear").unwrap());
+    }
+
+    #[test]
+    fn comparisons_work_in_loops() {
+        let source = ": main
+            loop
+              v0 += 1
+              while v0 < 10
+            again";
+        assert_eq!(vec![
+            0x70, 0x01, 0x6F, 0x0A, 0x8F, 0x07, 0x4F, 0x01, 0x12, 0x0C, 0x12, 0x00
+        ], assemble(source).unwrap());
+    }
+}
@@ -0,0 +1,309 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use cpu::instruction::Instruction;
+use cpu::opcode::Opcode;
+use cpu::platform::Platform;
+use {Address, Byte};
+
+/// Data bytes written per line.
+const BYTES_PER_LINE: usize = 8;
+
+/// Turns `rom` into an Octo listing that reassembles to the same bytes.
+/// Code is found by following jumps, calls and skips from the start of
+/// the ROM; everything else is written as data.
+pub fn disassemble(rom: &[Byte], platform: Platform) -> String {
+    let mut disassembler = Disassembler::new(rom, platform);
+    disassembler.trace();
+    disassembler.listing()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Line {
+    Label(Address),
+    Code(Address, Instruction),
+    Data(Address, usize)
+}
+
+#[derive(Debug)]
+struct Disassembler<'a> {
+    rom: &'a [Byte],
+    platform: Platform,
+    origin: Address,
+    code: BTreeMap<Address, Instruction>,
+    labels: BTreeMap<Address, String>
+}
+
+impl<'a> Disassembler<'a> {
+    fn new(rom: &'a [Byte], platform: Platform) -> Disassembler<'a> {
+        Disassembler {
+            rom,
+            platform,
+            origin: platform.rom_range().start,
+            code: BTreeMap::new(),
+            labels: BTreeMap::new()
+        }
+    }
+
+    fn end(&self) -> Address {
+        self.origin + self.rom.len()
+    }
+
+    fn word(&self, addr: Address) -> Option<u16> {
+        if addr < self.origin || addr + 2 > self.end() { return None; }
+        let offset = addr - self.origin;
+        Some((self.rom[offset] as u16) << 8 | self.rom[offset + 1] as u16)
+    }
+
+    fn decode(&self, addr: Address) -> Option<Instruction> {
+        self.word(addr).map(|code| Opcode::new(code).decode_for(self.platform))
+    }
+
+    fn size(&self, instruction: &Instruction) -> usize {
+        if instruction.is_long() { 4 } else { 2 }
+    }
+
+    fn label(&mut self, addr: Address, kind: &str) {
+        if addr >= self.origin && addr < self.end() {
+            self.labels.entry(addr).or_insert_with(|| format!("{}_{:x}", kind, addr));
+        }
+    }
+
+    /// Walks every path reachable from the origin, recording each
+    /// instruction met and labelling the addresses it refers to.
+    fn trace(&mut self) {
+        use cpu::instruction::Instruction::*;
+
+        let origin = self.origin;
+        self.labels.insert(origin, "main".to_string());
+        let mut pending = vec![origin];
+        while let Some(addr) = pending.pop() {
+            if self.code.contains_key(&addr) { continue; }
+            let instruction = match self.decode(addr) {
+                Some(Sys(_)) | Some(Unknown(_)) | None => continue,
+                Some(instruction) => instruction
+            };
+            let size = self.size(&instruction);
+            if addr + size > self.end() { continue; }
+            self.code.insert(addr, instruction);
+
+            let next = addr + size;
+            match instruction {
+                Jump(target) => {
+                    self.label(target, "label");
+                    pending.push(target);
+                },
+                Call(target) => {
+                    self.label(target, "sub");
+                    pending.push(target);
+                    pending.push(next);
+                },
+                Return | Exit => {},
+                JumpV0(target) => self.label(target, "table"),
+                LoadI(target) => {
+                    self.label(target, "data");
+                    pending.push(next);
+                },
+                LoadILong => {
+                    if let Some(target) = self.word(addr + 2) { self.label(target as Address, "data"); }
+                    pending.push(next);
+                },
+                SkipEqualByte(..) | SkipNotEqualByte(..) | SkipEqual(..) | SkipNotEqual(..)
+                    | SkipKeyPressed(_) | SkipKeyNotPressed(_)
+                    | SkipSecondKeyPressed(_) | SkipSecondKeyNotPressed(_) => {
+                    let skipped = self.decode(next).map_or(2, |instruction| self.size(&instruction));
+                    pending.push(next);
+                    pending.push(next + skipped);
+                },
+                _ => pending.push(next)
+            }
+        }
+    }
+
+    /// Lays the ROM out as labels, instructions and runs of data. An
+    /// instruction overlapping the one before it is written as data.
+    fn lines(&self) -> Vec<Line> {
+        let mut lines = Vec::new();
+        let mut addr = self.origin;
+        while addr < self.end() {
+            if self.labels.contains_key(&addr) { lines.push(Line::Label(addr)); }
+            if let Some(instruction) = self.code.get(&addr) {
+                lines.push(Line::Code(addr, *instruction));
+                addr += self.size(instruction);
+                continue;
+            }
+            let mut len = 1;
+            while len < BYTES_PER_LINE && addr + len < self.end()
+                && !self.labels.contains_key(&(addr + len)) && !self.code.contains_key(&(addr + len)) {
+                len += 1;
+            }
+            lines.push(Line::Data(addr, len));
+            addr += len;
+        }
+        lines
+    }
+
+    fn listing(&self) -> String {
+        let lines = self.lines();
+        let placed: BTreeMap<Address, &str> = lines.iter()
+            .filter_map(|line| match *line {
+                Line::Label(addr) => Some((addr, self.labels[&addr].as_str())),
+                _ => None
+            })
+            .collect();
+        let target = |addr: Address| -> String {
+            placed.get(&addr).map_or_else(|| format!("0x{:X}", addr), |name| name.to_string())
+        };
+
+        let mut out = String::new();
+        if self.origin != 0x200 { writeln!(out, ":org 0x{:X}", self.origin).unwrap(); }
+        for line in &lines {
+            match *line {
+                Line::Label(addr) => writeln!(out, ": {}", self.labels[&addr]).unwrap(),
+                Line::Code(addr, instruction) => {
+                    match octo(&instruction, self.word(addr + 2), &target) {
+                        Some(text) => writeln!(out, "  {}", text).unwrap(),
+                        None => {
+                            let code = instruction.encode().code();
+                            writeln!(out, "  0x{:02X} 0x{:02X} # {}", code >> 8, code & 0xFF, instruction).unwrap()
+                        }
+                    }
+                },
+                Line::Data(addr, len) => {
+                    let offset = addr - self.origin;
+                    let bytes: Vec<String> = self.rom[offset..offset + len].iter()
+                        .map(|byte| format!("0x{:02X}", byte))
+                        .collect();
+                    writeln!(out, "  {}", bytes.join(" ")).unwrap()
+                }
+            }
+        }
+        out
+    }
+}
+
+/// The Octo statement for `instruction`, or `None` for instructions Octo
+/// has no syntax for. `long` is the word after it, for `i := long`.
+fn octo<F>(instruction: &Instruction, long: Option<u16>, target: &F) -> Option<String> where F: Fn(Address) -> String {
+    use cpu::instruction::Instruction::*;
+
+    let text = match *instruction {
+        ClearDisplay => "clear".to_string(),
+        Return => "return".to_string(),
+        ScrollDown(n) => format!("scroll-down {}", n),
+        ScrollUp(n) => format!("scroll-up {}", n),
+        ScrollRight => "scroll-right".to_string(),
+        ScrollLeft => "scroll-left".to_string(),
+        Exit => "exit".to_string(),
+        LowRes => "lores".to_string(),
+        HighRes => "hires".to_string(),
+        Jump(addr) => format!("jump {}", target(addr)),
+        Call(addr) => format!(":call {}", target(addr)),
+        SkipEqualByte(x, kk) => format!("if v{:x} != 0x{:02X} then", x, kk),
+        SkipNotEqualByte(x, kk) => format!("if v{:x} == 0x{:02X} then", x, kk),
+        SkipEqual(x, y) => format!("if v{:x} != v{:x} then", x, y),
+        SaveRange(x, y) => format!("save v{:x} - v{:x}", x, y),
+        LoadRange(x, y) => format!("load v{:x} - v{:x}", x, y),
+        LoadByte(x, kk) => format!("v{:x} := 0x{:02X}", x, kk),
+        AddByte(x, kk) => format!("v{:x} += 0x{:02X}", x, kk),
+        Load(x, y) => format!("v{:x} := v{:x}", x, y),
+        Or(x, y) => format!("v{:x} |= v{:x}", x, y),
+        And(x, y) => format!("v{:x} &= v{:x}", x, y),
+        Xor(x, y) => format!("v{:x} ^= v{:x}", x, y),
+        Add(x, y) => format!("v{:x} += v{:x}", x, y),
+        Sub(x, y) => format!("v{:x} -= v{:x}", x, y),
+        ShiftRight(x, y) => format!("v{:x} >>= v{:x}", x, y),
+        SubN(x, y) => format!("v{:x} =- v{:x}", x, y),
+        ShiftLeft(x, y) => format!("v{:x} <<= v{:x}", x, y),
+        SkipNotEqual(x, y) => format!("if v{:x} == v{:x} then", x, y),
+        LoadI(addr) => format!("i := {}", target(addr)),
+        JumpV0(addr) => format!("jump0 {}", target(addr)),
+        Random(x, kk) => format!("v{:x} := random 0x{:02X}", x, kk),
+        Draw(x, y, n) => format!("sprite v{:x} v{:x} {}", x, y, n),
+        SkipKeyPressed(x) => format!("if v{:x} -key then", x),
+        SkipKeyNotPressed(x) => format!("if v{:x} key then", x),
+        LoadILong => format!("i := long {}", target(long? as Address)),
+        SelectPlanes(n) => format!("plane {}", n),
+        LoadAudio => "audio".to_string(),
+        LoadDelay(x) => format!("v{:x} := delay", x),
+        WaitKey(x) => format!("v{:x} := key", x),
+        SetDelay(x) => format!("delay := v{:x}", x),
+        SetSound(x) => format!("buzzer := v{:x}", x),
+        AddI(x) => format!("i += v{:x}", x),
+        LoadFont(x) => format!("i := hex v{:x}", x),
+        LoadBigFont(x) => format!("i := bighex v{:x}", x),
+        StoreBcd(x) => format!("bcd v{:x}", x),
+        SetPitch(x) => format!("pitch := v{:x}", x),
+        StoreRegisters(x) => format!("save v{:x}", x),
+        LoadRegisters(x) => format!("load v{:x}", x),
+        StoreFlags(x) => format!("saveflags v{:x}", x),
+        LoadFlags(x) => format!("loadflags v{:x}", x),
+        Sys(_) | CycleBackground | AddNibbles(..) | SetColour(..) | SkipSecondKeyPressed(_)
+            | SkipSecondKeyNotPressed(_) | OutputPort(_) | InputPort(_) | Unknown(_) => return None
+    };
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use load_rom;
+
+    #[test]
+    fn disassemble_ibm_logo() {
+        let rom = load_rom("rom", "ibm.ch8").unwrap();
+        let listing = disassemble(&rom, Platform::Chip8);
+        let lines: Vec<&str> = listing.lines().collect();
+
+        assert_eq!(": main", lines[0]);
+        assert_eq!("  clear", lines[1]);
+        assert_eq!("  i := data_22a", lines[2]);
+        assert!(lines.contains(&"  sprite v0 v1 15"));
+        assert!(lines.contains(&": label_228"));
+        assert!(lines.contains(&"  jump label_228"));
+        assert!(lines.contains(&": data_22a"));
+        assert!(lines.contains(&"  0xFF 0x00 0xFF 0x00 0x3C 0x00 0x3C 0x00"));
+    }
+
+    #[test]
+    fn follows_calls_and_skips() {
+        let rom = vec![
+            0x40, 0x01, // if v0 == 0x01 then
+            0x22, 0x08, // :call sub_208
+            0x12, 0x04, // jump label_204
+            0xAB, 0xCD, // unreachable
+            0x00, 0xEE  // return
+        ];
+        let listing = disassemble(&rom, Platform::Chip8);
+
+        assert_eq!(": main\n  if v0 == 0x01 then\n  :call sub_208\n: label_204\n  jump label_204\n  0xAB 0xCD\n: sub_208\n  return\n", listing);
+    }
+
+    #[test]
+    fn data_stops_at_unknown_opcode() {
+        let listing = disassemble(&[0x00, 0xFF, 0x12, 0x00], Platform::Chip8);
+        assert_eq!(": main\n  0x00 0xFF 0x12 0x00\n", listing);
+
+        let listing = disassemble(&[0x00, 0xFF, 0x12, 0x00], Platform::SuperChip);
+        assert_eq!(": main\n  hires\n  jump main\n", listing);
+    }
+
+    #[test]
+    fn long_load_and_skip_over_it() {
+        let rom = vec![0x30, 0x00, 0xF0, 0x00, 0x02, 0x08, 0x00, 0xFD, 0x01];
+        let listing = disassemble(&rom, Platform::XoChip);
+
+        assert_eq!(": main\n  if v0 != 0x00 then\n  i := long data_208\n  exit\n: data_208\n  0x01\n", listing);
+    }
+
+    #[test]
+    fn instructions_without_octo_syntax_are_bytes() {
+        let listing = disassemble(&[0xB1, 0x23, 0x13, 0x00], Platform::Chip8X);
+        assert_eq!(":org 0x300\n: main\n  0xB1 0x23 # COL V1, V2, 3\n  jump main\n", listing);
+    }
+
+    #[test]
+    fn jump_outside_rom_is_literal() {
+        assert_eq!(": main\n  jump 0x400\n", disassemble(&[0x14, 0x00], Platform::Chip8));
+    }
+}
@@ -0,0 +1,2 @@
+pub mod assembler;
+pub mod disassembler;
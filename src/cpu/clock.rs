@@ -0,0 +1,177 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+pub const FRAME_RATE: u32 = 60;
+pub const DEFAULT_INSTRUCTIONS_PER_FRAME: u32 = 10;
+
+/// Source of the 60 Hz frames that count down the delay and sound timers
+/// and release DXYN under the display wait quirk.
+pub trait Clock: fmt::Debug {
+    /// Called after every instruction. Returns the number of frames that
+    /// have started since the previous call.
+    fn tick(&mut self) -> u32;
+
+    /// How far the clock is into the current frame. Setting it back with
+    /// `set_progress` carries on from the same point, which is how save
+    /// states keep frame timing. Clocks that follow real time have none.
+    fn progress(&self) -> u32 {
+        0
+    }
+
+    fn set_progress(&mut self, _progress: u32) {}
+}
+
+/// Starts a frame every `instructions_per_frame` instructions, so a run
+/// depends only on the program and its input.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InstructionClock {
+    instructions_per_frame: u32,
+    instructions: u32
+}
+
+impl InstructionClock {
+    pub fn new(instructions_per_frame: u32) -> InstructionClock {
+        InstructionClock {
+            instructions_per_frame: instructions_per_frame.max(1),
+            instructions: 0
+        }
+    }
+
+    pub fn instructions_per_frame(&self) -> u32 {
+        self.instructions_per_frame
+    }
+}
+
+impl Default for InstructionClock {
+    fn default() -> InstructionClock {
+        InstructionClock::new(DEFAULT_INSTRUCTIONS_PER_FRAME)
+    }
+}
+
+impl Clock for InstructionClock {
+    fn tick(&mut self) -> u32 {
+        self.instructions += 1;
+        if self.instructions < self.instructions_per_frame { return 0; }
+
+        self.instructions = 0;
+        1
+    }
+
+    fn progress(&self) -> u32 {
+        self.instructions
+    }
+
+    fn set_progress(&mut self, progress: u32) {
+        self.instructions = progress % self.instructions_per_frame;
+    }
+}
+
+/// Starts frames as real time passes, for playing at the original speed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WallClock {
+    start: Instant,
+    frames: u128
+}
+
+impl WallClock {
+    pub fn new() -> WallClock {
+        WallClock::since(Instant::now())
+    }
+
+    pub fn since(start: Instant) -> WallClock {
+        WallClock {
+            start,
+            frames: 0
+        }
+    }
+
+    fn frames_at(&self, elapsed: Duration) -> u128 {
+        elapsed.as_nanos() * FRAME_RATE as u128 / Duration::from_secs(1).as_nanos()
+    }
+}
+
+impl Default for WallClock {
+    fn default() -> WallClock {
+        WallClock::new()
+    }
+}
+
+impl Clock for WallClock {
+    fn tick(&mut self) -> u32 {
+        let frames = self.frames_at(self.start.elapsed());
+        let started = frames - self.frames;
+        self.frames = frames;
+        started.min(u32::MAX as u128) as u32
+    }
+}
+
+/// Never starts a frame on its own. For callers that mark frames
+/// themselves with `Cpu::end_frame`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExternalClock;
+
+impl Clock for ExternalClock {
+    fn tick(&mut self) -> u32 {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instruction_clock_starts_frame_every_n_instructions() {
+        let mut c = InstructionClock::new(3);
+
+        assert_eq!(0, c.tick());
+        assert_eq!(0, c.tick());
+        assert_eq!(1, c.tick());
+        assert_eq!(0, c.tick());
+        assert_eq!(0, c.tick());
+        assert_eq!(1, c.tick());
+    }
+
+    #[test]
+    fn instruction_clock_resumes_from_progress() {
+        let mut c = InstructionClock::new(3);
+        c.tick();
+        c.tick();
+
+        let mut other = InstructionClock::new(3);
+        other.set_progress(c.progress());
+        assert_eq!(1, other.tick());
+    }
+
+    #[test]
+    fn instruction_clock_needs_at_least_one_instruction() {
+        let mut c = InstructionClock::new(0);
+
+        assert_eq!(1, c.instructions_per_frame());
+        assert_eq!(1, c.tick());
+    }
+
+    #[test]
+    fn external_clock_never_starts_frames() {
+        let mut c = ExternalClock;
+        assert_eq!(0, c.tick());
+    }
+
+    #[test]
+    fn wall_clock_counts_frames_past_one_second() {
+        let mut c = WallClock::since(Instant::now() - Duration::from_millis(2500));
+
+        let frames = c.tick();
+        assert!((150..155).contains(&frames), "expected 150 frames, got {}", frames);
+        assert!(c.tick() < 5);
+    }
+
+    #[test]
+    fn wall_clock_frames_at_rate() {
+        let c = WallClock::new();
+
+        assert_eq!(0, c.frames_at(Duration::from_millis(16)));
+        assert_eq!(1, c.frames_at(Duration::from_millis(17)));
+        assert_eq!(600, c.frames_at(Duration::from_secs(10)));
+    }
+}
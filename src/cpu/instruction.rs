@@ -0,0 +1,371 @@
+use std::fmt;
+
+use cpu::Register;
+use cpu::opcode::Opcode;
+use cpu::platform::Platform;
+use {Address, Byte};
+
+/// A decoded instruction. Every 16-bit word decodes to exactly one
+/// variant, with words that are not instructions kept as `Unknown`, so
+/// decoding and encoding round-trip.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Instruction {
+    /// 0NNN, a machine code routine on the original hardware. 0000 is
+    /// treated as a no-op.
+    Sys(Address),
+    ClearDisplay,
+    Return,
+    ScrollDown(Byte),
+    ScrollUp(Byte),
+    ScrollRight,
+    ScrollLeft,
+    Exit,
+    LowRes,
+    HighRes,
+    CycleBackground,
+    Jump(Address),
+    Call(Address),
+    SkipEqualByte(Register, Byte),
+    SkipNotEqualByte(Register, Byte),
+    SkipEqual(Register, Register),
+    AddNibbles(Register, Register),
+    SaveRange(Register, Register),
+    LoadRange(Register, Register),
+    LoadByte(Register, Byte),
+    AddByte(Register, Byte),
+    Load(Register, Register),
+    Or(Register, Register),
+    And(Register, Register),
+    Xor(Register, Register),
+    Add(Register, Register),
+    Sub(Register, Register),
+    ShiftRight(Register, Register),
+    SubN(Register, Register),
+    ShiftLeft(Register, Register),
+    SkipNotEqual(Register, Register),
+    LoadI(Address),
+    JumpV0(Address),
+    SetColour(Register, Register, Byte),
+    Random(Register, Byte),
+    Draw(Register, Register, Byte),
+    SkipKeyPressed(Register),
+    SkipKeyNotPressed(Register),
+    SkipSecondKeyPressed(Register),
+    SkipSecondKeyNotPressed(Register),
+    /// F000, followed by the 16-bit address in the next word.
+    LoadILong,
+    SelectPlanes(Byte),
+    LoadAudio,
+    LoadDelay(Register),
+    WaitKey(Register),
+    SetDelay(Register),
+    SetSound(Register),
+    AddI(Register),
+    LoadFont(Register),
+    LoadBigFont(Register),
+    StoreBcd(Register),
+    SetPitch(Register),
+    StoreRegisters(Register),
+    LoadRegisters(Register),
+    StoreFlags(Register),
+    LoadFlags(Register),
+    OutputPort(Register),
+    InputPort(Register),
+    Unknown(u16)
+}
+
+impl Opcode {
+    /// Decodes the word as the union of every supported dialect. BNNN is
+    /// read as `JumpV0`; use `decode_for` to get CHIP-8X's `SetColour`.
+    pub fn decode(&self) -> Instruction {
+        use self::Instruction::*;
+
+        let (x, y, n, kk, nnn) = (self.x(), self.y(), self.k() as Byte, self.kk(), self.nnn());
+        match (self.first_hex_digit(), x, y, n) {
+            (0x0, 0x0, 0xE, 0x0) => ClearDisplay,
+            (0x0, 0x0, 0xE, 0xE) => Return,
+            (0x0, 0x0, 0xC, n) => ScrollDown(n),
+            (0x0, 0x0, 0xD, n) => ScrollUp(n),
+            (0x0, 0x0, 0xF, 0xB) => ScrollRight,
+            (0x0, 0x0, 0xF, 0xC) => ScrollLeft,
+            (0x0, 0x0, 0xF, 0xD) => Exit,
+            (0x0, 0x0, 0xF, 0xE) => LowRes,
+            (0x0, 0x0, 0xF, 0xF) => HighRes,
+            (0x0, 0x2, 0xA, 0x0) => CycleBackground,
+            (0x0, _, _, _) => Sys(nnn),
+            (0x1, _, _, _) => Jump(nnn),
+            (0x2, _, _, _) => Call(nnn),
+            (0x3, _, _, _) => SkipEqualByte(x, kk),
+            (0x4, _, _, _) => SkipNotEqualByte(x, kk),
+            (0x5, _, _, 0x0) => SkipEqual(x, y),
+            (0x5, _, _, 0x1) => AddNibbles(x, y),
+            (0x5, _, _, 0x2) => SaveRange(x, y),
+            (0x5, _, _, 0x3) => LoadRange(x, y),
+            (0x6, _, _, _) => LoadByte(x, kk),
+            (0x7, _, _, _) => AddByte(x, kk),
+            (0x8, _, _, 0x0) => Load(x, y),
+            (0x8, _, _, 0x1) => Or(x, y),
+            (0x8, _, _, 0x2) => And(x, y),
+            (0x8, _, _, 0x3) => Xor(x, y),
+            (0x8, _, _, 0x4) => Add(x, y),
+            (0x8, _, _, 0x5) => Sub(x, y),
+            (0x8, _, _, 0x6) => ShiftRight(x, y),
+            (0x8, _, _, 0x7) => SubN(x, y),
+            (0x8, _, _, 0xE) => ShiftLeft(x, y),
+            (0x9, _, _, 0x0) => SkipNotEqual(x, y),
+            (0xA, _, _, _) => LoadI(nnn),
+            (0xB, _, _, _) => JumpV0(nnn),
+            (0xC, _, _, _) => Random(x, kk),
+            (0xD, _, _, n) => Draw(x, y, n),
+            (0xE, _, 0x9, 0xE) => SkipKeyPressed(x),
+            (0xE, _, 0xA, 0x1) => SkipKeyNotPressed(x),
+            (0xE, _, 0xF, 0x2) => SkipSecondKeyPressed(x),
+            (0xE, _, 0xF, 0x5) => SkipSecondKeyNotPressed(x),
+            (0xF, 0x0, 0x0, 0x0) => LoadILong,
+            (0xF, _, 0x0, 0x1) => SelectPlanes(x as Byte),
+            (0xF, 0x0, 0x0, 0x2) => LoadAudio,
+            (0xF, _, 0x0, 0x7) => LoadDelay(x),
+            (0xF, _, 0x0, 0xA) => WaitKey(x),
+            (0xF, _, 0x1, 0x5) => SetDelay(x),
+            (0xF, _, 0x1, 0x8) => SetSound(x),
+            (0xF, _, 0x1, 0xE) => AddI(x),
+            (0xF, _, 0x2, 0x9) => LoadFont(x),
+            (0xF, _, 0x3, 0x0) => LoadBigFont(x),
+            (0xF, _, 0x3, 0x3) => StoreBcd(x),
+            (0xF, _, 0x3, 0xA) => SetPitch(x),
+            (0xF, _, 0x5, 0x5) => StoreRegisters(x),
+            (0xF, _, 0x6, 0x5) => LoadRegisters(x),
+            (0xF, _, 0x7, 0x5) => StoreFlags(x),
+            (0xF, _, 0x8, 0x5) => LoadFlags(x),
+            (0xF, _, 0xF, 0x8) => OutputPort(x),
+            (0xF, _, 0xF, 0xB) => InputPort(x),
+            _ => Unknown(self.code())
+        }
+    }
+
+    /// Decodes the word as `platform` executes it, giving `Unknown` for
+    /// instructions the platform lacks.
+    pub fn decode_for(&self, platform: Platform) -> Instruction {
+        let instruction = match self.decode() {
+            Instruction::JumpV0(_) if platform.chip8x() => {
+                Instruction::SetColour(self.x(), self.y(), self.k() as Byte)
+            },
+            instruction => instruction
+        };
+        if instruction.available_on(platform) { instruction } else { Instruction::Unknown(self.code()) }
+    }
+}
+
+impl Instruction {
+    pub fn encode(&self) -> Opcode {
+        use self::Instruction::*;
+
+        fn xkk(prefix: u16, x: Register, kk: Byte) -> u16 {
+            prefix << 12 | (x as u16 & 0xF) << 8 | kk as u16
+        }
+        fn xyn(prefix: u16, x: Register, y: Register, n: Byte) -> u16 {
+            prefix << 12 | (x as u16 & 0xF) << 8 | (y as u16 & 0xF) << 4 | (n as u16 & 0xF)
+        }
+        fn nnn(prefix: u16, addr: Address) -> u16 {
+            prefix << 12 | (addr as u16 & 0xFFF)
+        }
+
+        let code = match *self {
+            Sys(addr) => nnn(0x0, addr),
+            ClearDisplay => 0x00E0,
+            Return => 0x00EE,
+            ScrollDown(n) => 0x00C0 | (n as u16 & 0xF),
+            ScrollUp(n) => 0x00D0 | (n as u16 & 0xF),
+            ScrollRight => 0x00FB,
+            ScrollLeft => 0x00FC,
+            Exit => 0x00FD,
+            LowRes => 0x00FE,
+            HighRes => 0x00FF,
+            CycleBackground => 0x02A0,
+            Jump(addr) => nnn(0x1, addr),
+            Call(addr) => nnn(0x2, addr),
+            SkipEqualByte(x, kk) => xkk(0x3, x, kk),
+            SkipNotEqualByte(x, kk) => xkk(0x4, x, kk),
+            SkipEqual(x, y) => xyn(0x5, x, y, 0x0),
+            AddNibbles(x, y) => xyn(0x5, x, y, 0x1),
+            SaveRange(x, y) => xyn(0x5, x, y, 0x2),
+            LoadRange(x, y) => xyn(0x5, x, y, 0x3),
+            LoadByte(x, kk) => xkk(0x6, x, kk),
+            AddByte(x, kk) => xkk(0x7, x, kk),
+            Load(x, y) => xyn(0x8, x, y, 0x0),
+            Or(x, y) => xyn(0x8, x, y, 0x1),
+            And(x, y) => xyn(0x8, x, y, 0x2),
+            Xor(x, y) => xyn(0x8, x, y, 0x3),
+            Add(x, y) => xyn(0x8, x, y, 0x4),
+            Sub(x, y) => xyn(0x8, x, y, 0x5),
+            ShiftRight(x, y) => xyn(0x8, x, y, 0x6),
+            SubN(x, y) => xyn(0x8, x, y, 0x7),
+            ShiftLeft(x, y) => xyn(0x8, x, y, 0xE),
+            SkipNotEqual(x, y) => xyn(0x9, x, y, 0x0),
+            LoadI(addr) => nnn(0xA, addr),
+            JumpV0(addr) => nnn(0xB, addr),
+            SetColour(x, y, n) => xyn(0xB, x, y, n),
+            Random(x, kk) => xkk(0xC, x, kk),
+            Draw(x, y, n) => xyn(0xD, x, y, n),
+            SkipKeyPressed(x) => xkk(0xE, x, 0x9E),
+            SkipKeyNotPressed(x) => xkk(0xE, x, 0xA1),
+            SkipSecondKeyPressed(x) => xkk(0xE, x, 0xF2),
+            SkipSecondKeyNotPressed(x) => xkk(0xE, x, 0xF5),
+            LoadILong => 0xF000,
+            SelectPlanes(n) => xkk(0xF, n as Register, 0x01),
+            LoadAudio => 0xF002,
+            LoadDelay(x) => xkk(0xF, x, 0x07),
+            WaitKey(x) => xkk(0xF, x, 0x0A),
+            SetDelay(x) => xkk(0xF, x, 0x15),
+            SetSound(x) => xkk(0xF, x, 0x18),
+            AddI(x) => xkk(0xF, x, 0x1E),
+            LoadFont(x) => xkk(0xF, x, 0x29),
+            LoadBigFont(x) => xkk(0xF, x, 0x30),
+            StoreBcd(x) => xkk(0xF, x, 0x33),
+            SetPitch(x) => xkk(0xF, x, 0x3A),
+            StoreRegisters(x) => xkk(0xF, x, 0x55),
+            LoadRegisters(x) => xkk(0xF, x, 0x65),
+            StoreFlags(x) => xkk(0xF, x, 0x75),
+            LoadFlags(x) => xkk(0xF, x, 0x85),
+            OutputPort(x) => xkk(0xF, x, 0xF8),
+            InputPort(x) => xkk(0xF, x, 0xFB),
+            Unknown(code) => code
+        };
+        Opcode::new(code)
+    }
+
+    pub fn available_on(&self, platform: Platform) -> bool {
+        use self::Instruction::*;
+
+        match *self {
+            ScrollDown(_) | ScrollRight | ScrollLeft | Exit | LowRes | HighRes
+                | LoadBigFont(_) | StoreFlags(_) | LoadFlags(_) => platform.super_chip(),
+            ScrollUp(_) | SaveRange(..) | LoadRange(..) | LoadILong | SelectPlanes(_)
+                | LoadAudio | SetPitch(_) => platform.xo_chip(),
+            CycleBackground | AddNibbles(..) | SetColour(..) | SkipSecondKeyPressed(_)
+                | SkipSecondKeyNotPressed(_) | OutputPort(_) | InputPort(_) => platform.chip8x(),
+            JumpV0(_) => !platform.chip8x(),
+            _ => true
+        }
+    }
+
+    /// Whether the instruction takes a second word, so that skipping it
+    /// must skip four bytes.
+    pub fn is_long(&self) -> bool {
+        *self == Instruction::LoadILong
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Instruction::*;
+
+        match *self {
+            Sys(addr) => write!(f, "SYS {:03x}", addr),
+            ClearDisplay => write!(f, "CLS"),
+            Return => write!(f, "RET"),
+            ScrollDown(n) => write!(f, "SCD {:x}", n),
+            ScrollUp(n) => write!(f, "SCU {:x}", n),
+            ScrollRight => write!(f, "SCR"),
+            ScrollLeft => write!(f, "SCL"),
+            Exit => write!(f, "EXIT"),
+            LowRes => write!(f, "LOW"),
+            HighRes => write!(f, "HIGH"),
+            CycleBackground => write!(f, "BGC"),
+            Jump(addr) => write!(f, "JP {:03x}", addr),
+            Call(addr) => write!(f, "CALL {:03x}", addr),
+            SkipEqualByte(x, kk) => write!(f, "SE V{:X}, {:02x}", x, kk),
+            SkipNotEqualByte(x, kk) => write!(f, "SNE V{:X}, {:02x}", x, kk),
+            SkipEqual(x, y) => write!(f, "SE V{:X}, V{:X}", x, y),
+            AddNibbles(x, y) => write!(f, "ADD V{:X}, V{:X} NIBBLES", x, y),
+            SaveRange(x, y) => write!(f, "SAVE V{:X} - V{:X}", x, y),
+            LoadRange(x, y) => write!(f, "LOAD V{:X} - V{:X}", x, y),
+            LoadByte(x, kk) => write!(f, "LD V{:X}, {:02x}", x, kk),
+            AddByte(x, kk) => write!(f, "ADD V{:X}, {:02x}", x, kk),
+            Load(x, y) => write!(f, "LD V{:X}, V{:X}", x, y),
+            Or(x, y) => write!(f, "OR V{:X}, V{:X}", x, y),
+            And(x, y) => write!(f, "AND V{:X}, V{:X}", x, y),
+            Xor(x, y) => write!(f, "XOR V{:X}, V{:X}", x, y),
+            Add(x, y) => write!(f, "ADD V{:X}, V{:X}", x, y),
+            Sub(x, y) => write!(f, "SUB V{:X}, V{:X}", x, y),
+            ShiftRight(x, y) => write!(f, "SHR V{:X}, V{:X}", x, y),
+            SubN(x, y) => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            ShiftLeft(x, y) => write!(f, "SHL V{:X}, V{:X}", x, y),
+            SkipNotEqual(x, y) => write!(f, "SNE V{:X}, V{:X}", x, y),
+            LoadI(addr) => write!(f, "LD I, {:03x}", addr),
+            JumpV0(addr) => write!(f, "JP V0, {:03x}", addr),
+            SetColour(x, y, n) => write!(f, "COL V{:X}, V{:X}, {:x}", x, y, n),
+            Random(x, kk) => write!(f, "RND V{:X}, {:02x}", x, kk),
+            Draw(x, y, n) => write!(f, "DRW V{:X}, V{:X}, {:x}", x, y, n),
+            SkipKeyPressed(x) => write!(f, "SKP V{:X}", x),
+            SkipKeyNotPressed(x) => write!(f, "SKNP V{:X}", x),
+            SkipSecondKeyPressed(x) => write!(f, "SKP2 V{:X}", x),
+            SkipSecondKeyNotPressed(x) => write!(f, "SKNP2 V{:X}", x),
+            LoadILong => write!(f, "LD I, LONG"),
+            SelectPlanes(n) => write!(f, "PLANE {:x}", n),
+            LoadAudio => write!(f, "AUDIO"),
+            LoadDelay(x) => write!(f, "LD V{:X}, DT", x),
+            WaitKey(x) => write!(f, "LD V{:X}, K", x),
+            SetDelay(x) => write!(f, "LD DT, V{:X}", x),
+            SetSound(x) => write!(f, "LD ST, V{:X}", x),
+            AddI(x) => write!(f, "ADD I, V{:X}", x),
+            LoadFont(x) => write!(f, "LD F, V{:X}", x),
+            LoadBigFont(x) => write!(f, "LD HF, V{:X}", x),
+            StoreBcd(x) => write!(f, "LD B, V{:X}", x),
+            SetPitch(x) => write!(f, "PITCH V{:X}", x),
+            StoreRegisters(x) => write!(f, "LD [I], V{:X}", x),
+            LoadRegisters(x) => write!(f, "LD V{:X}, [I]", x),
+            StoreFlags(x) => write!(f, "LD R, V{:X}", x),
+            LoadFlags(x) => write!(f, "LD V{:X}, R", x),
+            OutputPort(x) => write!(f, "OUT V{:X}", x),
+            InputPort(x) => write!(f, "IN V{:X}", x),
+            Unknown(code) => write!(f, "DW {:04x}", code)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLATFORMS: [Platform; 4] = [Platform::Chip8, Platform::SuperChip, Platform::XoChip, Platform::Chip8X];
+
+    #[test]
+    fn decode_encode_round_trips_every_word() {
+        for code in 0x0..=0xFFFF {
+            let opcode = Opcode::new(code);
+            assert_eq!(code, opcode.decode().encode().code(), "{:04x}", code);
+            for platform in PLATFORMS.iter() {
+                assert_eq!(code, opcode.decode_for(*platform).encode().code(), "{:04x} on {:?}", code, platform);
+            }
+        }
+    }
+
+    #[test]
+    fn decode_typed_operands() {
+        assert_eq!(Instruction::Draw(0x1, 0x2, 0x3), Opcode::new(0xD123).decode());
+        assert_eq!(Instruction::LoadI(0x2F0), Opcode::new(0xA2F0).decode());
+        assert_eq!(Instruction::SkipEqualByte(0xA, 0x42), Opcode::new(0x3A42).decode());
+        assert_eq!(Instruction::SelectPlanes(0x3), Opcode::new(0xF301).decode());
+        assert_eq!(Instruction::Unknown(0x5124), Opcode::new(0x5124).decode());
+    }
+
+    #[test]
+    fn decode_for_depends_on_platform() {
+        let opcode = Opcode::new(0xB123);
+        assert_eq!(Instruction::JumpV0(0x123), opcode.decode_for(Platform::Chip8));
+        assert_eq!(Instruction::SetColour(0x1, 0x2, 0x3), opcode.decode_for(Platform::Chip8X));
+
+        let opcode = Opcode::new(0x00C1);
+        assert_eq!(Instruction::Unknown(0x00C1), opcode.decode_for(Platform::Chip8));
+        assert_eq!(Instruction::ScrollDown(0x1), opcode.decode_for(Platform::SuperChip));
+    }
+
+    #[test]
+    fn display_mnemonics() {
+        assert_eq!("JP V0, 123", Instruction::JumpV0(0x123).to_string());
+        assert_eq!("COL V1, V2, 3", Instruction::SetColour(0x1, 0x2, 0x3).to_string());
+        assert_eq!("LD VA, 02", Instruction::LoadByte(0xA, 0x02).to_string());
+        assert_eq!("DW 00c1", Instruction::Unknown(0x00C1).to_string());
+    }
+}
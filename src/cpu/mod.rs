@@ -1,70 +1,137 @@
 const NUM_REGISTERS: usize = 16;
 
 const MAX_ADDR: Address = 0x1000;
+const XO_MAX_ADDR: Address = 0x10000;
+const STACK_SIZE: usize = 0x60;
 const FONT_RANGE: Range<Address> = 0x0..0x200;
+const BIG_FONT_ADDR: Address = 0x50;
 const ROM_RANGE: Range<Address> = 0x200..0xFA0;
+const CHIP8X_ROM_START: Address = 0x300;
 const STACK_RANGE: Range<Address> = 0xFA0..MAX_ADDR;
 
-extern crate rand;
-
-mod opcode;
+pub mod clock;
+pub mod instruction;
+pub mod opcode;
 mod ops;
+pub mod platform;
 mod pointer;
+pub mod quirks;
+pub mod random;
+pub mod rewind;
+pub mod state;
 mod timer;
+pub mod trace;
+pub mod watch;
 
-use self::rand::Rng;
 use std::ops::Range;
 
+use cpu::clock::{Clock, InstructionClock};
 use cpu::ops::Operation;
 use cpu::opcode::Opcode;
+use cpu::platform::Platform;
 use cpu::pointer::Pointer;
+use cpu::quirks::{IndexIncrement, Quirks};
+use cpu::random::{OsRandom, Random};
 use cpu::timer::Timer;
+use cpu::trace::{TraceRecord, TraceSink};
+use cpu::watch::{Watchpoint, WatchHit};
+use error::{Error, Result};
 use memory::Memory;
-use output::{font, graphics};
+use input;
+use output::{font, graphics, sound};
+use output::colour::Colour;
 
 use {Address, Byte};
-type Register = usize;
+pub type Register = usize;
 
-#[derive(Debug, PartialEq)]
-pub struct Cpu<'a, G: 'a> where G: graphics::GraphicsOutput {
+#[derive(Debug)]
+pub struct Cpu<'a, G: 'a, I: 'a> where G: graphics::GraphicsOutput, I: input::Input {
     pub exit: bool,
     pub beep: bool,
+    /// Last byte written to the CHIP-8X output port by FXF8.
+    pub port_out: Byte,
+    /// Byte waiting on the CHIP-8X input port for FXFB.
+    pub port_in: Option<Byte>,
+    platform: Platform,
+    quirks: Quirks,
     pc: Pointer,
     sp: Pointer,
     i: Pointer,
     dt: Timer,
     st: Timer,
+    clock: Box<dyn Clock>,
+    random: Box<dyn Random>,
+    trace: Option<Box<dyn TraceSink>>,
+    writes: Option<Vec<(Address, Byte)>>,
+    watch_hits: Vec<WatchHit>,
     v: [Byte; NUM_REGISTERS],
+    rpl: [Byte; NUM_REGISTERS],
+    audio: sound::AudioPattern,
     memory: Memory<Byte>,
-    graphics: &'a mut G
+    key_wait: Option<Byte>,
+    vblank: bool,
+    vblank_wait: bool,
+    graphics: &'a mut G,
+    input: &'a mut I
 }
 
-impl<'a, G> Cpu<'a, G> where G: graphics::GraphicsOutput {
-    pub fn new(rom: &Vec<Byte>, graphics: &'a mut G) -> Cpu<'a, G> {
-        let mut memory = Memory::new(MAX_ADDR, 0x0);
-        memory.load(&font::FONT_SET, FONT_RANGE);
-        memory.load(&rom, ROM_RANGE);
+impl<'a, G, I> Cpu<'a, G, I> where G: graphics::GraphicsOutput, I: input::Input {
+    pub fn new(rom: &[Byte], graphics: &'a mut G, input: &'a mut I, platform: Platform, quirks: Quirks) -> Result<Cpu<'a, G, I>> {
+        let mut memory = Memory::new(platform.memory_size(), 0x0);
+        memory.load(&font::FONT_SET, FONT_RANGE)?;
+        memory.load(&font::BIG_FONT_SET, BIG_FONT_ADDR..FONT_RANGE.end)?;
+        memory.load(rom, platform.rom_range())?;
 
-        Cpu {
+        Ok(Cpu {
             exit: false,
-            beep: true,
-            pc: Pointer::new(ROM_RANGE),
-            sp: Pointer::new(STACK_RANGE),
-            i: Pointer::new(FONT_RANGE.start..ROM_RANGE.end),
-            dt: Timer::new(60, 60),
-            st: Timer::new(60, 60),
+            beep: false,
+            port_out: 0x0,
+            port_in: None,
+            platform,
+            quirks,
+            pc: Pointer::new(platform.rom_range()),
+            sp: Pointer::new(platform.stack_range()),
+            i: Pointer::new(platform.i_range()),
+            dt: Timer::new(0),
+            st: Timer::new(0),
+            clock: Box::new(InstructionClock::default()),
+            random: Box::new(OsRandom::new()),
+            trace: None,
+            writes: None,
+            watch_hits: Vec::new(),
             v: [0x0; NUM_REGISTERS],
+            rpl: [0x0; NUM_REGISTERS],
+            audio: sound::AudioPattern::new(),
             memory,
-            graphics
-        }
+            key_wait: None,
+            vblank: false,
+            vblank_wait: false,
+            graphics,
+            input
+        })
     }
 
-    pub fn step(&mut self) {
-        let opcode = self.fetch_opcode();
+    pub fn step(&mut self) -> Result<()> {
+        let pc = self.pc.current;
+        let opcode = Opcode::from_bytes((self.memory.fetch(pc), self.memory.fetch(pc + 1)));
         let op = self.operation(&opcode);
-        op(self, &opcode);
+        let tracing = self.trace.as_ref().is_some_and(|trace| trace.wants(pc, opcode.code()));
+        let (v_before, i_before) = (self.v, self.i.current);
+        if tracing { self.writes = Some(Vec::new()); }
+
+        let result = op(self, &opcode);
+        if tracing { self.trace_step(pc, &opcode, v_before, i_before); }
+        let hits = self.memory.take_hits().into_iter()
+            .map(|(addr, access, old, new)| WatchHit { pc, addr, access, old, new });
+        self.watch_hits.extend(hits);
+        result.map_err(|cause| Error::Fault {
+            pc,
+            opcode: opcode.code(),
+            cause: Box::new(cause)
+        })?;
 
         self.update_timers();
+        Ok(())
     }
 
     pub fn fetch_opcode(&self) -> Opcode {
@@ -73,84 +140,214 @@ impl<'a, G> Cpu<'a, G> where G: graphics::GraphicsOutput {
         Opcode::from_bytes(bytes)
     }
 
-    pub fn operation(&mut self, opcode: &Opcode) -> fn(&mut Cpu<'a, G>, &Opcode) {
-        match opcode.first_hex_digit() {
-            0x0 => {
-                match opcode.kk() {
-                    0x00 => Cpu::no_op,
-                    0xE0 => Cpu::clear_display,
-                    0xEE => Cpu::return_from_subroutine,
-                    _ => Cpu::unknown
-                }
-            },
-            0x1 => Cpu::jump_addr,
-            0x2 => Cpu::call_addr,
-            0x3 => Cpu::skip_equal_vx_byte,
-            0x4 => Cpu::skip_not_equal_vx_byte,
-            0x5 => Cpu::skip_equal_vx_vy,
-            0x6 => Cpu::load_vx_byte,
-            0x7 => Cpu::add_vx_byte,
-            0x8 => {
-                match opcode.k() {
-                    0x0 => Cpu::load_vx_vy,
-                    0x1 => Cpu::or_vx_vy,
-                    0x2 => Cpu::and_vx_vy,
-                    0x3 => Cpu::xor_vx_vy,
-                    0x4 => Cpu::add_vx_vy,
-                    0x5 => Cpu::sub_vx_vy,
-                    0x6 => Cpu::shr_vx_vy,
-                    0x7 => Cpu::subn_vx_vy,
-                    0xE => Cpu::shl_vx_vy,
-                    _ => Cpu::unknown
-                }
-            },
-            0x9 => Cpu::skip_not_equal_vx_vy,
-            0xA => Cpu::load_i_addr,
-            0xB => Cpu::jump_v0_addr,
-            0xC => Cpu::rand_vx_byte,
-            0xD => Cpu::draw_vx_vy_n,
-            0xE => {
-                match opcode.kk() {
-                    0x9E => Cpu::skip_key_pressed_vx,
-                    0xA1 => Cpu::skip_key_not_pressed_vx,
-                    _ => Cpu::unknown
-                }
-            },
-            0xF => {
-                match opcode.kk() {
-                    0x07 => Cpu::load_vx_dt,
-                    0x0A => Cpu::load_vx_key,
-                    0x15 => Cpu::load_dt_vx,
-                    0x18 => Cpu::load_st_vx,
-                    0x1E => Cpu::add_i_vx,
-                    0x29 => Cpu::load_i_vx_font,
-                    0x33 => Cpu::load_bcd_vx,
-                    0x55 => Cpu::load_through_vx,
-                    0x65 => Cpu::read_through_vx,
-                    _ => Cpu::unknown
-                }
-            },
-            _ => Cpu::unknown
+    pub fn operation(&mut self, opcode: &Opcode) -> fn(&mut Cpu<'a, G, I>, &Opcode) -> Result<()> {
+        use cpu::instruction::Instruction::*;
+
+        match opcode.decode_for(self.platform) {
+            Sys(0x000) => Cpu::no_op,
+            ClearDisplay => Cpu::clear_display,
+            Return => Cpu::return_from_subroutine,
+            ScrollDown(_) => Cpu::scroll_down_n,
+            ScrollUp(_) => Cpu::scroll_up_n,
+            ScrollRight => Cpu::scroll_right,
+            ScrollLeft => Cpu::scroll_left,
+            Exit => Cpu::exit_interpreter,
+            LowRes => Cpu::low_res,
+            HighRes => Cpu::high_res,
+            CycleBackground => Cpu::cycle_background,
+            Jump(_) => Cpu::jump_addr,
+            Call(_) => Cpu::call_addr,
+            SkipEqualByte(..) => Cpu::skip_equal_vx_byte,
+            SkipNotEqualByte(..) => Cpu::skip_not_equal_vx_byte,
+            SkipEqual(..) => Cpu::skip_equal_vx_vy,
+            AddNibbles(..) => Cpu::add_nibbles_vx_vy,
+            SaveRange(..) => Cpu::save_vx_vy,
+            LoadRange(..) => Cpu::read_vx_vy,
+            LoadByte(..) => Cpu::load_vx_byte,
+            AddByte(..) => Cpu::add_vx_byte,
+            Load(..) => Cpu::load_vx_vy,
+            Or(..) => Cpu::or_vx_vy,
+            And(..) => Cpu::and_vx_vy,
+            Xor(..) => Cpu::xor_vx_vy,
+            Add(..) => Cpu::add_vx_vy,
+            Sub(..) => Cpu::sub_vx_vy,
+            ShiftRight(..) => Cpu::shr_vx_vy,
+            SubN(..) => Cpu::subn_vx_vy,
+            ShiftLeft(..) => Cpu::shl_vx_vy,
+            SkipNotEqual(..) => Cpu::skip_not_equal_vx_vy,
+            LoadI(_) => Cpu::load_i_addr,
+            JumpV0(_) => Cpu::jump_v0_addr,
+            SetColour(..) => Cpu::colour_vx_vy_n,
+            Random(..) => Cpu::rand_vx_byte,
+            Draw(..) => Cpu::draw_vx_vy_n,
+            SkipKeyPressed(_) => Cpu::skip_key_pressed_vx,
+            SkipKeyNotPressed(_) => Cpu::skip_key_not_pressed_vx,
+            SkipSecondKeyPressed(_) => Cpu::skip_second_key_pressed_vx,
+            SkipSecondKeyNotPressed(_) => Cpu::skip_second_key_not_pressed_vx,
+            LoadILong => Cpu::load_i_long,
+            SelectPlanes(_) => Cpu::select_planes,
+            LoadAudio => Cpu::load_audio,
+            LoadDelay(_) => Cpu::load_vx_dt,
+            WaitKey(_) => Cpu::load_vx_key,
+            SetDelay(_) => Cpu::load_dt_vx,
+            SetSound(_) => Cpu::load_st_vx,
+            AddI(_) => Cpu::add_i_vx,
+            LoadFont(_) => Cpu::load_i_vx_font,
+            LoadBigFont(_) => Cpu::load_i_vx_big_font,
+            StoreBcd(_) => Cpu::load_bcd_vx,
+            SetPitch(_) => Cpu::load_pitch_vx,
+            StoreRegisters(_) => Cpu::load_through_vx,
+            LoadRegisters(_) => Cpu::read_through_vx,
+            StoreFlags(_) => Cpu::store_flags_vx,
+            LoadFlags(_) => Cpu::read_flags_vx,
+            OutputPort(_) => Cpu::output_port_vx,
+            InputPort(_) => Cpu::input_port_vx,
+            Sys(_) | Unknown(_) => Cpu::unknown
+        }
+    }
+
+    pub fn input(&self) -> &I {
+        self.input
+    }
+
+    pub fn input_mut(&mut self) -> &mut I {
+        self.input
+    }
+
+    pub fn audio(&self) -> &sound::AudioPattern {
+        &self.audio
+    }
+
+    pub fn audio_mut(&mut self) -> &mut sound::AudioPattern {
+        &mut self.audio
+    }
+
+    /// Replaces the clock that drives the timers. The default starts a
+    /// frame every `clock::DEFAULT_INSTRUCTIONS_PER_FRAME` instructions.
+    pub fn set_clock<C>(&mut self, clock: C) where C: 'static + Clock {
+        self.clock = Box::new(clock);
+    }
+
+    /// Sends a record of every instruction executed from now on to `sink`.
+    pub fn set_trace<S>(&mut self, sink: S) where S: 'static + TraceSink {
+        self.trace = Some(Box::new(sink));
+    }
+
+    /// Stops tracing, returning the sink that was in use.
+    pub fn take_trace(&mut self) -> Option<Box<dyn TraceSink>> {
+        self.trace.take()
+    }
+
+    /// Replaces the generator behind CXKK, which defaults to `OsRandom`.
+    pub fn set_random<R>(&mut self, random: R) where R: 'static + Random {
+        self.random = Box::new(random);
+    }
+
+    /// Starts a 60 Hz frame regardless of the clock, counting down the
+    /// timers and signalling vblank.
+    pub fn end_frame(&mut self) {
+        self.advance_frames(1);
+    }
+
+    pub fn graphics(&self) -> &G {
+        self.graphics
+    }
+
+    pub fn graphics_mut(&mut self) -> &mut G {
+        self.graphics
+    }
+
+    /// Signals the start of a 60 Hz frame, releasing a DXYN that is waiting
+    /// on the display wait quirk.
+    pub fn vblank(&mut self) {
+        self.vblank = true;
+    }
+
+    pub fn platform(&self) -> Platform {
+        self.platform
+    }
+
+    pub fn pc(&self) -> Address {
+        self.pc.current
+    }
+
+    pub fn set_pc(&mut self, addr: Address) -> Result<()> {
+        self.pc.set(addr)
+    }
+
+    /// Address of the newest stack entry, or the stack base when empty.
+    pub fn sp(&self) -> Address {
+        self.sp.current
+    }
+
+    /// Return addresses on the stack, innermost call first. Each is the
+    /// address of the CALL that pushed it.
+    pub fn call_stack(&self) -> Vec<Address> {
+        let base = self.platform.stack_range().start;
+        (base + 2..self.sp.current + 2).step_by(2).rev()
+            .map(|addr| (self.memory[addr] as Address) << 8 | self.memory[addr + 1] as Address)
+            .collect()
+    }
+
+    pub fn memory(&self) -> &[Byte] {
+        &self.memory
+    }
+
+    /// Writes memory on behalf of a debugger. These writes do not trigger
+    /// watchpoints.
+    pub fn write_memory(&mut self, addr: Address, bytes: &[Byte]) -> Result<()> {
+        self.check_memory_range(addr, bytes.len())?;
+        for (offset, byte) in bytes.iter().enumerate() {
+            self.memory[addr + offset] = *byte;
         }
+        Ok(())
+    }
+
+    /// Watches memory accessed by executing instructions, including fetches,
+    /// sprite and register loads, stores and the stack.
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        self.memory.watch(watchpoint);
+    }
+
+    /// Removes every watchpoint covering `addr`, returning whether there were any.
+    pub fn remove_watchpoint(&mut self, addr: Address) -> bool {
+        self.memory.unwatch(addr)
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        self.memory.watchpoints()
+    }
+
+    /// Watched accesses since the last `take_watch_hits`, oldest first.
+    pub fn watch_hits(&self) -> &[WatchHit] {
+        &self.watch_hits
+    }
+
+    pub fn take_watch_hits(&mut self) -> Vec<WatchHit> {
+        ::std::mem::take(&mut self.watch_hits)
     }
 
-    fn skip(&mut self) {
-        self.pc.move_forward();
+    fn skip(&mut self) -> Result<()> {
+        self.pc.move_forward()?;
+        if self.fetch_opcode().decode_for(self.platform).is_long() {
+            self.pc.move_forward()?;
+        }
+        Ok(())
     }
 
-    fn read_i(&self) -> Address {
+    pub fn read_i(&self) -> Address {
         self.i.current
     }
 
-    fn load_i(&mut self, addr: Address) {
-        self.i.set(addr);
+    pub fn load_i(&mut self, addr: Address) -> Result<()> {
+        self.i.set(addr)
     }
 
-    fn load_register(&mut self, register: Register, val: Byte) {
+    pub fn load_register(&mut self, register: Register, val: Byte) {
         self.v[register] = val;
     }
 
-    fn read_register(&self, register: Register) -> Byte {
+    pub fn read_register(&self, register: Register) -> Byte {
         self.v[register]
     }
 
@@ -162,184 +359,359 @@ impl<'a, G> Cpu<'a, G> where G: graphics::GraphicsOutput {
         }
     }
 
-    fn read_bytes(&self, addr: Address, n: usize) -> Vec<Byte> {
-        self.memory[addr..addr + n].to_vec()
+    fn check_memory_range(&self, addr: Address, n: usize) -> Result<()> {
+        check_range(addr, n, self.memory.len())
+    }
+
+    /// Accesses through I stay within the platform's address space, so on
+    /// XO-CHIP they cannot reach the call stack kept above it.
+    fn check_address_space(&self, addr: Address, n: usize) -> Result<()> {
+        check_range(addr, n, self.platform.address_space())
+    }
+
+    fn read_bytes(&self, addr: Address, n: usize) -> Result<Vec<Byte>> {
+        self.check_address_space(addr, n)?;
+        Ok((addr..addr + n).map(|addr| self.memory.read(addr)).collect())
     }
 
-    fn load_byte(&mut self, addr: Address, byte: Byte) {
-        self.memory[addr] = byte;
+    fn load_byte(&mut self, addr: Address, byte: Byte) -> Result<()> {
+        self.check_address_space(addr, 1)?;
+        self.store_byte(addr, byte);
+        Ok(())
     }
 
-    fn draw_byte(&mut self, x: Address, y: Address, byte: Byte) -> bool {
+    fn store_byte(&mut self, addr: Address, byte: Byte) {
+        self.memory.write(addr, byte);
+        if let Some(ref mut writes) = self.writes { writes.push((addr, byte)); }
+    }
+
+    fn trace_step(&mut self, pc: Address, opcode: &Opcode, v_before: [Byte; NUM_REGISTERS], i_before: Address) {
+        let record = TraceRecord {
+            pc,
+            opcode: opcode.code(),
+            instruction: opcode.decode_for(self.platform).to_string(),
+            v_before,
+            v_after: self.v,
+            i_before,
+            i_after: self.i.current,
+            writes: self.writes.take().unwrap_or_default()
+        };
+        if let Some(ref mut trace) = self.trace { trace.record(&record); }
+    }
+
+    fn draw_byte(&mut self, plane: usize, x: Address, y: Address, byte: Byte) -> bool {
+        if self.quirks.clip_sprites && y >= self.graphics.height() { return false; }
+
         let mut collision = false;
         for b in 0..8 {
+            let px = x + b as usize;
+            if self.quirks.clip_sprites && px >= self.graphics.width() { break; }
+
             let bit = byte.wrapping_shr(8 - b - 1) & 0b1;
-            if self.graphics.update_pixel(x + b as usize, y, bit == 1) {
+            if self.graphics.update_plane_pixel(plane, px, y, bit == 1) {
                 collision = true;
             }
         }
         collision
     }
 
-    fn read_delay_timer(&self) -> Byte {
+    fn wait_for_vblank(&mut self) -> bool {
+        if !self.quirks.display_wait { return false; }
+
+        if !self.vblank_wait {
+            self.vblank_wait = true;
+            self.vblank = false;
+        }
+
+        if !self.vblank { return true; }
+
+        self.vblank_wait = false;
+        false
+    }
+
+    fn index_increment(&self, x: Register) -> Address {
+        match self.quirks.index_increment {
+            IndexIncrement::XPlusOne => x + 1,
+            IndexIncrement::X => x,
+            IndexIncrement::Unchanged => 0
+        }
+    }
+
+    fn key_pressed(&self, key: Byte) -> bool {
+        self.input.key_pressed(key)
+    }
+
+    fn first_key_pressed(&self) -> Option<Byte> {
+        (0x0..0x10).find(|key| self.key_pressed(*key))
+    }
+
+    pub fn read_delay_timer(&self) -> Byte {
         self.dt.current
     }
 
-    fn load_delay_timer(&mut self, val: Byte) {
+    pub fn load_delay_timer(&mut self, val: Byte) {
         self.dt.set(val);
     }
 
-    fn read_sound_timer(&mut self) -> Byte {
-        self.st.current
+    pub fn load_sound_timer(&mut self, val: Byte) {
+        self.st.set(val);
     }
 
-    fn load_sound_timer(&mut self, val: Byte) {
-        self.st.set(val);
+    pub fn read_sound_timer(&self) -> Byte {
+        self.st.current
     }
 
     fn update_timers(&mut self) {
-        self.dt.tick();
-        self.st.tick();
+        let frames = self.clock.tick();
+        self.advance_frames(frames);
+    }
+
+    fn advance_frames(&mut self, frames: u32) {
+        if frames > 0 {
+            self.vblank();
+            self.input.end_frame();
+        }
+        self.dt.tick(frames);
+        self.st.tick(frames);
         self.beep = self.st.active();
     }
 
-    fn stack_pop(&mut self) -> Address {
+    fn stack_pop(&mut self) -> Result<Address> {
         let current = self.sp.current;
-        let addr = (self.memory[current] as Address) << 8 | (self.memory[current + 1] as Address);
-        self.sp.move_backward();
-        addr
+        let addr = (self.memory.read(current) as Address) << 8 | (self.memory.read(current + 1) as Address);
+        self.sp.move_backward()?;
+        Ok(addr)
     }
 
-    fn stack_push(&mut self) {
-        self.sp.move_forward();
+    fn stack_push(&mut self) -> Result<()> {
+        self.sp.move_forward()?;
         let current = self.sp.current;
         let addr = self.pc.current;
-        self.memory[current] = ((addr & 0xFF00) >> 8) as Byte;
-        self.memory[current + 1] = (addr & 0x00FF) as Byte;
+        self.store_byte(current, ((addr & 0xFF00) >> 8) as Byte);
+        self.store_byte(current + 1, (addr & 0x00FF) as Byte);
+        Ok(())
+    }
+}
+
+fn check_range(addr: Address, n: usize, len: usize) -> Result<()> {
+    match addr.checked_add(n) {
+        Some(end) if end <= len => Ok(()),
+        _ => Err(Error::AddressOutOfRange { addr: addr.saturating_add(n.saturating_sub(1)), range: 0x0..len })
     }
 }
 
-impl<'a, G> Operation for Cpu<'a, G> where G: graphics::GraphicsOutput {
-    fn no_op(&mut self, _opcode: &Opcode) {
-        self.pc.move_forward();
+impl<'a, G, I> Operation for Cpu<'a, G, I> where G: graphics::GraphicsOutput, I: input::Input {
+    fn no_op(&mut self, _opcode: &Opcode) -> Result<()> {
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn unknown(&mut self, opcode: &Opcode) {
-        panic!("Unknown opcode {}", opcode);
+    fn unknown(&mut self, opcode: &Opcode) -> Result<()> {
+        Err(Error::UnknownOpcode(opcode.code()))
     }
 
-    fn clear_display(&mut self, _opcode: &Opcode) {
+    fn clear_display(&mut self, _opcode: &Opcode) -> Result<()> {
         self.graphics.clear();
-        self.pc.move_forward();
-        println!("\tCLS");
+        self.pc.move_forward()?;
+        Ok(())
+    }
+
+    fn return_from_subroutine(&mut self, _opcode: &Opcode) -> Result<()> {
+        let addr = self.stack_pop()?;
+        self.pc.set(addr)?;
+        self.pc.move_forward()?;
+        Ok(())
+    }
+
+    fn scroll_down_n(&mut self, opcode: &Opcode) -> Result<()> {
+        let n = opcode.k();
+        self.graphics.scroll(0, n as isize);
+        self.pc.move_forward()?;
+        Ok(())
+    }
+
+    fn scroll_right(&mut self, _opcode: &Opcode) -> Result<()> {
+        self.graphics.scroll(4, 0);
+        self.pc.move_forward()?;
+        Ok(())
+    }
+
+    fn scroll_left(&mut self, _opcode: &Opcode) -> Result<()> {
+        self.graphics.scroll(-4, 0);
+        self.pc.move_forward()?;
+        Ok(())
+    }
+
+    fn exit_interpreter(&mut self, _opcode: &Opcode) -> Result<()> {
+        self.exit = true;
+        Ok(())
     }
 
-    fn return_from_subroutine(&mut self, _opcode: &Opcode) {
-        let addr = self.stack_pop();
-        self.pc.set(addr);
-        self.pc.move_forward();
-        println!("\tRTN => {:x}", addr);
+    fn low_res(&mut self, _opcode: &Opcode) -> Result<()> {
+        self.graphics.set_high_res(false);
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn jump_addr(&mut self, opcode: &Opcode) {
+    fn high_res(&mut self, _opcode: &Opcode) -> Result<()> {
+        self.graphics.set_high_res(true);
+        self.pc.move_forward()?;
+        Ok(())
+    }
+
+    fn scroll_up_n(&mut self, opcode: &Opcode) -> Result<()> {
+        let n = opcode.k();
+        self.graphics.scroll(0, -(n as isize));
+        self.pc.move_forward()?;
+        Ok(())
+    }
+
+    fn cycle_background(&mut self, _opcode: &Opcode) -> Result<()> {
+        self.graphics.cycle_background();
+        self.pc.move_forward()?;
+        Ok(())
+    }
+
+    fn jump_addr(&mut self, opcode: &Opcode) -> Result<()> {
         let addr = opcode.nnn();
 
         if self.pc.current == addr {
             self.exit = true;
-            return;
+            return Ok(());
         }
 
-        self.pc.set(addr);
-        println!("\tJP {:x}", addr);
+        self.pc.set(addr)?;
+        Ok(())
     }
 
-    fn call_addr(&mut self, opcode: &Opcode) {
+    fn call_addr(&mut self, opcode: &Opcode) -> Result<()> {
         let addr = opcode.nnn();
-        self.stack_push();
-        self.pc.set(addr);
-        println!("\tCALL {:x}", addr);
+        self.stack_push()?;
+        self.pc.set(addr)?;
+        Ok(())
     }
 
-    fn skip_equal_vx_byte(&mut self, opcode: &Opcode) {
+    fn skip_equal_vx_byte(&mut self, opcode: &Opcode) -> Result<()> {
         let vx = self.read_register(opcode.x());
         let byte = opcode.kk();
-        if vx == byte { self.skip(); }
-        self.pc.move_forward();
-        println!("\tSE vx: {:x}, byte: {:x}", vx, byte);
+        if vx == byte { self.skip()?; }
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn skip_not_equal_vx_byte(&mut self, opcode: &Opcode) {
+    fn skip_not_equal_vx_byte(&mut self, opcode: &Opcode) -> Result<()> {
         let vx = self.read_register(opcode.x());
         let byte = opcode.kk();
-        if vx != byte { self.skip(); }
-        self.pc.move_forward();
-        println!("\tSNE vx: {:x}, byte: {:x}", vx, byte);
+        if vx != byte { self.skip()?; }
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn skip_equal_vx_vy(&mut self, opcode: &Opcode) {
+    fn skip_equal_vx_vy(&mut self, opcode: &Opcode) -> Result<()> {
         let vx = self.read_register(opcode.x());
         let vy = self.read_register(opcode.y());
-        if vx == vy { self.skip(); }
-        self.pc.move_forward();
-        println!("\tSE vx: {:x}, vy: {:x}", vx, vy);
+        if vx == vy { self.skip()?; }
+        self.pc.move_forward()?;
+        Ok(())
+    }
+
+    fn add_nibbles_vx_vy(&mut self, opcode: &Opcode) -> Result<()> {
+        let x = opcode.x();
+        let y = opcode.y();
+        let vx = self.read_register(x);
+        let vy = self.read_register(y);
+        let high = (vx & 0xF0).wrapping_add(vy & 0xF0);
+        let low = vx.wrapping_add(vy) & 0x0F;
+        self.load_register(x, high | low);
+        self.pc.move_forward()?;
+        Ok(())
+    }
+
+    fn save_vx_vy(&mut self, opcode: &Opcode) -> Result<()> {
+        let x = opcode.x();
+        let y = opcode.y();
+        let i = self.read_i();
+        let registers: Vec<Register> = if x <= y { (x..y + 1).collect() } else { (y..x + 1).rev().collect() };
+        for (offset, r) in registers.iter().enumerate() {
+            let byte = self.read_register(*r);
+            self.load_byte(i + offset, byte)?;
+        }
+        self.pc.move_forward()?;
+        Ok(())
+    }
+
+    fn read_vx_vy(&mut self, opcode: &Opcode) -> Result<()> {
+        let x = opcode.x();
+        let y = opcode.y();
+        let i = self.read_i();
+        let registers: Vec<Register> = if x <= y { (x..y + 1).collect() } else { (y..x + 1).rev().collect() };
+        let bytes = self.read_bytes(i, registers.len())?;
+        for (r, byte) in registers.iter().zip(bytes.iter()) {
+            self.load_register(*r, *byte);
+        }
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn load_vx_byte(&mut self, opcode: &Opcode) {
+    fn load_vx_byte(&mut self, opcode: &Opcode) -> Result<()> {
         let x = opcode.x();
         self.load_register(x, opcode.kk());
-        self.pc.move_forward();
-        println!("\tLD V{:x}, byte: {:x} => {:x}", x, opcode.kk(), self.read_register(x));
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn add_vx_byte(&mut self, opcode: &Opcode) {
+    fn add_vx_byte(&mut self, opcode: &Opcode) -> Result<()> {
         let x = opcode.x();
         let byte = opcode.kk();
         let vx = self.read_register(x);
         self.load_register(x, vx.wrapping_add(byte));
-        self.pc.move_forward();
-        println!("\tADD V{:x}: {:x}, byte: {:x} => {:x}", x, vx, byte, self.read_register(x));
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn load_vx_vy(&mut self, opcode: &Opcode) {
+    fn load_vx_vy(&mut self, opcode: &Opcode) -> Result<()> {
         let x = opcode.x();
         let y = opcode.y();
         let vy = self.read_register(y);
         self.load_register(x, vy);
-        self.pc.move_forward();
-        println!("\tLD V{:x}, V{:x}: {:x} => {:x}", x, y, vy, self.read_register(x));
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn or_vx_vy(&mut self, opcode: &Opcode) {
+    fn or_vx_vy(&mut self, opcode: &Opcode) -> Result<()> {
         let x = opcode.x();
         let y = opcode.y();
         let vx = self.read_register(x);
         let vy = self.read_register(y);
         self.load_register(x, vx | vy);
-        self.pc.move_forward();
-        println!("\tOR V{:x}: {:x}, V{:x}: {:x} => {:x}", x, vx, y, vy, self.read_register(x));
+        if self.quirks.logic_reset_vf { self.load_flag(false); }
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn and_vx_vy(&mut self, opcode: &Opcode) {
+    fn and_vx_vy(&mut self, opcode: &Opcode) -> Result<()> {
         let x = opcode.x();
         let y = opcode.y();
         let vx = self.read_register(x);
         let vy = self.read_register(y);
         self.load_register(x, vx & vy);
-        self.pc.move_forward();
-        println!("\tAND V{:x}: {:x}, V{:x}: {:x} => {:x}", x, vx, y, vy, self.read_register(x));
+        if self.quirks.logic_reset_vf { self.load_flag(false); }
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn xor_vx_vy(&mut self, opcode: &Opcode) {
+    fn xor_vx_vy(&mut self, opcode: &Opcode) -> Result<()> {
         let x = opcode.x();
         let y = opcode.y();
         let vx = self.read_register(x);
         let vy = self.read_register(y);
         self.load_register(x, vx ^ vy);
-        self.pc.move_forward();
-        println!("\tXOR V{:x}: {:x}, V{:x}: {:x} => {:x}", x, vx, y, vy, self.read_register(x));
+        if self.quirks.logic_reset_vf { self.load_flag(false); }
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn add_vx_vy(&mut self, opcode: &Opcode) {
+    fn add_vx_vy(&mut self, opcode: &Opcode) -> Result<()> {
         let x = opcode.x();
         let y = opcode.y();
         let vx = self.read_register(x);
@@ -347,11 +719,11 @@ impl<'a, G> Operation for Cpu<'a, G> where G: graphics::GraphicsOutput {
         let (result, carry) = vx.overflowing_add(vy);
         self.load_register(x, result);
         self.load_flag(carry);
-        self.pc.move_forward();
-        println!("\tADD V{:x}: {:x}, V{:x}: {:x} => ({:x}, {})", x, vx, y, vy, self.read_register(x), self.read_register(0xF));
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn sub_vx_vy(&mut self, opcode: &Opcode) {
+    fn sub_vx_vy(&mut self, opcode: &Opcode) -> Result<()> {
         let x = opcode.x();
         let y = opcode.y();
         let vx = self.read_register(x);
@@ -359,22 +731,22 @@ impl<'a, G> Operation for Cpu<'a, G> where G: graphics::GraphicsOutput {
         let (result, borrow) = vx.overflowing_sub(vy);
         self.load_register(x, result);
         self.load_flag(!borrow);
-        self.pc.move_forward();
-        println!("\tSUB V{:x}: {:x}, V{:x}: {:x} => ({:x}, {})", x, vx, y, vy, self.read_register(x), self.read_register(0xF));
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn shr_vx_vy(&mut self, opcode: &Opcode) {
+    fn shr_vx_vy(&mut self, opcode: &Opcode) -> Result<()> {
         let x = opcode.x();
-        let y = opcode.y();
+        let y = if self.quirks.shift_vx { x } else { opcode.y() };
         let vy = self.read_register(y);
         let bit = vy % 2;
         self.load_register(x, vy.wrapping_shr(1));
         self.load_flag(bit == 1);
-        self.pc.move_forward();
-        println!("\tSHR V{:x}, V{:x}: {:x} => ({:x}, {})", x, y, vy, self.read_register(x), self.read_register(0xF));
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn subn_vx_vy(&mut self, opcode: &Opcode) {
+    fn subn_vx_vy(&mut self, opcode: &Opcode) -> Result<()> {
         let x = opcode.x();
         let y = opcode.y();
         let vx = self.read_register(x);
@@ -382,173 +754,334 @@ impl<'a, G> Operation for Cpu<'a, G> where G: graphics::GraphicsOutput {
         let (result, borrow) = vy.overflowing_sub(vx);
         self.load_register(x, result);
         self.load_flag(!borrow);
-        self.pc.move_forward();
-        println!("\tSUBN V{:x}: {:x}, V{:x}: {:x} => ({:x}, {})", x, vx, y, vy, self.read_register(x), self.read_register(0xF));
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn shl_vx_vy(&mut self, opcode: &Opcode) {
+    fn shl_vx_vy(&mut self, opcode: &Opcode) -> Result<()> {
         let x = opcode.x();
-        let y = opcode.y();
+        let y = if self.quirks.shift_vx { x } else { opcode.y() };
         let vy = self.read_register(y);
         let bit = vy >> 7;
         self.load_register(x, vy.wrapping_shl(1));
         self.load_flag(bit == 1);
-        self.pc.move_forward();
-        println!("\tSHL V{:x}, V{:x}: {:x} => ({:x}, {})", x, y, vy, self.read_register(x), self.read_register(0xF));
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn skip_not_equal_vx_vy(&mut self, opcode: &Opcode) {
+    fn skip_not_equal_vx_vy(&mut self, opcode: &Opcode) -> Result<()> {
         let x = opcode.x();
         let y = opcode.y();
         let vx = self.read_register(x);
         let vy = self.read_register(y);
-        if vx != vy { self.skip(); }
-        self.pc.move_forward();
-        println!("\tSNE V{:x}: {:x}, V{:x}: {:x}", x, vx, y, vy);
+        if vx != vy { self.skip()?; }
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn load_i_addr(&mut self, opcode: &Opcode) {
+    fn load_i_addr(&mut self, opcode: &Opcode) -> Result<()> {
         let addr = opcode.nnn();
-        self.load_i(addr);
-        self.pc.move_forward();
-        println!("\tLD I, {:x} => {:x}", addr, self.read_i());
+        self.load_i(addr)?;
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn jump_v0_addr(&mut self, opcode: &Opcode) {
-        let v0 = self.read_register(0x0) as Address;
+    fn jump_v0_addr(&mut self, opcode: &Opcode) -> Result<()> {
+        let x = if self.quirks.jump_vx { opcode.x() } else { 0x0 };
+        let v0 = self.read_register(x) as Address;
         let addr = opcode.nnn();
-        self.pc.set(addr + v0);
-        println!("\tJP V0: {:x}, {:x}", v0, addr);
+        self.pc.set(addr + v0)?;
+        Ok(())
+    }
+
+    /// BXY0 colours whole 8x4 zones: the low nibbles of VX and VX+1 give the
+    /// first zone column and row, and the high nibbles how many more to fill.
+    /// BXYN colours N rows of a single 8x1 zone at pixel position VX, VX+1.
+    /// Both take the colour from VY.
+    fn colour_vx_vy_n(&mut self, opcode: &Opcode) -> Result<()> {
+        let x = opcode.x();
+        let y = opcode.y();
+        let n = opcode.k();
+        let vx = self.read_register(x) as usize;
+        let vx1 = self.read_register((x + 1) & 0xF) as usize;
+        let colour = Colour::from_byte(self.read_register(y));
+        if n == 0 {
+            let (left, top) = (vx & 0xF, vx1 & 0xF);
+            for zone_y in top..=top + (vx1 >> 4) {
+                for zone_x in left..=left + (vx >> 4) {
+                    for row in 0..graphics::COLOUR_ZONE_HEIGHT {
+                        let px = zone_x * graphics::COLOUR_ZONE_WIDTH;
+                        let py = zone_y * graphics::COLOUR_ZONE_HEIGHT + row;
+                        self.graphics.set_foreground(px, py, colour);
+                    }
+                }
+            }
+        } else {
+            for row in 0..n {
+                self.graphics.set_foreground(vx, vx1 + row, colour);
+            }
+        }
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn rand_vx_byte(&mut self, opcode: &Opcode) {
+    fn rand_vx_byte(&mut self, opcode: &Opcode) -> Result<()> {
         let x = opcode.x();
         let byte = opcode.kk();
-        let random_byte: Byte = rand::thread_rng().gen_range(0x0, 0xFF);
+        let random_byte = self.random.next_byte();
         self.load_register(x, random_byte & byte);
-        self.pc.move_forward();
-        println!("\tRND V{:x} => {:x}", x, self.read_register(x));
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn draw_vx_vy_n(&mut self, opcode: &Opcode) {
-        let vx = self.read_register(opcode.x()) as Address;
-        let vy = self.read_register(opcode.y()) as Address;
+    fn draw_vx_vy_n(&mut self, opcode: &Opcode) -> Result<()> {
+        if self.wait_for_vblank() { return Ok(()); }
+
+        let vx = self.read_register(opcode.x()) as Address % self.graphics.width();
+        let vy = self.read_register(opcode.y()) as Address % self.graphics.height();
         let n = opcode.k();
-        let i = self.read_i();
-        let sprite_bytes = self.read_bytes(i, n);
+        let (rows, row_bytes) = if n == 0 && self.platform.super_chip() { (16, 2) } else { (n, 1) };
+        let planes = if self.platform.xo_chip() { self.graphics.planes() } else { 0b01 };
+        let mut i = self.read_i();
         let mut collision = false;
-        for sprite_y in 0..n {
-            let sprite_byte = sprite_bytes[sprite_y];
-            if self.draw_byte(vx, vy + sprite_y, sprite_byte) {
-                collision = true;
+        for plane in 0..graphics::NUM_PLANES {
+            if planes & (0b1 << plane) == 0 { continue; }
+
+            let plane_bytes = self.read_bytes(i, rows * row_bytes)?;
+            i += plane_bytes.len();
+            for (sprite_y, row) in plane_bytes.chunks(row_bytes).enumerate() {
+                for (sprite_x, sprite_byte) in row.iter().enumerate() {
+                    let x = vx + sprite_x * graphics::SPRITE_WIDTH;
+                    if self.draw_byte(plane, x, vy + sprite_y, *sprite_byte) {
+                        collision = true;
+                    }
+                }
             }
         }
 
         self.load_flag(collision);
-        self.graphics.draw();
-        self.pc.move_forward();
+        self.pc.move_forward()?;
+        Ok(())
+    }
+
+    fn skip_key_pressed_vx(&mut self, opcode: &Opcode) -> Result<()> {
+        let x = opcode.x();
+        let vx = self.read_register(x);
+        if self.key_pressed(vx) { self.skip()?; }
+        self.pc.move_forward()?;
+        Ok(())
+    }
+
+    fn skip_key_not_pressed_vx(&mut self, opcode: &Opcode) -> Result<()> {
+        let x = opcode.x();
+        let vx = self.read_register(x);
+        if !self.key_pressed(vx) { self.skip()?; }
+        self.pc.move_forward()?;
+        Ok(())
+    }
+
+    fn skip_second_key_pressed_vx(&mut self, opcode: &Opcode) -> Result<()> {
+        let x = opcode.x();
+        let vx = self.read_register(x);
+        if self.input.second_key_pressed(vx) { self.skip()?; }
+        self.pc.move_forward()?;
+        Ok(())
+    }
+
+    fn skip_second_key_not_pressed_vx(&mut self, opcode: &Opcode) -> Result<()> {
+        let x = opcode.x();
+        let vx = self.read_register(x);
+        if !self.input.second_key_pressed(vx) { self.skip()?; }
+        self.pc.move_forward()?;
+        Ok(())
+    }
+
+    fn load_i_long(&mut self, _opcode: &Opcode) -> Result<()> {
+        let pc = self.pc.current;
+        let bytes = self.read_bytes(pc + 2, 2)?;
+        let addr = (bytes[0] as Address) << 8 | bytes[1] as Address;
+        self.load_i(addr)?;
+        self.pc.move_forward()?;
+        self.pc.move_forward()?;
+        Ok(())
+    }
 
-        println!("\tDRW Vx: {:x}, Vy: {:x}, {:?}", vx, vy, sprite_bytes);
+    fn select_planes(&mut self, opcode: &Opcode) -> Result<()> {
+        let n = opcode.x() as Byte;
+        self.graphics.select_planes(n);
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn skip_key_pressed_vx(&mut self, _opcode: &Opcode) {}
-    fn skip_key_not_pressed_vx(&mut self, _opcode: &Opcode) {}
+    fn load_audio(&mut self, _opcode: &Opcode) -> Result<()> {
+        let i = self.read_i();
+        let bytes = self.read_bytes(i, sound::PATTERN_SIZE)?;
+        self.audio.buffer.copy_from_slice(&bytes);
+        self.pc.move_forward()?;
+        Ok(())
+    }
 
-    fn load_vx_dt(&mut self, opcode: &Opcode) {
+    fn load_vx_dt(&mut self, opcode: &Opcode) -> Result<()> {
         let x = opcode.x();
         let dt = self.read_delay_timer();
         self.load_register(x, dt);
-        self.pc.move_forward();
-        println!("\tLD V{:x}, DT: {:x} => {:x}", x, dt, self.read_register(x));
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn load_vx_key(&mut self, _opcode: &Opcode) {}
+    fn load_vx_key(&mut self, opcode: &Opcode) -> Result<()> {
+        let x = opcode.x();
+        match self.key_wait {
+            None => {
+                self.key_wait = self.first_key_pressed();
+            },
+            Some(key) => {
+                if self.key_pressed(key) { return Ok(()); }
+
+                self.key_wait = None;
+                self.load_register(x, key);
+                self.pc.move_forward()?;
+            }
+        }
+        Ok(())
+    }
 
-    fn load_dt_vx(&mut self, opcode: &Opcode) {
+    fn load_dt_vx(&mut self, opcode: &Opcode) -> Result<()> {
         let x = opcode.x();
         let vx = self.read_register(x);
         self.load_delay_timer(vx);
-        self.pc.move_forward();
-        println!("\tLD DT, V{:x}: {:x} => {:x}", x, vx, self.read_delay_timer());
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn load_st_vx(&mut self, opcode: &Opcode) {
+    fn load_st_vx(&mut self, opcode: &Opcode) -> Result<()> {
         let x = opcode.x();
         let vx = self.read_register(x);
         self.load_sound_timer(vx);
-        self.pc.move_forward();
-        println!("\tLD ST, V{:x}: {:x} => {:x}", x, vx, self.read_sound_timer());
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn add_i_vx(&mut self, opcode: &Opcode) {
+    fn add_i_vx(&mut self, opcode: &Opcode) -> Result<()> {
         let i = self.read_i();
         let x = opcode.x();
         let vx = self.read_register(x) as Address;
-        self.load_i(i.wrapping_add(vx));
-        self.pc.move_forward();
-        println!("\tADD I: {:x}, V{:x}: {:x} => {:x}", i, x, vx, self.read_i());
+        self.load_i(i.wrapping_add(vx))?;
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn load_i_vx_font(&mut self, opcode: &Opcode) {
+    fn load_i_vx_font(&mut self, opcode: &Opcode) -> Result<()> {
         let x = opcode.x();
         let vx = self.read_register(x) as Address;
-        self.load_i(vx * font::SPRITE_HEIGHT);
-        self.pc.move_forward();
-        println!("\tLD I, FONT V{:x}: {:x} => {:x}", x, vx, self.read_i());
+        self.load_i(vx * font::SPRITE_HEIGHT)?;
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn load_bcd_vx(&mut self, opcode: &Opcode) {
+    fn load_i_vx_big_font(&mut self, opcode: &Opcode) -> Result<()> {
+        let x = opcode.x();
+        let vx = self.read_register(x) as Address & 0xF;
+        self.load_i(BIG_FONT_ADDR + vx * font::BIG_SPRITE_HEIGHT)?;
+        self.pc.move_forward()?;
+        Ok(())
+    }
+
+    fn load_bcd_vx(&mut self, opcode: &Opcode) -> Result<()> {
         let i = self.read_i();
         let x = opcode.x();
         let vx = self.read_register(x);
-        self.memory[i + 0] = vx / 100;
-        self.memory[i + 1] = vx % 100 / 10;
-        self.memory[i + 2] = vx % 10;
-        self.pc.move_forward();
-        println!("\tLD BCD V{:x}: {:x} ({}) => {:?}", x, vx, vx, self.memory[i..i + 2].to_vec());
+        self.load_byte(i, vx / 100)?;
+        self.load_byte(i + 1, vx % 100 / 10)?;
+        self.load_byte(i + 2, vx % 10)?;
+        self.pc.move_forward()?;
+        Ok(())
+    }
+
+    fn load_pitch_vx(&mut self, opcode: &Opcode) -> Result<()> {
+        let x = opcode.x();
+        let vx = self.read_register(x);
+        self.audio.pitch = vx;
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn load_through_vx(&mut self, opcode: &Opcode) {
+    fn load_through_vx(&mut self, opcode: &Opcode) -> Result<()> {
         let x = opcode.x();
         let i = self.read_i();
         let bytes: Vec<Byte> = (0..x + 1).map(|r| self.read_register(r)).collect();
         for (addr, byte) in bytes.iter().enumerate() {
-            self.load_byte(i + addr, *byte);
+            self.load_byte(i + addr, *byte)?;
         }
-        self.load_i(i + x + 1);
-        self.pc.move_forward();
-
-        let i = self.read_i();
-        let register_bytes: Vec<Byte> = (0..x + 1).map(|r| self.read_register(r)).collect();
-        println!("\tLD [I], V{:x} [{:?}] => {:?}", x, register_bytes, self.read_bytes(i, x + 1));
+        let increment = self.index_increment(x);
+        self.load_i(i + increment)?;
+        self.pc.move_forward()?;
+        Ok(())
     }
 
-    fn read_through_vx(&mut self, opcode: &Opcode) {
+    fn read_through_vx(&mut self, opcode: &Opcode) -> Result<()> {
         let x = opcode.x();
         let i = self.read_i();
-        let bytes = self.read_bytes(i, x + 1);
+        let bytes = self.read_bytes(i, x + 1)?;
         for (r, byte) in bytes.iter().enumerate() {
             self.load_register(r, *byte);
         }
-        self.load_i(i + x + 1);
-        self.pc.move_forward();
+        let increment = self.index_increment(x);
+        self.load_i(i + increment)?;
+        self.pc.move_forward()?;
+        Ok(())
+    }
 
-        let i = self.read_i();
-        let register_bytes: Vec<Byte> = (0..x + 1).map(|r| self.read_register(r)).collect();
-        println!("\tRD V{:x} [{:?}], [I] => {:?}", x, self.read_bytes(i, x + 1), register_bytes);
+    fn output_port_vx(&mut self, opcode: &Opcode) -> Result<()> {
+        let x = opcode.x();
+        self.port_out = self.read_register(x);
+        self.pc.move_forward()?;
+        Ok(())
+    }
+
+    fn input_port_vx(&mut self, opcode: &Opcode) -> Result<()> {
+        let x = opcode.x();
+        if let Some(byte) = self.port_in.take() {
+            self.load_register(x, byte);
+            self.pc.move_forward()?;
+        }
+        Ok(())
+    }
+
+    fn store_flags_vx(&mut self, opcode: &Opcode) -> Result<()> {
+        let x = opcode.x();
+        for r in 0..x + 1 {
+            self.rpl[r] = self.read_register(r);
+        }
+        self.pc.move_forward()?;
+        Ok(())
+    }
+
+    fn read_flags_vx(&mut self, opcode: &Opcode) -> Result<()> {
+        let x = opcode.x();
+        for r in 0..x + 1 {
+            let flag = self.rpl[r];
+            self.load_register(r, flag);
+        }
+        self.pc.move_forward()?;
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use input::keypad;
     use output::graphics::GraphicsOutput;
 
     #[test]
     fn new_loads_font_to_memory() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let cpu = Cpu::new(&rom, &mut graphics);
+        let cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let font_set = font::FONT_SET.to_vec();
         let range = FONT_RANGE.start..FONT_RANGE.start + font::FONT_SET.len();
@@ -559,19 +1092,73 @@ mod tests {
     #[test]
     fn new_loads_rom_to_memory() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = vec![0x00, 0x01, 0x02, 0x03];
-        let cpu = Cpu::new(&rom, &mut graphics);
+        let cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
         
         let range = ROM_RANGE.start..ROM_RANGE.start + rom.len();
         let mem = cpu.memory[range].to_vec();
         assert_eq!(rom, mem);
     }
 
+    #[test]
+    fn new_with_oversized_rom_errors() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = vec![0x00; ROM_RANGE.len() + 1];
+
+        match Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()) {
+            Err(Error::DataTooLarge { len, .. }) => assert_eq!(rom.len(), len),
+            _ => panic!("expected Error::DataTooLarge")
+        }
+    }
+
+    #[test]
+    fn step_unknown_opcode_faults_with_pc_and_opcode() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = vec![0x00, 0x00, 0x01, 0x23];
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+
+        cpu.step().unwrap();
+        match cpu.step() {
+            Err(Error::Fault { pc, opcode, cause }) => {
+                assert_eq!(ROM_RANGE.start + 2, pc);
+                assert_eq!(0x0123, opcode);
+                match *cause {
+                    Error::UnknownOpcode(code) => assert_eq!(0x0123, code),
+                    _ => panic!("expected Error::UnknownOpcode")
+                }
+            },
+            _ => panic!("expected Error::Fault")
+        }
+    }
+
+    #[test]
+    fn step_return_with_empty_stack_faults() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = vec![0x00, 0xEE];
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+
+        match cpu.step() {
+            Err(Error::Fault { pc, cause, .. }) => {
+                assert_eq!(ROM_RANGE.start, pc);
+                match *cause {
+                    Error::AddressOutOfRange { range, .. } => assert_eq!(STACK_RANGE, range),
+                    _ => panic!("expected Error::AddressOutOfRange")
+                }
+            },
+            _ => panic!("expected Error::Fault")
+        }
+    }
+
     #[test]
     fn fetch_opcode_fetches_two_current_bytes() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = vec![0xAB, 0xCD, 0xEF, 0xFF];
-        let cpu = Cpu::new(&rom, &mut graphics);
+        let cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         assert_eq!(Opcode::new(0xABCD), cpu.fetch_opcode());        
     }
@@ -579,15 +1166,16 @@ mod tests {
     #[test]
     fn operation_0000_no_op() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x0000);
         let op = cpu.operation(&opcode);
         let pc = cpu.pc.current;
         let memory = cpu.memory.clone();
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(memory, cpu.memory);
         assert_eq!(pc + 2, cpu.pc.current);
     }
@@ -595,14 +1183,15 @@ mod tests {
     #[test]
     fn operation_00e0_clear_display() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x00E0);
         let op = cpu.operation(&opcode);
         let pc = cpu.pc.current;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         for x in 0..graphics::SCREEN_WIDTH {
             for y in 0..graphics::SCREEN_HEIGHT {
                 assert!(!cpu.graphics.read_pixel(x, y));
@@ -614,136 +1203,249 @@ mod tests {
     #[test]
     fn operation_00ee_return_from_subroutine() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x00EE);
         let op = cpu.operation(&opcode);
         let pc = cpu.pc.current;
 
-        cpu.stack_push();
-        cpu.pc.move_forward();
-        cpu.pc.move_forward();
-        cpu.pc.move_forward();
+        cpu.stack_push().unwrap();
+        cpu.pc.move_forward().unwrap();
+        cpu.pc.move_forward().unwrap();
+        cpu.pc.move_forward().unwrap();
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(pc + 2, cpu.pc.current);
     }
 
     #[test]
-    fn operation_1nnn_jump_addr() {
+    fn operation_00cn_scroll_down_n() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::SuperChip, Quirks::default()).unwrap();
 
-        let opcode = Opcode::new(0x1404);
+        let opcode = Opcode::new(0x00C3);
         let op = cpu.operation(&opcode);
+        let pc = cpu.pc.current;
 
-        op(&mut cpu, &opcode);
-        assert_eq!(0x404, cpu.pc.current);
+        cpu.graphics.update_pixel(4, 1, true);
+
+        op(&mut cpu, &opcode).unwrap();
+        assert!(cpu.graphics.read_pixel(4, 4));
+        assert!(!cpu.graphics.read_pixel(4, 1));
+        assert_eq!(pc + 2, cpu.pc.current);
     }
 
     #[test]
-    fn operation_2nnn_call_subroutine() {
+    fn operation_00cn_unknown_on_chip8() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
-        let opcode = Opcode::new(0x2404);
+        let opcode = Opcode::new(0x00C3);
         let op = cpu.operation(&opcode);
 
-        op(&mut cpu, &opcode);
-        assert_eq!(0x404, cpu.pc.current);
-        assert_eq!(0x02, cpu.memory[cpu.sp.current]);
-        assert_eq!(0x00, cpu.memory[cpu.sp.current + 1]);
+        assert!(op(&mut cpu, &opcode).is_err());
     }
 
     #[test]
-    fn operation_3xkk_skip_equal_vx_byte() {
+    fn operation_00fb_scroll_right() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::SuperChip, Quirks::default()).unwrap();
 
-        let opcode = Opcode::new(0x3123);
+        let opcode = Opcode::new(0x00FB);
         let op = cpu.operation(&opcode);
         let pc = cpu.pc.current;
 
-        cpu.v[0x1] = 0x23;
+        cpu.graphics.update_pixel(4, 1, true);
 
-        op(&mut cpu, &opcode);
-        assert_eq!(pc + 4, cpu.pc.current);
+        op(&mut cpu, &opcode).unwrap();
+        assert!(cpu.graphics.read_pixel(8, 1));
+        assert!(!cpu.graphics.read_pixel(4, 1));
+        assert_eq!(pc + 2, cpu.pc.current);
     }
 
     #[test]
-    fn operation_3xkk_skip_equal_vx_byte_no_skip() {
+    fn operation_00fc_scroll_left() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::SuperChip, Quirks::default()).unwrap();
 
-        let opcode = Opcode::new(0x3122);
+        let opcode = Opcode::new(0x00FC);
         let op = cpu.operation(&opcode);
         let pc = cpu.pc.current;
-        
-        cpu.v[0x1] = 0x23;
 
-        op(&mut cpu, &opcode);
+        cpu.graphics.update_pixel(4, 1, true);
+
+        op(&mut cpu, &opcode).unwrap();
+        assert!(cpu.graphics.read_pixel(0, 1));
+        assert!(!cpu.graphics.read_pixel(4, 1));
         assert_eq!(pc + 2, cpu.pc.current);
     }
 
     #[test]
-    fn operation_4xkk_skip_not_equal_vx_byte() {
+    fn operation_00fd_exit_interpreter() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::SuperChip, Quirks::default()).unwrap();
 
-        let opcode = Opcode::new(0x4123);
+        let opcode = Opcode::new(0x00FD);
         let op = cpu.operation(&opcode);
-        let pc = cpu.pc.current;
 
-        cpu.v[0x1] = 0x22;
-
-        op(&mut cpu, &opcode);
-        assert_eq!(pc + 4, cpu.pc.current);
+        op(&mut cpu, &opcode).unwrap();
+        assert!(cpu.exit);
     }
 
     #[test]
-    fn operation_4xkk_skip_not_equal_vx_byte_no_skip() {
+    fn operation_00fe_00ff_switch_resolution() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
-
-        let opcode = Opcode::new(0x4123);
-        let op = cpu.operation(&opcode);
-        let pc = cpu.pc.current;
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::SuperChip, Quirks::default()).unwrap();
 
-        cpu.v[0x1] = 0x23;
+        let high = Opcode::new(0x00FF);
+        let op = cpu.operation(&high);
+        op(&mut cpu, &high).unwrap();
+        assert!(cpu.graphics.high_res());
+        assert_eq!(graphics::HIRES_SCREEN_WIDTH, cpu.graphics.width());
 
-        op(&mut cpu, &opcode);
-        assert_eq!(pc + 2, cpu.pc.current);
+        let low = Opcode::new(0x00FE);
+        let op = cpu.operation(&low);
+        op(&mut cpu, &low).unwrap();
+        assert!(!cpu.graphics.high_res());
+        assert_eq!(graphics::SCREEN_WIDTH, cpu.graphics.width());
     }
 
     #[test]
-    fn operation_5xy0_skip_equal_vx_vy() {
+    fn operation_1nnn_jump_addr() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
-        let opcode = Opcode::new(0x5010);
+        let opcode = Opcode::new(0x1404);
         let op = cpu.operation(&opcode);
-        let pc = cpu.pc.current;
 
-        cpu.v[0x0] = 0x23;
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(0x404, cpu.pc.current);
+    }
+
+    #[test]
+    fn operation_2nnn_call_subroutine() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0x2404);
+        let op = cpu.operation(&opcode);
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(0x404, cpu.pc.current);
+        assert_eq!(0x02, cpu.memory[cpu.sp.current]);
+        assert_eq!(0x00, cpu.memory[cpu.sp.current + 1]);
+    }
+
+    #[test]
+    fn operation_3xkk_skip_equal_vx_byte() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0x3123);
+        let op = cpu.operation(&opcode);
+        let pc = cpu.pc.current;
+
+        cpu.v[0x1] = 0x23;
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(pc + 4, cpu.pc.current);
+    }
+
+    #[test]
+    fn operation_3xkk_skip_equal_vx_byte_no_skip() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0x3122);
+        let op = cpu.operation(&opcode);
+        let pc = cpu.pc.current;
+        
+        cpu.v[0x1] = 0x23;
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(pc + 2, cpu.pc.current);
+    }
+
+    #[test]
+    fn operation_4xkk_skip_not_equal_vx_byte() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0x4123);
+        let op = cpu.operation(&opcode);
+        let pc = cpu.pc.current;
+
+        cpu.v[0x1] = 0x22;
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(pc + 4, cpu.pc.current);
+    }
+
+    #[test]
+    fn operation_4xkk_skip_not_equal_vx_byte_no_skip() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0x4123);
+        let op = cpu.operation(&opcode);
+        let pc = cpu.pc.current;
+
+        cpu.v[0x1] = 0x23;
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(pc + 2, cpu.pc.current);
+    }
+
+    #[test]
+    fn operation_5xy0_skip_equal_vx_vy() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0x5010);
+        let op = cpu.operation(&opcode);
+        let pc = cpu.pc.current;
+
+        cpu.v[0x0] = 0x23;
         cpu.v[0x1] = cpu.v[0x0];
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(pc + 4, cpu.pc.current);
     }
 
     #[test]
     fn operation_5xy0_skip_equal_vx_vy_no_skip() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x5010);
         let op = cpu.operation(&opcode);
@@ -752,21 +1454,22 @@ mod tests {
         cpu.v[0x0] = 0x23;
         cpu.v[0x1] = 0x22;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(pc + 2, cpu.pc.current);
     }
 
     #[test]
     fn operation_6xkk_load_vx_byte() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x6123);
         let op = cpu.operation(&opcode);
         let pc = cpu.pc.current;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0x23, cpu.read_register(0x1));
         assert_eq!(pc + 2, cpu.pc.current);
     }
@@ -774,14 +1477,15 @@ mod tests {
     #[test]
     fn operation_7xkk_add_vx_byte() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x71FF);
         let op = cpu.operation(&opcode);
         let pc = cpu.pc.current;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0xFF, cpu.read_register(0x1));
         assert_eq!(pc + 2, cpu.pc.current);
     }
@@ -789,8 +1493,9 @@ mod tests {
     #[test]
     fn operation_7xkk_add_vx_byte_wrap() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x71FF);
         let op = cpu.operation(&opcode);
@@ -798,7 +1503,7 @@ mod tests {
 
         cpu.v[0x1] = 0x02;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0x01, cpu.read_register(0x1));
         assert_eq!(pc + 2, cpu.pc.current);
     }
@@ -806,8 +1511,9 @@ mod tests {
     #[test]
     fn operation_8xy0_load_vx_vy() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x8010);
         let op = cpu.operation(&opcode);
@@ -815,7 +1521,7 @@ mod tests {
 
         cpu.v[0x1] = 0x23;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0x23, cpu.read_register(0x0));
         assert_eq!(pc + 2, cpu.pc.current);
     }
@@ -823,8 +1529,9 @@ mod tests {
     #[test]
     fn operation_8xy1_or_vx_vy_00() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x8011);
         let op = cpu.operation(&opcode);
@@ -833,7 +1540,7 @@ mod tests {
         cpu.v[0x0] = 0x0;
         cpu.v[0x1] = 0x0;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0x0, cpu.read_register(0x0));
         assert_eq!(pc + 2, cpu.pc.current);
     }
@@ -841,8 +1548,9 @@ mod tests {
     #[test]
     fn operation_8xy1_or_vx_vy_01() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x8011);
         let op = cpu.operation(&opcode);
@@ -851,7 +1559,7 @@ mod tests {
         cpu.v[0x0] = 0x0;
         cpu.v[0x1] = 0x1;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0x1, cpu.read_register(0x0));
         assert_eq!(pc + 2, cpu.pc.current);
     }
@@ -859,8 +1567,9 @@ mod tests {
     #[test]
     fn operation_8xy1_or_vx_vy_10() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x8011);
         let op = cpu.operation(&opcode);
@@ -869,7 +1578,7 @@ mod tests {
         cpu.v[0x0] = 0x1;
         cpu.v[0x1] = 0x0;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0x1, cpu.read_register(0x0));
         assert_eq!(pc + 2, cpu.pc.current);
     }
@@ -877,8 +1586,9 @@ mod tests {
     #[test]
     fn operation_8xy1_or_vx_vy_11() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x8011);
         let op = cpu.operation(&opcode);
@@ -887,7 +1597,7 @@ mod tests {
         cpu.v[0x0] = 0x1;
         cpu.v[0x1] = 0x1;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0x1, cpu.read_register(0x0));
         assert_eq!(pc + 2, cpu.pc.current);
     }
@@ -895,8 +1605,9 @@ mod tests {
     #[test]
     fn operation_8xy2_and_vx_vy_00() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x8012);
         let op = cpu.operation(&opcode);
@@ -905,7 +1616,7 @@ mod tests {
         cpu.v[0x0] = 0x0;
         cpu.v[0x1] = 0x0;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0x0, cpu.read_register(0x0));
         assert_eq!(pc + 2, cpu.pc.current);
     }
@@ -913,8 +1624,9 @@ mod tests {
     #[test]
     fn operation_8xy2_and_vx_vy_01() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x8012);
         let op = cpu.operation(&opcode);
@@ -923,7 +1635,7 @@ mod tests {
         cpu.v[0x0] = 0x0;
         cpu.v[0x1] = 0x1;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0x0, cpu.read_register(0x0));
         assert_eq!(pc + 2, cpu.pc.current);
     }
@@ -931,8 +1643,9 @@ mod tests {
     #[test]
     fn operation_8xy2_and_vx_vy_10() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x8012);
         let op = cpu.operation(&opcode);
@@ -941,7 +1654,7 @@ mod tests {
         cpu.v[0x0] = 0x1;
         cpu.v[0x1] = 0x0;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0x0, cpu.read_register(0x0));
         assert_eq!(pc + 2, cpu.pc.current);
     }
@@ -949,8 +1662,9 @@ mod tests {
     #[test]
     fn operation_8xy2_and_vx_vy_11() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x8012);
         let op = cpu.operation(&opcode);
@@ -959,7 +1673,7 @@ mod tests {
         cpu.v[0x0] = 0x1;
         cpu.v[0x1] = 0x1;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0x1, cpu.read_register(0x0));
         assert_eq!(pc + 2, cpu.pc.current);
     }
@@ -967,8 +1681,9 @@ mod tests {
     #[test]
     fn operation_8xy3_xor_vx_vy_00() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x8013);
         let op = cpu.operation(&opcode);
@@ -977,7 +1692,7 @@ mod tests {
         cpu.v[0x0] = 0x0;
         cpu.v[0x1] = 0x0;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0x0, cpu.read_register(0x0));
         assert_eq!(pc + 2, cpu.pc.current);
     }
@@ -985,8 +1700,9 @@ mod tests {
     #[test]
     fn operation_8xy3_xor_vx_vy_01() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x8013);
         let op = cpu.operation(&opcode);
@@ -995,7 +1711,7 @@ mod tests {
         cpu.v[0x0] = 0x0;
         cpu.v[0x1] = 0x1;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0x1, cpu.read_register(0x0));
         assert_eq!(pc + 2, cpu.pc.current);
     }
@@ -1003,8 +1719,9 @@ mod tests {
     #[test]
     fn operation_8xy3_xor_vx_vy_10() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x8013);
         let op = cpu.operation(&opcode);
@@ -1013,7 +1730,7 @@ mod tests {
         cpu.v[0x0] = 0x1;
         cpu.v[0x1] = 0x0;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0x1, cpu.read_register(0x0));
         assert_eq!(pc + 2, cpu.pc.current);
     }
@@ -1021,8 +1738,9 @@ mod tests {
     #[test]
     fn operation_8xy3_xor_vx_vy_11() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x8013);
         let op = cpu.operation(&opcode);
@@ -1031,7 +1749,7 @@ mod tests {
         cpu.v[0x0] = 0x1;
         cpu.v[0x1] = 0x1;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0x0, cpu.read_register(0x0));
         assert_eq!(pc + 2, cpu.pc.current);
     }
@@ -1039,8 +1757,9 @@ mod tests {
     #[test]
     fn operation_8xy4_add_vx_vy() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x8014);
         let op = cpu.operation(&opcode);
@@ -1049,7 +1768,7 @@ mod tests {
         cpu.v[0x0] = 0x01;
         cpu.v[0x1] = 0x02;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0x03, cpu.read_register(0x0));
         assert_eq!(0b0, cpu.read_register(0xF));
         assert_eq!(pc + 2, cpu.pc.current);
@@ -1058,8 +1777,9 @@ mod tests {
     #[test]
     fn operation_8xy4_add_vx_vy_carry() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x8014);
         let op = cpu.operation(&opcode);
@@ -1068,7 +1788,7 @@ mod tests {
         cpu.v[0x0] = 0xFF;
         cpu.v[0x1] = 0x02;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0x01, cpu.read_register(0x0));
         assert_eq!(0b1, cpu.read_register(0xF));
         assert_eq!(pc + 2, cpu.pc.current);
@@ -1077,8 +1797,9 @@ mod tests {
     #[test]
     fn operation_8xy5_sub_vx_vy() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x8015);
         let op = cpu.operation(&opcode);
@@ -1087,7 +1808,7 @@ mod tests {
         cpu.v[0x0] = 0x02;
         cpu.v[0x1] = 0x01;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0x01, cpu.read_register(0x0));
         assert_eq!(0b1, cpu.read_register(0xF));
         assert_eq!(pc + 2, cpu.pc.current);
@@ -1096,8 +1817,9 @@ mod tests {
     #[test]
     fn operation_8xy5_sub_vx_vy_carry() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x8015);
         let op = cpu.operation(&opcode);
@@ -1106,7 +1828,7 @@ mod tests {
         cpu.v[0x0] = 0x01;
         cpu.v[0x1] = 0x02;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0xFF, cpu.read_register(0x0));
         assert_eq!(0b0, cpu.read_register(0xF));
         assert_eq!(pc + 2, cpu.pc.current);
@@ -1115,8 +1837,9 @@ mod tests {
     #[test]
     fn operation_8xy6_shr_vx_vy() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x8016);
         let op = cpu.operation(&opcode);
@@ -1124,7 +1847,7 @@ mod tests {
 
         cpu.v[0x1] = 0b11111110;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0b01111111, cpu.read_register(0x0));
         assert_eq!(0b0, cpu.read_register(0xF));
         assert_eq!(pc + 2, cpu.pc.current);
@@ -1133,8 +1856,9 @@ mod tests {
     #[test]
     fn operation_8xy6_shr_vx_vy_sig_bit() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x8016);
         let op = cpu.operation(&opcode);
@@ -1142,7 +1866,7 @@ mod tests {
 
         cpu.v[0x1] = 0b11111111;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0b01111111, cpu.read_register(0x0));
         assert_eq!(0b1, cpu.read_register(0xF));
         assert_eq!(pc + 2, cpu.pc.current);
@@ -1151,8 +1875,9 @@ mod tests {
     #[test]
     fn operation_8xy7_subn_vx_vy() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x8017);
         let op = cpu.operation(&opcode);
@@ -1161,7 +1886,7 @@ mod tests {
         cpu.v[0x0] = 0x01;
         cpu.v[0x1] = 0x02;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0x01, cpu.read_register(0x0));
         assert_eq!(0b1, cpu.read_register(0xF));
         assert_eq!(pc + 2, cpu.pc.current);
@@ -1170,8 +1895,9 @@ mod tests {
     #[test]
     fn operation_8xy7_subn_vx_vy_carry() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x8017);
         let op = cpu.operation(&opcode);
@@ -1180,7 +1906,7 @@ mod tests {
         cpu.v[0x0] = 0x02;
         cpu.v[0x1] = 0x01;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0xFF, cpu.read_register(0x0));
         assert_eq!(0b0, cpu.read_register(0xF));
         assert_eq!(pc + 2, cpu.pc.current);
@@ -1189,8 +1915,9 @@ mod tests {
     #[test]
     fn operation_8xye_shl_vx_vy() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x801E);
         let op = cpu.operation(&opcode);
@@ -1198,7 +1925,7 @@ mod tests {
 
         cpu.v[0x1] = 0b01111111;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0b11111110, cpu.read_register(0x0));
         assert_eq!(0b0, cpu.read_register(0xF));
         assert_eq!(pc + 2, cpu.pc.current);
@@ -1207,8 +1934,9 @@ mod tests {
     #[test]
     fn operation_8xye_shl_vx_vy_sig_bit() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x801E);
         let op = cpu.operation(&opcode);
@@ -1216,7 +1944,7 @@ mod tests {
 
         cpu.v[0x1] = 0b11111111;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0b11111110, cpu.read_register(0x0));
         assert_eq!(0b1, cpu.read_register(0xF));   
         assert_eq!(pc + 2, cpu.pc.current);     
@@ -1225,8 +1953,9 @@ mod tests {
     #[test]
     fn operation_9xy0_skip_not_equal_vx_vy() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x9010);
         let op = cpu.operation(&opcode);
@@ -1235,15 +1964,16 @@ mod tests {
         cpu.v[0x0] = 0x23;
         cpu.v[0x1] = 0x22;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(pc + 4, cpu.pc.current);
     }
 
     #[test]
     fn operation_9xy0_skip_not_equal_vx_vy_no_skip() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0x9010);
         let op = cpu.operation(&opcode);
@@ -1252,21 +1982,22 @@ mod tests {
         cpu.v[0x0] = 0x23;
         cpu.v[0x1] = cpu.v[0x0];
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(pc + 2, cpu.pc.current);
     }
 
     #[test]
     fn operation_annn_load_i_addr() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0xA456);
         let op = cpu.operation(&opcode);
         let pc = cpu.pc.current;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0x456, cpu.read_i());
         assert_eq!(pc + 2, cpu.pc.current);
     }
@@ -1274,23 +2005,25 @@ mod tests {
     #[test]
     fn operation_bnnn_jump_v0_addr() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0xB300);
         let op = cpu.operation(&opcode);
 
         cpu.v[0x0] = 0x08;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0x308, cpu.pc.current);
     }
 
     #[test]
     fn operation_cxkk_rand_vx_byte() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0xC00F);
         let op = cpu.operation(&opcode);
@@ -1298,30 +2031,49 @@ mod tests {
         for _ in 0..1000 {
             let pc = cpu.pc.current;
 
-            op(&mut cpu, &opcode);
+            op(&mut cpu, &opcode).unwrap();
             assert!(cpu.read_register(0x0) <= 0xF);
             assert_eq!(pc + 2, cpu.pc.current);
         }
     }
 
+    #[test]
+    fn operation_cxkk_rand_vx_byte_seeded() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        cpu.set_random(random::SeededRandom::new(1234));
+
+        let opcode = Opcode::new(0xC0FF);
+        let op = cpu.operation(&opcode);
+        let mut expected = random::SeededRandom::new(1234);
+
+        for _ in 0..10 {
+            op(&mut cpu, &opcode).unwrap();
+            assert_eq!(expected.next_byte(), cpu.read_register(0x0));
+        }
+    }
+
     #[test]
     fn operation_dxyn_draw_vx_vy_n() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0xD012);
         let op = cpu.operation(&opcode);
         let pc = cpu.pc.current;
 
-        cpu.load_i(ROM_RANGE.start);
+        cpu.load_i(ROM_RANGE.start).unwrap();
         let i = cpu.read_i();
         cpu.v[0] = 8;
         cpu.v[1] = 5;
-        cpu.memory[i + 0] = 0b11110000;
+        cpu.memory[i] = 0b11110000;
         cpu.memory[i + 1] = 0b00001111;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert!(cpu.graphics.read_pixel(8, 5));
         assert!(cpu.graphics.read_pixel(9, 5));
         assert!(cpu.graphics.read_pixel(10, 5));
@@ -1347,23 +2099,24 @@ mod tests {
     #[test]
     fn operation_dxyn_draw_vx_vy_n_collision() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0xD012);
         let op = cpu.operation(&opcode);
         let pc = cpu.pc.current;
 
-        cpu.load_i(ROM_RANGE.start);
+        cpu.load_i(ROM_RANGE.start).unwrap();
         let i = cpu.read_i();
         cpu.v[0] = 8;
         cpu.v[1] = 5;
-        cpu.memory[i + 0] = 0b11110000;
+        cpu.memory[i] = 0b11110000;
         cpu.memory[i + 1] = 0b00001111;
 
         cpu.graphics.update_pixel(12, 6, true);
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert!(cpu.graphics.read_pixel(8, 5));
         assert!(cpu.graphics.read_pixel(9, 5));
         assert!(cpu.graphics.read_pixel(10, 5));
@@ -1389,21 +2142,22 @@ mod tests {
     #[test]
     fn operation_dxyn_draw_vx_vy_n_with_vx_wrap() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0xD012);
         let op = cpu.operation(&opcode);
         let pc = cpu.pc.current;
 
-        cpu.load_i(ROM_RANGE.start);
+        cpu.load_i(ROM_RANGE.start).unwrap();
         let i = cpu.read_i();
         cpu.v[0] = 60;
         cpu.v[1] = 5;
-        cpu.memory[i + 0] = 0b11110000;
+        cpu.memory[i] = 0b11110000;
         cpu.memory[i + 1] = 0b00001111;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert!(cpu.graphics.read_pixel(60, 5));
         assert!(cpu.graphics.read_pixel(61, 5));
         assert!(cpu.graphics.read_pixel(62, 5));
@@ -1429,21 +2183,22 @@ mod tests {
     #[test]
     fn operation_dxyn_draw_vx_vy_n_with_vy_wrap() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0xD012);
         let op = cpu.operation(&opcode);
         let pc = cpu.pc.current;
 
-        cpu.load_i(ROM_RANGE.start);
+        cpu.load_i(ROM_RANGE.start).unwrap();
         let i = cpu.read_i();
         cpu.v[0] = 8;
         cpu.v[1] = 31;
-        cpu.memory[i + 0] = 0b11110000;
+        cpu.memory[i] = 0b11110000;
         cpu.memory[i + 1] = 0b00001111;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert!(cpu.graphics.read_pixel(8, 31));
         assert!(cpu.graphics.read_pixel(9, 31));
         assert!(cpu.graphics.read_pixel(10, 31));
@@ -1466,11 +2221,139 @@ mod tests {
         assert_eq!(pc + 2, cpu.pc.current);
     }
 
+    #[test]
+    fn operation_dxy0_draw_16x16_sprite() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::SuperChip, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0xD010);
+        let op = cpu.operation(&opcode);
+        let pc = cpu.pc.current;
+
+        cpu.graphics.set_high_res(true);
+        cpu.load_i(ROM_RANGE.start).unwrap();
+        let i = cpu.read_i();
+        cpu.v[0] = 100;
+        cpu.v[1] = 40;
+        for row in 0..16 {
+            cpu.memory[i + row * 2] = 0b10000000;
+            cpu.memory[i + row * 2 + 1] = 0b00000001;
+        }
+
+        op(&mut cpu, &opcode).unwrap();
+        assert!(cpu.graphics.read_pixel(100, 40));
+        assert!(cpu.graphics.read_pixel(115, 40));
+        assert!(cpu.graphics.read_pixel(100, 55));
+        assert!(cpu.graphics.read_pixel(115, 55));
+        assert!(!cpu.graphics.read_pixel(101, 40));
+        assert!(!cpu.graphics.read_pixel(100, 56));
+        assert_eq!(0b0, cpu.read_register(0xF));
+        assert_eq!(pc + 2, cpu.pc.current);
+    }
+
+    #[test]
+    fn operation_ex9e_skip_key_pressed_vx() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0xE09E);
+        let op = cpu.operation(&opcode);
+        let pc = cpu.pc.current;
+
+        cpu.v[0x0] = 0xA;
+        cpu.input.press(0xA);
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(pc + 4, cpu.pc.current);
+    }
+
+    #[test]
+    fn operation_ex9e_skip_key_pressed_vx_no_skip() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0xE09E);
+        let op = cpu.operation(&opcode);
+        let pc = cpu.pc.current;
+
+        cpu.v[0x0] = 0xA;
+        cpu.input.press(0xB);
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(pc + 2, cpu.pc.current);
+    }
+
+    #[test]
+    fn operation_exa1_skip_key_not_pressed_vx() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0xE0A1);
+        let op = cpu.operation(&opcode);
+        let pc = cpu.pc.current;
+
+        cpu.v[0x0] = 0xA;
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(pc + 4, cpu.pc.current);
+    }
+
+    #[test]
+    fn operation_exa1_skip_key_not_pressed_vx_no_skip() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0xE0A1);
+        let op = cpu.operation(&opcode);
+        let pc = cpu.pc.current;
+
+        cpu.v[0x0] = 0xA;
+        cpu.input.press(0xA);
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(pc + 2, cpu.pc.current);
+    }
+
+    #[test]
+    fn operation_fx0a_load_vx_key_waits_for_press_and_release() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0xF30A);
+        let op = cpu.operation(&opcode);
+        let pc = cpu.pc.current;
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(pc, cpu.pc.current, "should block while no key is pressed");
+
+        cpu.input.press(0x7);
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(pc, cpu.pc.current, "should block while the key is held");
+
+        cpu.input.release(0x7);
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(0x7, cpu.read_register(0x3));
+        assert_eq!(pc + 2, cpu.pc.current);
+    }
+
     #[test]
     fn operation_fx07_load_vx_dt() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0xF007);
         let op = cpu.operation(&opcode);
@@ -1479,7 +2362,7 @@ mod tests {
         let time = 0x20;
         cpu.dt.set(time);
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(time, cpu.read_register(0x0));
         assert_eq!(pc + 2, cpu.pc.current);
     }
@@ -1487,8 +2370,9 @@ mod tests {
     #[test]
     fn operation_fx15_load_dt_vx() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0xF015);
         let op = cpu.operation(&opcode);
@@ -1497,7 +2381,7 @@ mod tests {
         let time = 0x20;
         cpu.v[0x0] = time;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(time, cpu.read_delay_timer());
         assert_eq!(pc + 2, cpu.pc.current);
     }
@@ -1505,8 +2389,9 @@ mod tests {
     #[test]
     fn operation_fx18_load_st_vx() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0xF018);
         let op = cpu.operation(&opcode);
@@ -1515,16 +2400,17 @@ mod tests {
         let time = 0x20;
         cpu.v[0x0] = time;
 
-        op(&mut cpu, &opcode);
-        assert_eq!(time, cpu.read_sound_timer());
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(time, cpu.st.current);
         assert_eq!(pc + 2, cpu.pc.current);
     }
 
     #[test]
     fn operation_fx1e_add_i_vx() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0xF01E);
         let op = cpu.operation(&opcode);
@@ -1533,7 +2419,7 @@ mod tests {
 
         cpu.v[0x0] = 0x08;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(i + 0x08, cpu.read_i());
         assert_eq!(pc + 2, cpu.pc.current);
     }
@@ -1541,8 +2427,9 @@ mod tests {
     #[test]
     fn operation_fx29_load_i_vx_font() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0xF029);
         let op = cpu.operation(&opcode);
@@ -1550,16 +2437,37 @@ mod tests {
 
         cpu.v[0x0] = 0x02;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(FONT_RANGE.start + 10, cpu.read_i());
         assert_eq!(pc + 2, cpu.pc.current);
     }
 
+    #[test]
+    fn operation_fx30_load_i_vx_big_font() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::SuperChip, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0xF030);
+        let op = cpu.operation(&opcode);
+        let pc = cpu.pc.current;
+
+        cpu.v[0x0] = 0x02;
+
+        op(&mut cpu, &opcode).unwrap();
+        let i = cpu.read_i();
+        assert_eq!(BIG_FONT_ADDR + 20, i);
+        assert_eq!(font::BIG_FONT_SET[20..30].to_vec(), cpu.read_bytes(i, 10).unwrap());
+        assert_eq!(pc + 2, cpu.pc.current);
+    }
+
     #[test]
     fn operation_fx33_load_bcd_vx() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0xF033);
         let op = cpu.operation(&opcode);
@@ -1568,8 +2476,8 @@ mod tests {
 
         cpu.v[0x0] = 0xFE;
 
-        op(&mut cpu, &opcode);
-        assert_eq!(0x02, cpu.memory[i + 0]);
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(0x02, cpu.memory[i]);
         assert_eq!(0x05, cpu.memory[i + 1]);
         assert_eq!(0x04, cpu.memory[i + 2]);
         assert_eq!(pc + 2, cpu.pc.current);
@@ -1578,14 +2486,15 @@ mod tests {
     #[test]
     fn operation_fx55_load_through_vx() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0xF555);
         let op = cpu.operation(&opcode);
         let pc = cpu.pc.current;
 
-        cpu.load_i(ROM_RANGE.start);
+        cpu.load_i(ROM_RANGE.start).unwrap();
         let i = cpu.read_i();
         cpu.v[0] = 0xFF;
         cpu.v[1] = 0xEE;
@@ -1594,8 +2503,8 @@ mod tests {
         cpu.v[4] = 0xBB;
         cpu.v[5] = 0xAA;
 
-        op(&mut cpu, &opcode);
-        assert_eq!(0xFF, cpu.memory[i + 0]);
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(0xFF, cpu.memory[i]);
         assert_eq!(0xEE, cpu.memory[i + 1]);
         assert_eq!(0xDD, cpu.memory[i + 2]);
         assert_eq!(0xCC, cpu.memory[i + 3]);
@@ -1608,23 +2517,24 @@ mod tests {
     #[test]
     fn operation_fx65_read_through_vx() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
 
         let opcode = Opcode::new(0xF565);
         let op = cpu.operation(&opcode);
         let pc = cpu.pc.current;
 
-        cpu.load_i(ROM_RANGE.start);
+        cpu.load_i(ROM_RANGE.start).unwrap();
         let i = cpu.read_i();
-        cpu.memory[i + 0] = 0xFF;
+        cpu.memory[i] = 0xFF;
         cpu.memory[i + 1] = 0xEE;
         cpu.memory[i + 2] = 0xDD;
         cpu.memory[i + 3] = 0xCC;
         cpu.memory[i + 4] = 0xBB;
         cpu.memory[i + 5] = 0xAA;
 
-        op(&mut cpu, &opcode);
+        op(&mut cpu, &opcode).unwrap();
         assert_eq!(0xFF, cpu.read_register(0x0));
         assert_eq!(0xEE, cpu.read_register(0x1));
         assert_eq!(0xDD, cpu.read_register(0x2));
@@ -1636,11 +2546,753 @@ mod tests {
     }
 
     #[test]
-    fn beep_while_sound_timer_active() {
+    fn quirk_logic_reset_vf() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::cosmac_vip()).unwrap();
+
+        for code in &[0x8011, 0x8012, 0x8013] {
+            let opcode = Opcode::new(*code);
+            let op = cpu.operation(&opcode);
+            cpu.v[0xF] = 0x1;
+
+            op(&mut cpu, &opcode).unwrap();
+            assert_eq!(0x0, cpu.read_register(0xF), "{} should reset VF", opcode);
+        }
+    }
+
+    #[test]
+    fn quirk_shift_vx_shr() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::super_chip()).unwrap();
+
+        let opcode = Opcode::new(0x8016);
+        let op = cpu.operation(&opcode);
+
+        cpu.v[0x0] = 0b00000011;
+        cpu.v[0x1] = 0b11111110;
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(0b00000001, cpu.read_register(0x0));
+        assert_eq!(0b1, cpu.read_register(0xF));
+    }
+
+    #[test]
+    fn quirk_shift_vx_shl() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::super_chip()).unwrap();
+
+        let opcode = Opcode::new(0x801E);
+        let op = cpu.operation(&opcode);
+
+        cpu.v[0x0] = 0b01000000;
+        cpu.v[0x1] = 0b11111111;
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(0b10000000, cpu.read_register(0x0));
+        assert_eq!(0b0, cpu.read_register(0xF));
+    }
+
+    #[test]
+    fn quirk_jump_vx() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::chip48()).unwrap();
+
+        let opcode = Opcode::new(0xB300);
+        let op = cpu.operation(&opcode);
+
+        cpu.v[0x0] = 0x08;
+        cpu.v[0x3] = 0x10;
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(0x310, cpu.pc.current);
+    }
+
+    #[test]
+    fn quirk_index_increment_x() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::chip48()).unwrap();
+
+        let opcode = Opcode::new(0xF555);
+        let op = cpu.operation(&opcode);
+
+        cpu.load_i(ROM_RANGE.start).unwrap();
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(ROM_RANGE.start + 5, cpu.read_i());
+    }
+
+    #[test]
+    fn quirk_index_unchanged() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::super_chip()).unwrap();
+
+        let opcode = Opcode::new(0xF565);
+        let op = cpu.operation(&opcode);
+
+        cpu.load_i(ROM_RANGE.start).unwrap();
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(ROM_RANGE.start, cpu.read_i());
+    }
+
+    #[test]
+    fn quirk_clip_sprites() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::super_chip()).unwrap();
+
+        let opcode = Opcode::new(0xD012);
+        let op = cpu.operation(&opcode);
+
+        cpu.load_i(ROM_RANGE.start).unwrap();
+        let i = cpu.read_i();
+        cpu.v[0] = 60;
+        cpu.v[1] = 31;
+        cpu.memory[i] = 0b11111111;
+        cpu.memory[i + 1] = 0b11111111;
+
+        op(&mut cpu, &opcode).unwrap();
+        assert!(cpu.graphics.read_pixel(60, 31));
+        assert!(cpu.graphics.read_pixel(63, 31));
+        assert!(!cpu.graphics.read_pixel(0, 31));
+        assert!(!cpu.graphics.read_pixel(60, 0));
+        assert!(!cpu.graphics.read_pixel(0, 0));
+    }
+
+    #[test]
+    fn quirk_clip_sprites_wraps_start_position() {
         let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
         let rom = Vec::new();
-        let mut cpu = Cpu::new(&rom, &mut graphics);
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::super_chip()).unwrap();
+
+        let opcode = Opcode::new(0xD011);
+        let op = cpu.operation(&opcode);
+
+        cpu.load_i(ROM_RANGE.start).unwrap();
+        let i = cpu.read_i();
+        cpu.v[0] = 66;
+        cpu.v[1] = 33;
+        cpu.memory[i] = 0b10000000;
+
+        op(&mut cpu, &opcode).unwrap();
+        assert!(cpu.graphics.read_pixel(2, 1));
+    }
+
+    #[test]
+    fn quirk_display_wait() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::cosmac_vip()).unwrap();
+
+        let opcode = Opcode::new(0xD011);
+        let op = cpu.operation(&opcode);
+        let pc = cpu.pc.current;
+
+        cpu.load_i(ROM_RANGE.start).unwrap();
+        let i = cpu.read_i();
+        cpu.memory[i] = 0b10000000;
+        cpu.vblank();
+
+        op(&mut cpu, &opcode).unwrap();
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(pc, cpu.pc.current, "should wait for the next vblank");
+        assert!(!cpu.graphics.read_pixel(0, 0));
+
+        cpu.vblank();
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(pc + 2, cpu.pc.current);
+        assert!(cpu.graphics.read_pixel(0, 0));
+    }
+
+    #[test]
+    fn operation_fx75_fx85_store_and_read_flags() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::SuperChip, Quirks::default()).unwrap();
+
+        let store = Opcode::new(0xF275);
+        let read = Opcode::new(0xF285);
+        let pc = cpu.pc.current;
+
+        cpu.v[0x0] = 0xAA;
+        cpu.v[0x1] = 0xBB;
+        cpu.v[0x2] = 0xCC;
+        cpu.v[0x3] = 0xDD;
+
+        let op = cpu.operation(&store);
+        op(&mut cpu, &store).unwrap();
+        cpu.v = [0x0; NUM_REGISTERS];
+
+        let op = cpu.operation(&read);
+        op(&mut cpu, &read).unwrap();
+        assert_eq!(0xAA, cpu.read_register(0x0));
+        assert_eq!(0xBB, cpu.read_register(0x1));
+        assert_eq!(0xCC, cpu.read_register(0x2));
+        assert_eq!(0x00, cpu.read_register(0x3));
+        assert_eq!(pc + 4, cpu.pc.current);
+    }
+
+    #[test]
+    fn xo_chip_loads_rom_above_classic_memory() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = vec![0xAB; ROM_RANGE.len() + 2];
+        let cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::XoChip, Quirks::default()).unwrap();
+
+        assert_eq!(0xAB, cpu.memory[ROM_RANGE.end + 1]);
+        assert_eq!(XO_MAX_ADDR + STACK_SIZE, cpu.memory.len());
+    }
+
+    #[test]
+    fn xo_chip_i_cannot_reach_the_stack() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::XoChip, Quirks::default()).unwrap();
+        cpu.load_i(XO_MAX_ADDR - 1).unwrap();
+
+        let store = Opcode::new(0xF155);
+        let op = cpu.operation(&store);
+        match op(&mut cpu, &store) {
+            Err(Error::AddressOutOfRange { addr, range }) => {
+                assert_eq!(XO_MAX_ADDR, addr);
+                assert_eq!(0x0..XO_MAX_ADDR, range);
+            },
+            _ => panic!("expected Error::AddressOutOfRange")
+        }
+        assert_eq!(0x0, cpu.memory[XO_MAX_ADDR]);
+
+        let load = Opcode::new(0xF165);
+        let op = cpu.operation(&load);
+        assert!(op(&mut cpu, &load).is_err());
+    }
+
+    #[test]
+    fn operation_00dn_scroll_up_n() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::XoChip, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0x00D3);
+        let op = cpu.operation(&opcode);
+        let pc = cpu.pc.current;
+
+        cpu.graphics.update_pixel(4, 4, true);
+
+        op(&mut cpu, &opcode).unwrap();
+        assert!(cpu.graphics.read_pixel(4, 1));
+        assert!(!cpu.graphics.read_pixel(4, 4));
+        assert_eq!(pc + 2, cpu.pc.current);
+    }
+
+    #[test]
+    fn operation_00dn_unknown_on_super_chip() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::SuperChip, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0x00D3);
+        let op = cpu.operation(&opcode);
+
+        assert!(op(&mut cpu, &opcode).is_err());
+    }
+
+    #[test]
+    fn operation_5xy2_5xy3_save_and_read_range() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::XoChip, Quirks::default()).unwrap();
+
+        let save = Opcode::new(0x5242);
+        let read = Opcode::new(0x5423);
+        let pc = cpu.pc.current;
+
+        cpu.load_i(ROM_RANGE.start).unwrap();
+        let i = cpu.read_i();
+        cpu.v[0x2] = 0xAA;
+        cpu.v[0x3] = 0xBB;
+        cpu.v[0x4] = 0xCC;
+
+        let op = cpu.operation(&save);
+        op(&mut cpu, &save).unwrap();
+        assert_eq!(vec![0xAA, 0xBB, 0xCC], cpu.memory[i..i + 3].to_vec());
+        assert_eq!(i, cpu.read_i());
+
+        let op = cpu.operation(&read);
+        op(&mut cpu, &read).unwrap();
+        assert_eq!(0xAA, cpu.read_register(0x4));
+        assert_eq!(0xBB, cpu.read_register(0x3));
+        assert_eq!(0xCC, cpu.read_register(0x2));
+        assert_eq!(pc + 4, cpu.pc.current);
+    }
+
+    #[test]
+    fn operation_f000_load_i_long() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = vec![0xF0, 0x00, 0xAB, 0xCD];
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::XoChip, Quirks::default()).unwrap();
+
+        let pc = cpu.pc.current;
+        cpu.step().unwrap();
+        assert_eq!(0xABCD, cpu.read_i());
+        assert_eq!(pc + 4, cpu.pc.current);
+    }
+
+    #[test]
+    fn skip_over_f000_skips_both_words() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = vec![0x30, 0x00, 0xF0, 0x00, 0xAB, 0xCD];
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::XoChip, Quirks::default()).unwrap();
+
+        let pc = cpu.pc.current;
+        cpu.step().unwrap();
+        assert_eq!(pc + 6, cpu.pc.current);
+    }
+
+    #[test]
+    fn operation_fn01_select_planes() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::XoChip, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0xF301);
+        let op = cpu.operation(&opcode);
+        let pc = cpu.pc.current;
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(0b11, cpu.graphics.planes());
+        assert_eq!(pc + 2, cpu.pc.current);
+    }
+
+    #[test]
+    fn operation_dxyn_draws_each_selected_plane() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::XoChip, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0xD011);
+        let op = cpu.operation(&opcode);
+
+        cpu.graphics.select_planes(0b11);
+        cpu.load_i(ROM_RANGE.start).unwrap();
+        let i = cpu.read_i();
+        cpu.memory[i] = 0b10000000;
+        cpu.memory[i + 1] = 0b01000000;
+
+        op(&mut cpu, &opcode).unwrap();
+        assert!(cpu.graphics.read_plane_pixel(0, 0, 0));
+        assert!(!cpu.graphics.read_plane_pixel(1, 0, 0));
+        assert!(cpu.graphics.read_plane_pixel(1, 1, 0));
+        assert!(!cpu.graphics.read_plane_pixel(0, 1, 0));
+    }
+
+    #[test]
+    fn operation_f002_load_audio() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::XoChip, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0xF002);
+        let op = cpu.operation(&opcode);
+        let pc = cpu.pc.current;
+
+        cpu.load_i(ROM_RANGE.start).unwrap();
+        let i = cpu.read_i();
+        for n in 0..sound::PATTERN_SIZE {
+            cpu.memory[i + n] = n as Byte;
+        }
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(cpu.memory[i..i + sound::PATTERN_SIZE].to_vec(), cpu.audio().buffer.to_vec());
+        assert_eq!(pc + 2, cpu.pc.current);
+    }
+
+    #[test]
+    fn operation_fx3a_load_pitch_vx() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::XoChip, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0xF53A);
+        let op = cpu.operation(&opcode);
+        let pc = cpu.pc.current;
+
+        cpu.v[0x5] = 112;
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(112, cpu.audio().pitch);
+        assert_eq!(pc + 2, cpu.pc.current);
+    }
+
+    #[test]
+    fn chip8x_loads_rom_at_300() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = vec![0x12, 0x34];
+        let cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8X, Quirks::default()).unwrap();
+
+        assert_eq!(CHIP8X_ROM_START, cpu.pc.current);
+        assert_eq!(0x1234, cpu.fetch_opcode().code());
+    }
+
+    #[test]
+    fn operation_02a0_cycle_background() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8X, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0x02A0);
+        let op = cpu.operation(&opcode);
+        let pc = cpu.pc.current;
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(Colour::Black, cpu.graphics.background());
+        assert_eq!(pc + 2, cpu.pc.current);
+    }
+
+    #[test]
+    fn operation_5xy1_add_nibbles_vx_vy() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8X, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0x5121);
+        let op = cpu.operation(&opcode);
+        let pc = cpu.pc.current;
+
+        cpu.v[0x1] = 0x9C;
+        cpu.v[0x2] = 0x8A;
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(0x16, cpu.read_register(0x1));
+        assert_eq!(0x0, cpu.read_register(0xF));
+        assert_eq!(pc + 2, cpu.pc.current);
+    }
+
+    #[test]
+    fn operation_bxy0_colour_zones() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8X, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0xB240);
+        let op = cpu.operation(&opcode);
+        let pc = cpu.pc.current;
+
+        cpu.v[0x2] = 0x11;
+        cpu.v[0x3] = 0x02;
+        cpu.v[0x4] = 0x4;
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(Colour::Green, cpu.graphics.foreground(8, 8));
+        assert_eq!(Colour::Green, cpu.graphics.foreground(23, 11));
+        assert_eq!(Colour::Red, cpu.graphics.foreground(24, 8));
+        assert_eq!(Colour::Red, cpu.graphics.foreground(8, 12));
+        assert_eq!(Colour::Red, cpu.graphics.foreground(7, 8));
+        assert_eq!(pc + 2, cpu.pc.current);
+    }
+
+    #[test]
+    fn operation_bxyn_colour_rows() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8X, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0xB242);
+        let op = cpu.operation(&opcode);
+
+        cpu.v[0x2] = 20;
+        cpu.v[0x3] = 5;
+        cpu.v[0x4] = 0x7;
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(Colour::White, cpu.graphics.foreground(16, 5));
+        assert_eq!(Colour::White, cpu.graphics.foreground(23, 6));
+        assert_eq!(Colour::Red, cpu.graphics.foreground(20, 7));
+    }
+
+    #[test]
+    fn operation_bnnn_jumps_outside_chip8x() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+
+        let opcode = Opcode::new(0xB232);
+        let op = cpu.operation(&opcode);
+
+        op(&mut cpu, &opcode).unwrap();
+        assert_eq!(0x232, cpu.pc.current);
+        assert_eq!(Colour::Red, cpu.graphics.foreground(0, 0));
+    }
+
+    #[test]
+    fn operation_exf2_exf5_skip_second_keypad() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::DualKeypad::new();
+        keypad.second.press(0x6);
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8X, Quirks::default()).unwrap();
+
+        let pressed = Opcode::new(0xE1F2);
+        let not_pressed = Opcode::new(0xE1F5);
+        let pc = cpu.pc.current;
+
+        cpu.v[0x1] = 0x6;
+
+        let op = cpu.operation(&pressed);
+        op(&mut cpu, &pressed).unwrap();
+        assert_eq!(pc + 4, cpu.pc.current);
+
+        let op = cpu.operation(&not_pressed);
+        op(&mut cpu, &not_pressed).unwrap();
+        assert_eq!(pc + 6, cpu.pc.current);
+    }
+
+    #[test]
+    fn operation_fxf8_fxfb_io_ports() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8X, Quirks::default()).unwrap();
+
+        let output = Opcode::new(0xF4F8);
+        let input = Opcode::new(0xF5FB);
+        let pc = cpu.pc.current;
+
+        cpu.v[0x4] = 0x42;
+
+        let op = cpu.operation(&output);
+        op(&mut cpu, &output).unwrap();
+        assert_eq!(0x42, cpu.port_out);
+
+        let op = cpu.operation(&input);
+        op(&mut cpu, &input).unwrap();
+        assert_eq!(pc + 2, cpu.pc.current);
+
+        cpu.port_in = Some(0x99);
+        op(&mut cpu, &input).unwrap();
+        assert_eq!(0x99, cpu.read_register(0x5));
+        assert_eq!(None, cpu.port_in);
+        assert_eq!(pc + 4, cpu.pc.current);
+    }
+
+    #[test]
+    fn instruction_clock_counts_down_timers_per_frame() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = vec![0x12, 0x00];
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        cpu.set_clock(clock::InstructionClock::new(4));
+        cpu.dt.set(10);
+
+        for _ in 0..11 {
+            cpu.step().unwrap();
+        }
+        assert_eq!(8, cpu.read_delay_timer());
+        assert!(cpu.vblank);
+    }
+
+    #[test]
+    fn end_frame_ticks_timers_once() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        cpu.dt.set(5);
+
+        cpu.end_frame();
+        assert_eq!(4, cpu.read_delay_timer());
+        assert!(cpu.vblank);
+    }
+
+    #[test]
+    fn end_frame_clears_key_events() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        cpu.input_mut().press(0x5);
+        cpu.input_mut().release(0x5);
+        assert_eq!(2, cpu.input().events().len());
+
+        cpu.end_frame();
+        assert!(cpu.input().events().is_empty());
+    }
+
+    #[derive(Debug)]
+    struct Collect(Rc<RefCell<Vec<trace::TraceRecord>>>);
+
+    impl trace::TraceSink for Collect {
+        fn record(&mut self, record: &trace::TraceRecord) {
+            self.0.borrow_mut().push(record.clone());
+        }
+    }
+
+    #[test]
+    fn step_traces_registers_i_and_writes() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = vec![0x6A, 0x02, 0xA3, 0x00, 0xFA, 0x33];
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        let records = Rc::new(RefCell::new(Vec::new()));
+        cpu.set_trace(Collect(records.clone()));
+
+        for _ in 0..3 {
+            cpu.step().unwrap();
+        }
+
+        let records = records.borrow();
+        assert_eq!(3, records.len());
+        assert_eq!(ROM_RANGE.start, records[0].pc);
+        assert_eq!("LD VA, 02", records[0].instruction);
+        assert_eq!(0x0, records[0].v_before[0xA]);
+        assert_eq!(0x2, records[0].v_after[0xA]);
+        assert_eq!(0x300, records[1].i_after);
+        assert_eq!(vec![(0x300, 0), (0x301, 0), (0x302, 2)], records[2].writes);
+    }
+
+    #[test]
+    fn step_traces_only_filtered_instructions() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = vec![0x6A, 0x02, 0xA3, 0x00, 0xFA, 0x33];
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        let records = Rc::new(RefCell::new(Vec::new()));
+        cpu.set_trace(trace::Filter::new(Collect(records.clone())).classes(&[0xA, 0xF]).addresses(0x202..0x204));
+
+        for _ in 0..3 {
+            cpu.step().unwrap();
+        }
+
+        let records = records.borrow();
+        assert_eq!(1, records.len());
+        assert_eq!(0xA300, records[0].opcode);
+    }
+
+    #[test]
+    fn watchpoint_reports_bcd_writes() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = vec![0x6A, 0x7B, 0xA3, 0x00, 0xFA, 0x33];
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        cpu.add_watchpoint(watch::Watchpoint::new(0x301..0x303).write());
+
+        for _ in 0..3 {
+            cpu.step().unwrap();
+        }
+
+        let write = |addr, new| watch::WatchHit { pc: 0x204, addr, access: watch::Access::Write, old: 0x0, new };
+        assert_eq!(vec![write(0x301, 2), write(0x302, 3)], cpu.take_watch_hits());
+        assert!(cpu.watch_hits().is_empty());
+    }
+
+    #[test]
+    fn watchpoint_reports_register_stores() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = vec![0x61, 0x09, 0xA3, 0x00, 0xF1, 0x55];
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        cpu.write_memory(0x301, &[0x05]).unwrap();
+        cpu.add_watchpoint(watch::Watchpoint::new(0x301..0x302).write());
+
+        for _ in 0..3 {
+            cpu.step().unwrap();
+        }
+
+        let hit = watch::WatchHit { pc: 0x204, addr: 0x301, access: watch::Access::Write, old: 0x05, new: 0x09 };
+        assert_eq!(&[hit], cpu.watch_hits());
+    }
+
+    #[test]
+    fn watchpoint_reports_stack_push_and_pop() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = vec![0x22, 0x04, 0x00, 0x00, 0x00, 0xEE];
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        let top = STACK_RANGE.start + 2;
+        cpu.add_watchpoint(watch::Watchpoint::new(top..top + 2).read().write());
+
+        cpu.step().unwrap();
+        let hits = cpu.take_watch_hits();
+        assert_eq!(2, hits.len());
+        assert!(hits.iter().all(|hit| hit.pc == 0x200 && hit.access == watch::Access::Write));
+        assert_eq!((0x02, 0x00), (hits[0].new, hits[1].new));
+
+        cpu.step().unwrap();
+        let hits = cpu.take_watch_hits();
+        assert_eq!(2, hits.len());
+        assert!(hits.iter().all(|hit| hit.pc == 0x204 && hit.access == watch::Access::Read));
+    }
+
+    #[test]
+    fn watchpoint_reports_execution() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = vec![0x60, 0x01, 0x61, 0x02];
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        cpu.add_watchpoint(watch::Watchpoint::new(0x203..0x204).execute());
+
+        cpu.step().unwrap();
+        assert!(cpu.watch_hits().is_empty());
+        cpu.step().unwrap();
+        let hit = watch::WatchHit { pc: 0x202, addr: 0x203, access: watch::Access::Execute, old: 0x02, new: 0x02 };
+        assert_eq!(&[hit], cpu.watch_hits());
+    }
+
+    #[test]
+    fn debugger_writes_do_not_trigger_watchpoints() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        cpu.add_watchpoint(watch::Watchpoint::new(0x300..0x400).write());
+
+        cpu.write_memory(0x300, &[0xAA]).unwrap();
+        assert!(cpu.watch_hits().is_empty());
+        assert!(cpu.remove_watchpoint(0x3FF));
+        assert!(cpu.watchpoints().is_empty());
+    }
+
+    #[test]
+    fn beep_while_sound_timer_active() {
+        let mut graphics = graphics::Display::new();
+        let mut keypad = keypad::Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        assert!(!cpu.beep);
+        assert_eq!(0, cpu.read_delay_timer());
 
+        cpu.st.set(60);
         cpu.update_timers();
 
         assert!(cpu.st.active());
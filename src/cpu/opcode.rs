@@ -18,6 +18,10 @@ impl Opcode {
         }
     }
 
+    pub fn code(&self) -> u16 {
+        self.code
+    }
+
     pub fn first_hex_digit(&self) -> Byte {
         ((self.code & 0xF000) >> 12) as Byte
     }
@@ -68,8 +72,8 @@ mod tests {
             0xCCCC, 0xDDDD, 0xEEEE, 0xFFFF
         ];
 
-        for i in 0x0..0x10 {
-            let opcode = Opcode::new(codes[i]);
+        for (i, code) in codes.iter().enumerate() {
+            let opcode = Opcode::new(*code);
             assert_eq!(i as Byte, opcode.first_hex_digit());
         }
     }
@@ -83,8 +87,8 @@ mod tests {
             0x0C00, 0x0D00, 0x0E00, 0x0F00
         ];
 
-        for i in 0x0..0x10 {
-            let opcode = Opcode::new(codes[i]);
+        for (i, code) in codes.iter().enumerate() {
+            let opcode = Opcode::new(*code);
             assert_eq!(i as Address, opcode.x());
         }
     }
@@ -98,8 +102,8 @@ mod tests {
             0x00C0, 0x00D0, 0x00E0, 0x00F0
         ];
 
-        for i in 0x0..0x10 {
-            let opcode = Opcode::new(codes[i]);
+        for (i, code) in codes.iter().enumerate() {
+            let opcode = Opcode::new(*code);
             assert_eq!(i as Address, opcode.y());
         }
     }
@@ -122,6 +126,6 @@ mod tests {
     fn mask_k() {
         let code = 0xABCD;
         let opcode = Opcode::new(code);
-        assert_eq!(0xD as usize, opcode.k());
+        assert_eq!(0xD, opcode.k());
     }
 }
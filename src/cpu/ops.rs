@@ -1,40 +1,64 @@
 use cpu::opcode::Opcode;
+use error::Result;
 
 pub trait Operation {
-    fn no_op(&mut self, &Opcode);
-    fn unknown(&mut self, &Opcode);
-    fn clear_display(&mut self, &Opcode);
-    fn return_from_subroutine(&mut self, &Opcode);
-    fn jump_addr(&mut self, &Opcode);
-    fn call_addr(&mut self, &Opcode);
-    fn skip_equal_vx_byte(&mut self, &Opcode);
-    fn skip_not_equal_vx_byte(&mut self, &Opcode);
-    fn skip_equal_vx_vy(&mut self, &Opcode);
-    fn load_vx_byte(&mut self, &Opcode);
-    fn add_vx_byte(&mut self, &Opcode);
-    fn load_vx_vy(&mut self, &Opcode);
-    fn or_vx_vy(&mut self, &Opcode);
-    fn and_vx_vy(&mut self, &Opcode);
-    fn xor_vx_vy(&mut self, &Opcode);
-    fn add_vx_vy(&mut self, &Opcode);
-    fn sub_vx_vy(&mut self, &Opcode);
-    fn shr_vx_vy(&mut self, &Opcode);
-    fn subn_vx_vy(&mut self, &Opcode);
-    fn shl_vx_vy(&mut self, &Opcode);
-    fn skip_not_equal_vx_vy(&mut self, &Opcode);
-    fn load_i_addr(&mut self, &Opcode);
-    fn jump_v0_addr(&mut self, &Opcode);
-    fn rand_vx_byte(&mut self, &Opcode);
-    fn draw_vx_vy_n(&mut self, &Opcode);
-    fn skip_key_pressed_vx(&mut self, &Opcode);
-    fn skip_key_not_pressed_vx(&mut self, &Opcode);
-    fn load_vx_dt(&mut self, &Opcode);
-    fn load_vx_key(&mut self, &Opcode);
-    fn load_dt_vx(&mut self, &Opcode);
-    fn load_st_vx(&mut self, &Opcode);
-    fn add_i_vx(&mut self, &Opcode);
-    fn load_i_vx_font(&mut self, &Opcode);
-    fn load_bcd_vx(&mut self, &Opcode);
-    fn load_through_vx(&mut self, &Opcode);
-    fn read_through_vx(&mut self, &Opcode);
+    fn no_op(&mut self, opcode: &Opcode) -> Result<()>;
+    fn unknown(&mut self, opcode: &Opcode) -> Result<()>;
+    fn clear_display(&mut self, opcode: &Opcode) -> Result<()>;
+    fn return_from_subroutine(&mut self, opcode: &Opcode) -> Result<()>;
+    fn scroll_down_n(&mut self, opcode: &Opcode) -> Result<()>;
+    fn scroll_up_n(&mut self, opcode: &Opcode) -> Result<()>;
+    fn scroll_right(&mut self, opcode: &Opcode) -> Result<()>;
+    fn scroll_left(&mut self, opcode: &Opcode) -> Result<()>;
+    fn exit_interpreter(&mut self, opcode: &Opcode) -> Result<()>;
+    fn low_res(&mut self, opcode: &Opcode) -> Result<()>;
+    fn high_res(&mut self, opcode: &Opcode) -> Result<()>;
+    fn cycle_background(&mut self, opcode: &Opcode) -> Result<()>;
+    fn jump_addr(&mut self, opcode: &Opcode) -> Result<()>;
+    fn call_addr(&mut self, opcode: &Opcode) -> Result<()>;
+    fn skip_equal_vx_byte(&mut self, opcode: &Opcode) -> Result<()>;
+    fn skip_not_equal_vx_byte(&mut self, opcode: &Opcode) -> Result<()>;
+    fn skip_equal_vx_vy(&mut self, opcode: &Opcode) -> Result<()>;
+    fn add_nibbles_vx_vy(&mut self, opcode: &Opcode) -> Result<()>;
+    fn save_vx_vy(&mut self, opcode: &Opcode) -> Result<()>;
+    fn read_vx_vy(&mut self, opcode: &Opcode) -> Result<()>;
+    fn load_vx_byte(&mut self, opcode: &Opcode) -> Result<()>;
+    fn add_vx_byte(&mut self, opcode: &Opcode) -> Result<()>;
+    fn load_vx_vy(&mut self, opcode: &Opcode) -> Result<()>;
+    fn or_vx_vy(&mut self, opcode: &Opcode) -> Result<()>;
+    fn and_vx_vy(&mut self, opcode: &Opcode) -> Result<()>;
+    fn xor_vx_vy(&mut self, opcode: &Opcode) -> Result<()>;
+    fn add_vx_vy(&mut self, opcode: &Opcode) -> Result<()>;
+    fn sub_vx_vy(&mut self, opcode: &Opcode) -> Result<()>;
+    fn shr_vx_vy(&mut self, opcode: &Opcode) -> Result<()>;
+    fn subn_vx_vy(&mut self, opcode: &Opcode) -> Result<()>;
+    fn shl_vx_vy(&mut self, opcode: &Opcode) -> Result<()>;
+    fn skip_not_equal_vx_vy(&mut self, opcode: &Opcode) -> Result<()>;
+    fn load_i_addr(&mut self, opcode: &Opcode) -> Result<()>;
+    fn jump_v0_addr(&mut self, opcode: &Opcode) -> Result<()>;
+    fn colour_vx_vy_n(&mut self, opcode: &Opcode) -> Result<()>;
+    fn rand_vx_byte(&mut self, opcode: &Opcode) -> Result<()>;
+    fn draw_vx_vy_n(&mut self, opcode: &Opcode) -> Result<()>;
+    fn skip_key_pressed_vx(&mut self, opcode: &Opcode) -> Result<()>;
+    fn skip_key_not_pressed_vx(&mut self, opcode: &Opcode) -> Result<()>;
+    fn skip_second_key_pressed_vx(&mut self, opcode: &Opcode) -> Result<()>;
+    fn skip_second_key_not_pressed_vx(&mut self, opcode: &Opcode) -> Result<()>;
+    fn load_i_long(&mut self, opcode: &Opcode) -> Result<()>;
+    fn select_planes(&mut self, opcode: &Opcode) -> Result<()>;
+    fn load_audio(&mut self, opcode: &Opcode) -> Result<()>;
+    fn load_vx_dt(&mut self, opcode: &Opcode) -> Result<()>;
+    fn load_vx_key(&mut self, opcode: &Opcode) -> Result<()>;
+    fn load_dt_vx(&mut self, opcode: &Opcode) -> Result<()>;
+    fn load_st_vx(&mut self, opcode: &Opcode) -> Result<()>;
+    fn add_i_vx(&mut self, opcode: &Opcode) -> Result<()>;
+    fn load_i_vx_font(&mut self, opcode: &Opcode) -> Result<()>;
+    fn load_i_vx_big_font(&mut self, opcode: &Opcode) -> Result<()>;
+    fn load_bcd_vx(&mut self, opcode: &Opcode) -> Result<()>;
+    fn load_pitch_vx(&mut self, opcode: &Opcode) -> Result<()>;
+    fn load_through_vx(&mut self, opcode: &Opcode) -> Result<()>;
+    fn read_through_vx(&mut self, opcode: &Opcode) -> Result<()>;
+    fn output_port_vx(&mut self, opcode: &Opcode) -> Result<()>;
+    fn input_port_vx(&mut self, opcode: &Opcode) -> Result<()>;
+    fn store_flags_vx(&mut self, opcode: &Opcode) -> Result<()>;
+    fn read_flags_vx(&mut self, opcode: &Opcode) -> Result<()>;
 }
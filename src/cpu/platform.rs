@@ -0,0 +1,184 @@
+use std::ops::Range;
+
+use cpu::{CHIP8X_ROM_START, FONT_RANGE, MAX_ADDR, ROM_RANGE, STACK_RANGE, STACK_SIZE, XO_MAX_ADDR};
+use cpu::quirks::Quirks;
+use {Address, Byte};
+
+/// The CHIP-8 dialect whose instruction set the interpreter accepts.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Platform {
+    #[default]
+    Chip8,
+    SuperChip,
+    XoChip,
+    Chip8X
+}
+
+impl Platform {
+    pub fn from_name(name: &str) -> Option<Platform> {
+        match name.to_lowercase().as_str() {
+            "chip8" | "chip-8" => Some(Platform::Chip8),
+            "schip" | "superchip" | "super-chip" => Some(Platform::SuperChip),
+            "xochip" | "xo-chip" => Some(Platform::XoChip),
+            "chip8x" | "chip-8x" => Some(Platform::Chip8X),
+            _ => None
+        }
+    }
+
+    /// Guesses the platform from a ROM's file extension, as archives name
+    /// them: `.ch8`, `.sc8`, `.xo8` and `.c8x`.
+    pub fn from_extension(extension: &str) -> Option<Platform> {
+        match extension.to_lowercase().as_str() {
+            "ch8" | "c8" => Some(Platform::Chip8),
+            "sc8" => Some(Platform::SuperChip),
+            "xo8" => Some(Platform::XoChip),
+            "c8x" => Some(Platform::Chip8X),
+            _ => None
+        }
+    }
+
+    /// A stable number for the platform, used in save states and across FFI.
+    pub fn id(&self) -> Byte {
+        match *self {
+            Platform::Chip8 => 0,
+            Platform::SuperChip => 1,
+            Platform::XoChip => 2,
+            Platform::Chip8X => 3
+        }
+    }
+
+    pub fn from_id(id: Byte) -> Option<Platform> {
+        match id {
+            0 => Some(Platform::Chip8),
+            1 => Some(Platform::SuperChip),
+            2 => Some(Platform::XoChip),
+            3 => Some(Platform::Chip8X),
+            _ => None
+        }
+    }
+
+    pub fn super_chip(&self) -> bool {
+        match *self {
+            Platform::Chip8 | Platform::Chip8X => false,
+            Platform::SuperChip | Platform::XoChip => true
+        }
+    }
+
+    pub fn xo_chip(&self) -> bool {
+        *self == Platform::XoChip
+    }
+
+    pub fn chip8x(&self) -> bool {
+        *self == Platform::Chip8X
+    }
+
+    pub fn default_quirks(&self) -> Quirks {
+        match *self {
+            Platform::Chip8 | Platform::Chip8X => Quirks::cosmac_vip(),
+            Platform::SuperChip => Quirks::super_chip(),
+            Platform::XoChip => Quirks::octo()
+        }
+    }
+
+    /// XO-CHIP keeps the call stack above its 64 KiB address space so the
+    /// whole space is available to I.
+    pub fn memory_size(&self) -> usize {
+        match *self {
+            Platform::XoChip => self.address_space() + STACK_SIZE,
+            _ => self.address_space()
+        }
+    }
+
+    /// The memory a program can reach through I.
+    pub fn address_space(&self) -> usize {
+        match *self {
+            Platform::XoChip => XO_MAX_ADDR,
+            _ => MAX_ADDR
+        }
+    }
+
+    pub fn rom_range(&self) -> Range<Address> {
+        match *self {
+            Platform::XoChip => ROM_RANGE.start..XO_MAX_ADDR,
+            Platform::Chip8X => CHIP8X_ROM_START..ROM_RANGE.end,
+            _ => ROM_RANGE
+        }
+    }
+
+    pub fn stack_range(&self) -> Range<Address> {
+        match *self {
+            Platform::XoChip => XO_MAX_ADDR..XO_MAX_ADDR + STACK_SIZE,
+            _ => STACK_RANGE
+        }
+    }
+
+    pub fn i_range(&self) -> Range<Address> {
+        FONT_RANGE.start..self.rom_range().end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_name_finds_platforms() {
+        assert_eq!(Some(Platform::Chip8), Platform::from_name("CHIP-8"));
+        assert_eq!(Some(Platform::SuperChip), Platform::from_name("schip"));
+        assert_eq!(Some(Platform::XoChip), Platform::from_name("xo-chip"));
+        assert_eq!(Some(Platform::Chip8X), Platform::from_name("CHIP-8X"));
+        assert_eq!(None, Platform::from_name("chip-9"));
+    }
+
+    #[test]
+    fn from_extension_matches_rom_archives() {
+        assert_eq!(Some(Platform::Chip8), Platform::from_extension("CH8"));
+        assert_eq!(Some(Platform::SuperChip), Platform::from_extension("sc8"));
+        assert_eq!(Some(Platform::XoChip), Platform::from_extension("xo8"));
+        assert_eq!(None, Platform::from_extension("bin"));
+    }
+
+    #[test]
+    fn ids_round_trip() {
+        for &platform in &[Platform::Chip8, Platform::SuperChip, Platform::XoChip, Platform::Chip8X] {
+            assert_eq!(Some(platform), Platform::from_id(platform.id()));
+        }
+        assert_eq!(None, Platform::from_id(4));
+    }
+
+    #[test]
+    fn only_super_chip_and_xo_chip_have_super_chip_instructions() {
+        assert!(!Platform::Chip8.super_chip());
+        assert!(Platform::SuperChip.super_chip());
+        assert!(Platform::XoChip.super_chip());
+        assert!(!Platform::SuperChip.xo_chip());
+        assert!(!Platform::Chip8X.super_chip());
+    }
+
+    #[test]
+    fn chip8x_loads_programs_at_300() {
+        let p = Platform::Chip8X;
+        assert_eq!(MAX_ADDR, p.memory_size());
+        assert_eq!(0x300..ROM_RANGE.end, p.rom_range());
+        assert_eq!(STACK_RANGE, p.stack_range());
+    }
+
+    #[test]
+    fn classic_memory_layout() {
+        let p = Platform::Chip8;
+        assert_eq!(MAX_ADDR, p.memory_size());
+        assert_eq!(ROM_RANGE, p.rom_range());
+        assert_eq!(STACK_RANGE, p.stack_range());
+        assert_eq!(0x0..ROM_RANGE.end, p.i_range());
+    }
+
+    #[test]
+    fn xo_chip_memory_layout() {
+        let p = Platform::XoChip;
+        assert_eq!(0x10000 + STACK_SIZE, p.memory_size());
+        assert_eq!(0x10000, p.address_space());
+        assert_eq!(0x200..0x10000, p.rom_range());
+        assert_eq!(0x10000..0x10000 + STACK_SIZE, p.stack_range());
+        assert_eq!(0x0..0x10000, p.i_range());
+    }
+}
@@ -1,6 +1,7 @@
 use std::fmt;
 use std::ops::Range;
 
+use error::{Error, Result};
 use Address;
 
 #[derive(Clone, PartialEq)]
@@ -19,33 +20,31 @@ impl Pointer {
         }
     }
 
-    pub fn move_forward(&mut self) {
+    pub fn move_forward(&mut self) -> Result<()> {
         let next = self.next();
-        self.set(next);
+        self.set(next)
     }
 
-    pub fn move_backward(&mut self) {
+    pub fn move_backward(&mut self) -> Result<()> {
         let prev = self.prev();
-        self.set(prev);
+        self.set(prev)
     }
 
-    pub fn set(&mut self, addr: Address) {
+    pub fn set(&mut self, addr: Address) -> Result<()> {
         if !self.range.contains(&addr) {
-            panic!(
-                "Address {:x} out of pointer range ({:x}..{:x})",
-                addr, self.range.start, self.range.end
-            );
-        } else {
-            self.current = addr;
+            return Err(Error::AddressOutOfRange { addr, range: self.range.clone() });
         }
+
+        self.current = addr;
+        Ok(())
     }
 
     fn next(&self) -> Address {
-        self.current + self.step_size
+        self.current.wrapping_add(self.step_size)
     }
 
     fn prev(&self) -> Address {
-        self.current - self.step_size
+        self.current.wrapping_sub(self.step_size)
     }
 }
 
@@ -74,7 +73,7 @@ mod tests {
     fn move_forward_adds_2_to_current() {
         let mut p = Pointer::new(TEST_RANGE);
 
-        p.move_forward();
+        p.move_forward().unwrap();
         assert_eq!(TEST_RANGE.start + 2, p.current);
     }
 
@@ -83,7 +82,7 @@ mod tests {
         let mut p = Pointer::new(TEST_RANGE);
         p.current = TEST_RANGE.end;
 
-        p.move_backward();
+        p.move_backward().unwrap();
         assert_eq!(TEST_RANGE.end - 2, p.current);
     }
 
@@ -92,7 +91,23 @@ mod tests {
         let mut p = Pointer::new(TEST_RANGE);
         let addr = 0xABC;
 
-        p.set(addr);
+        p.set(addr).unwrap();
         assert_eq!(addr, p.current);
     }
+
+    #[test]
+    fn set_out_of_range_errors() {
+        let mut p = Pointer::new(TEST_RANGE);
+
+        assert!(p.set(TEST_RANGE.end).is_err());
+        assert_eq!(TEST_RANGE.start, p.current);
+    }
+
+    #[test]
+    fn move_backward_past_start_errors() {
+        let mut p = Pointer::new(0x0..0x10);
+
+        assert!(p.move_backward().is_err());
+        assert_eq!(0x0, p.current);
+    }
 }
@@ -0,0 +1,104 @@
+/// How FX55 and FX65 leave I after storing or loading registers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IndexIncrement {
+    XPlusOne,
+    X,
+    Unchanged
+}
+
+/// Switches for instructions whose behaviour differs between interpreters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quirks {
+    /// 8XY6 and 8XYE shift VX in place instead of loading VY shifted.
+    pub shift_vx: bool,
+    pub index_increment: IndexIncrement,
+    /// BNNN jumps to XNN + VX instead of NNN + V0.
+    pub jump_vx: bool,
+    /// DXYN clips sprites at the screen edge instead of wrapping them.
+    pub clip_sprites: bool,
+    /// 8XY1, 8XY2 and 8XY3 reset VF to 0.
+    pub logic_reset_vf: bool,
+    /// DXYN waits for the next vertical blank before drawing.
+    pub display_wait: bool
+}
+
+impl Quirks {
+    pub fn cosmac_vip() -> Quirks {
+        Quirks {
+            shift_vx: false,
+            index_increment: IndexIncrement::XPlusOne,
+            jump_vx: false,
+            clip_sprites: true,
+            logic_reset_vf: true,
+            display_wait: true
+        }
+    }
+
+    pub fn chip48() -> Quirks {
+        Quirks {
+            shift_vx: true,
+            index_increment: IndexIncrement::X,
+            jump_vx: true,
+            clip_sprites: true,
+            logic_reset_vf: false,
+            display_wait: false
+        }
+    }
+
+    pub fn super_chip() -> Quirks {
+        Quirks {
+            shift_vx: true,
+            index_increment: IndexIncrement::Unchanged,
+            jump_vx: true,
+            clip_sprites: true,
+            logic_reset_vf: false,
+            display_wait: false
+        }
+    }
+
+    pub fn octo() -> Quirks {
+        Quirks {
+            shift_vx: false,
+            index_increment: IndexIncrement::XPlusOne,
+            jump_vx: false,
+            clip_sprites: false,
+            logic_reset_vf: false,
+            display_wait: false
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Quirks> {
+        match name.to_lowercase().as_str() {
+            "vip" | "cosmac" | "cosmac-vip" => Some(Quirks::cosmac_vip()),
+            "chip48" | "chip-48" => Some(Quirks::chip48()),
+            "schip" | "superchip" | "super-chip" => Some(Quirks::super_chip()),
+            "octo" | "modern" => Some(Quirks::octo()),
+            _ => None
+        }
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Quirks {
+        Quirks::octo()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_is_octo() {
+        assert_eq!(Quirks::octo(), Quirks::default());
+    }
+
+    #[test]
+    fn from_name_finds_presets() {
+        assert_eq!(Some(Quirks::cosmac_vip()), Quirks::from_name("VIP"));
+        assert_eq!(Some(Quirks::chip48()), Quirks::from_name("chip-48"));
+        assert_eq!(Some(Quirks::super_chip()), Quirks::from_name("schip"));
+        assert_eq!(Some(Quirks::octo()), Quirks::from_name("modern"));
+        assert_eq!(None, Quirks::from_name("chip-9"));
+    }
+}
@@ -0,0 +1,161 @@
+extern crate rand;
+
+use std::fmt;
+
+use self::rand::Rng;
+use Byte;
+
+/// Source of the bytes CXKK masks into VX.
+pub trait Random: fmt::Debug {
+    fn next_byte(&mut self) -> Byte;
+
+    /// The generator's current seed. Reseeding a generator with it
+    /// continues the same sequence, which is how save states resume it.
+    fn seed(&self) -> u64;
+
+    fn reseed(&mut self, seed: u64);
+}
+
+/// Draws from the thread-local generator seeded by the operating system.
+/// It has no seed of its own, so its sequence is not saved.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OsRandom;
+
+impl OsRandom {
+    pub fn new() -> OsRandom {
+        OsRandom
+    }
+}
+
+impl Random for OsRandom {
+    fn next_byte(&mut self) -> Byte {
+        rand::thread_rng().gen::<Byte>()
+    }
+
+    fn seed(&self) -> u64 {
+        0
+    }
+
+    fn reseed(&mut self, _seed: u64) {}
+}
+
+/// SplitMix64, for runs that must repeat exactly.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeededRandom {
+    state: u64
+}
+
+impl SeededRandom {
+    pub fn new(seed: u64) -> SeededRandom {
+        SeededRandom {
+            state: seed
+        }
+    }
+}
+
+impl Random for SeededRandom {
+    fn next_byte(&mut self) -> Byte {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        (z ^ (z >> 31)) as Byte
+    }
+
+    fn seed(&self) -> u64 {
+        self.state
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.state = seed;
+    }
+}
+
+/// A small generator that adds a counter to its previous result and
+/// rotates it. It is cheap and predictable, but successive values are
+/// visibly correlated, so prefer `SeededRandom` for anything that should
+/// look random.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CounterRandom {
+    counter: u8,
+    last: Byte
+}
+
+impl CounterRandom {
+    pub fn new() -> CounterRandom {
+        CounterRandom::default()
+    }
+}
+
+impl Random for CounterRandom {
+    fn next_byte(&mut self) -> Byte {
+        self.counter = self.counter.wrapping_add(1);
+        let sum = self.last.wrapping_add(self.counter);
+        self.last = sum.rotate_right(1) ^ self.counter;
+        self.last
+    }
+
+    fn seed(&self) -> u64 {
+        (self.counter as u64) << 8 | self.last as u64
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.counter = (seed >> 8) as Byte;
+        self.last = seed as Byte;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(r: &mut dyn Random, n: usize) -> Vec<Byte> {
+        (0..n).map(|_| r.next_byte()).collect()
+    }
+
+    #[test]
+    fn seeded_random_repeats_for_same_seed() {
+        let mut a = SeededRandom::new(42);
+        let mut b = SeededRandom::new(42);
+        let mut c = SeededRandom::new(43);
+
+        let first = bytes(&mut a, 32);
+        assert_eq!(first, bytes(&mut b, 32));
+        assert_ne!(first, bytes(&mut c, 32));
+    }
+
+    #[test]
+    fn seeded_random_covers_full_range() {
+        let mut r = SeededRandom::new(7);
+        let mut seen = [false; 256];
+        for byte in bytes(&mut r, 10000) {
+            seen[byte as usize] = true;
+        }
+        assert!(seen.iter().all(|seen| *seen));
+    }
+
+    #[test]
+    fn reseed_with_seed_resumes_sequence() {
+        for r in [&mut SeededRandom::new(1) as &mut dyn Random, &mut CounterRandom::new()] {
+            bytes(r, 5);
+            let seed = r.seed();
+            let expected = bytes(r, 5);
+
+            r.reseed(seed);
+            assert_eq!(expected, bytes(r, 5));
+        }
+    }
+
+    #[test]
+    fn os_random_reaches_0xff() {
+        let mut r = OsRandom::new();
+        assert!(bytes(&mut r, 10000).contains(&0xFF));
+    }
+
+    #[test]
+    fn counter_random_is_deterministic() {
+        let mut a = CounterRandom::new();
+        let mut b = CounterRandom::new();
+        assert_eq!(bytes(&mut a, 16), bytes(&mut b, 16));
+    }
+}
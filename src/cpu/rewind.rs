@@ -0,0 +1,280 @@
+use std::collections::VecDeque;
+
+use cpu::Cpu;
+use error::Result;
+use input::Input;
+use output::graphics::GraphicsOutput;
+use Byte;
+
+/// A backward delta: XORing it over a snapshot gives the snapshot recorded
+/// before it. Runs of unchanged bytes are stored as counts, so a frame that
+/// only touched a few registers costs a few bytes.
+#[derive(Clone, Debug, PartialEq)]
+struct Delta {
+    len: usize,
+    runs: Vec<Byte>
+}
+
+impl Delta {
+    /// Encodes the change from `newer` back to `older` as alternating
+    /// (unchanged count, changed count, changed bytes) records.
+    fn between(older: &[Byte], newer: &[Byte]) -> Delta {
+        let size = older.len().max(newer.len());
+        let xor: Vec<Byte> = (0..size)
+            .map(|i| older.get(i).unwrap_or(&0x0) ^ newer.get(i).unwrap_or(&0x0))
+            .collect();
+
+        let mut runs = Vec::new();
+        let mut pos = 0;
+        while pos < size {
+            let same = xor[pos..].iter().take_while(|b| **b == 0x0).count();
+            pos += same;
+            let changed = xor[pos..].iter().take_while(|b| **b != 0x0).count();
+            write_count(&mut runs, same);
+            write_count(&mut runs, changed);
+            runs.extend_from_slice(&xor[pos..pos + changed]);
+            pos += changed;
+        }
+        Delta { len: older.len(), runs }
+    }
+
+    fn apply(&self, newer: &[Byte]) -> Vec<Byte> {
+        let mut older = newer.to_vec();
+        let mut pos = 0;
+        let mut i = 0;
+        while i < self.runs.len() {
+            let same = read_count(&self.runs, &mut i);
+            let changed = read_count(&self.runs, &mut i);
+            pos += same;
+            if older.len() < pos + changed { older.resize(pos + changed, 0x0); }
+            for (byte, xor) in older[pos..pos + changed].iter_mut().zip(&self.runs[i..i + changed]) {
+                *byte ^= xor;
+            }
+            pos += changed;
+            i += changed;
+        }
+        older.resize(self.len, 0x0);
+        older
+    }
+
+    fn size(&self) -> usize {
+        self.runs.len()
+    }
+}
+
+/// Writes `count` as a little-endian base-128 varint.
+fn write_count(out: &mut Vec<Byte>, mut count: usize) {
+    while count >= 0x80 {
+        out.push((count as Byte & 0x7F) | 0x80);
+        count >>= 7;
+    }
+    out.push(count as Byte);
+}
+
+fn read_count(runs: &[Byte], i: &mut usize) -> usize {
+    let mut count = 0;
+    let mut shift = 0;
+    loop {
+        let byte = runs[*i];
+        *i += 1;
+        count |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 { return count; }
+        shift += 7;
+    }
+}
+
+/// Ring buffer of save states for stepping a game backwards. The newest
+/// snapshot is kept whole and older ones as deltas against their successor,
+/// dropping the oldest once either limit is reached.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rewind {
+    max_snapshots: usize,
+    max_bytes: usize,
+    latest: Option<Vec<Byte>>,
+    deltas: VecDeque<Delta>,
+    delta_bytes: usize
+}
+
+impl Rewind {
+    pub fn new(max_snapshots: usize, max_bytes: usize) -> Rewind {
+        Rewind {
+            max_snapshots,
+            max_bytes,
+            latest: None,
+            deltas: VecDeque::new(),
+            delta_bytes: 0
+        }
+    }
+
+    pub fn push(&mut self, snapshot: Vec<Byte>) {
+        if let Some(latest) = self.latest.take() {
+            let delta = Delta::between(&latest, &snapshot);
+            self.delta_bytes += delta.size();
+            self.deltas.push_back(delta);
+        }
+        self.latest = Some(snapshot);
+
+        while self.len() > self.max_snapshots.max(1) || (self.bytes() > self.max_bytes && !self.deltas.is_empty()) {
+            if let Some(oldest) = self.deltas.pop_front() {
+                self.delta_bytes -= oldest.size();
+            }
+        }
+    }
+
+    /// Steps back `n` snapshots, or as far as the buffer reaches, and
+    /// returns the snapshot arrived at. It stays the newest snapshot, so
+    /// recording resumes from there.
+    pub fn rewind(&mut self, n: usize) -> Option<&[Byte]> {
+        for _ in 0..n {
+            let delta = match self.deltas.pop_back() {
+                Some(delta) => delta,
+                None => break
+            };
+            self.delta_bytes -= delta.size();
+            let older = delta.apply(self.latest.as_ref()?);
+            self.latest = Some(older);
+        }
+        self.latest.as_deref()
+    }
+
+    /// Snapshots the machine; call once per frame.
+    pub fn record<G, I>(&mut self, cpu: &Cpu<G, I>) where G: GraphicsOutput, I: Input {
+        self.push(cpu.save_state());
+    }
+
+    /// Rewinds `n` snapshots and restores the machine to the result.
+    /// Returns whether there was anything to restore.
+    pub fn restore<G, I>(&mut self, cpu: &mut Cpu<G, I>, n: usize) -> Result<bool> where G: GraphicsOutput, I: Input {
+        match self.rewind(n) {
+            Some(snapshot) => { cpu.load_state(snapshot)?; Ok(true) },
+            None => Ok(false)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.deltas.len() + self.latest.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.latest.is_none()
+    }
+
+    /// Memory used by the stored snapshots.
+    pub fn bytes(&self) -> usize {
+        self.delta_bytes + self.latest.as_ref().map_or(0, |latest| latest.len())
+    }
+
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+        self.delta_bytes = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cpu::platform::Platform;
+    use cpu::quirks::Quirks;
+    use input::keypad::Keypad;
+    use output::graphics::Display;
+
+    #[test]
+    fn delta_round_trips_changed_and_resized_data() {
+        let older = vec![1, 2, 3, 4, 5, 6];
+        let newer = vec![1, 9, 3, 4, 0, 6, 7, 8];
+
+        assert_eq!(older, Delta::between(&older, &newer).apply(&newer));
+        assert_eq!(newer, Delta::between(&newer, &older).apply(&older));
+    }
+
+    #[test]
+    fn delta_of_long_unchanged_run_is_small() {
+        let older = vec![0xAA; 5000];
+        let mut newer = older.clone();
+        newer[4000] = 0xAB;
+
+        let delta = Delta::between(&older, &newer);
+        assert!(delta.size() < 8);
+        assert_eq!(older, delta.apply(&newer));
+    }
+
+    #[test]
+    fn rewind_steps_back_through_snapshots() {
+        let mut r = Rewind::new(10, 1000);
+        r.push(vec![0, 0, 0]);
+        r.push(vec![1, 0, 0]);
+        r.push(vec![1, 2, 0]);
+        r.push(vec![1, 2, 3]);
+
+        assert_eq!(Some(&[1, 2, 0][..]), r.rewind(1));
+        assert_eq!(Some(&[0, 0, 0][..]), r.rewind(2));
+        assert_eq!(1, r.len());
+    }
+
+    #[test]
+    fn rewind_past_oldest_stops_at_oldest() {
+        let mut r = Rewind::new(10, 1000);
+        assert_eq!(None, r.rewind(1));
+
+        r.push(vec![1]);
+        r.push(vec![2]);
+        assert_eq!(Some(&[1][..]), r.rewind(5));
+    }
+
+    #[test]
+    fn push_after_rewind_resumes_from_rewound_snapshot() {
+        let mut r = Rewind::new(10, 1000);
+        r.push(vec![1]);
+        r.push(vec![2]);
+        r.push(vec![3]);
+
+        r.rewind(1);
+        r.push(vec![4]);
+        assert_eq!(Some(&[2][..]), r.rewind(1));
+        assert_eq!(Some(&[1][..]), r.rewind(1));
+    }
+
+    #[test]
+    fn push_drops_oldest_past_capacity() {
+        let mut r = Rewind::new(3, 1000);
+        for n in 0..5 {
+            r.push(vec![n]);
+        }
+
+        assert_eq!(3, r.len());
+        assert_eq!(Some(&[2][..]), r.rewind(10));
+    }
+
+    #[test]
+    fn push_drops_oldest_past_byte_budget() {
+        let mut r = Rewind::new(100, 64);
+        for n in 0..50 {
+            r.push(vec![n; 16]);
+        }
+
+        assert!(r.bytes() <= 64);
+        assert!(r.len() > 1);
+        assert!(r.len() < 50);
+    }
+
+    #[test]
+    fn restore_rewinds_cpu() {
+        let mut graphics = Display::new();
+        let mut keypad = Keypad::new();
+        let rom = vec![0x70, 0x01, 0x12, 0x00];
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        let mut r = Rewind::new(100, 0x10000);
+
+        for _ in 0..10 {
+            r.record(&cpu);
+            cpu.step().unwrap();
+            cpu.step().unwrap();
+        }
+        assert_eq!(10, cpu.read_register(0x0));
+
+        assert!(r.restore(&mut cpu, 3).unwrap());
+        assert_eq!(6, cpu.read_register(0x0));
+        assert!(r.bytes() < 10 * cpu.save_state().len());
+    }
+}
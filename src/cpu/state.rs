@@ -0,0 +1,662 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use cpu::{Cpu, NUM_REGISTERS};
+use cpu::platform::Platform;
+use cpu::pointer::Pointer;
+use cpu::quirks::{IndexIncrement, Quirks};
+use error::{Error, Result};
+use input::Input;
+use memory::Memory;
+use output::colour::{self, Colour};
+use output::graphics::{self, GraphicsOutput};
+use output::sound;
+use {Address, Byte};
+
+const MAGIC: &[Byte; 4] = b"RCH8";
+
+/// Version written by `Cpu::save_state`. Bump it when the layout changes
+/// and read the new fields only when the loaded version has them, so old
+/// saves stay loadable.
+pub const VERSION: u16 = 4;
+
+const COLOUR_COLUMNS: usize = graphics::SCREEN_WIDTH / graphics::COLOUR_ZONE_WIDTH;
+
+struct Writer {
+    bytes: Vec<Byte>
+}
+
+impl Writer {
+    fn new() -> Writer {
+        Writer {
+            bytes: Vec::new()
+        }
+    }
+
+    fn byte(&mut self, val: Byte) {
+        self.bytes.push(val);
+    }
+
+    fn bool(&mut self, val: bool) {
+        self.byte(val as Byte);
+    }
+
+    fn u16(&mut self, val: u16) {
+        self.bytes.extend_from_slice(&val.to_le_bytes());
+    }
+
+    fn u32(&mut self, val: u32) {
+        self.bytes.extend_from_slice(&val.to_le_bytes());
+    }
+
+    fn u64(&mut self, val: u64) {
+        self.bytes.extend_from_slice(&val.to_le_bytes());
+    }
+
+    fn address(&mut self, val: Address) {
+        self.u32(val as u32);
+    }
+
+    fn slice(&mut self, val: &[Byte]) {
+        self.u32(val.len() as u32);
+        self.bytes.extend_from_slice(val);
+    }
+
+    /// Packs one bit per pixel, most significant bit first.
+    fn bits<T>(&mut self, bits: T) where T: Iterator<Item = bool> {
+        let bits: Vec<bool> = bits.collect();
+        self.u32(bits.len() as u32);
+        for chunk in bits.chunks(8) {
+            let byte = chunk.iter().enumerate()
+                .fold(0x0, |acc, (i, bit)| if *bit { acc | 0b10000000 >> i } else { acc });
+            self.byte(byte);
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [Byte],
+    pos: usize
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [Byte]) -> Reader<'a> {
+        Reader {
+            bytes,
+            pos: 0
+        }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [Byte]> {
+        if self.bytes.len() - self.pos < n {
+            return Err(Error::BadSaveState("unexpected end of data"));
+        }
+        let taken = &self.bytes[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<Byte> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool> {
+        match self.byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(Error::BadSaveState("invalid flag"))
+        }
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        let mut bytes = [0x0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn address(&mut self) -> Result<Address> {
+        Ok(self.u32()? as Address)
+    }
+
+    fn slice(&mut self) -> Result<&'a [Byte]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    fn bits(&mut self) -> Result<Vec<bool>> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len.div_ceil(8))?;
+        Ok((0..len).map(|i| bytes[i / 8] & (0b10000000 >> (i % 8)) != 0).collect())
+    }
+
+    fn finish(&self) -> Result<()> {
+        if self.pos != self.bytes.len() {
+            return Err(Error::BadSaveState("trailing data"));
+        }
+        Ok(())
+    }
+}
+
+fn write_quirks(w: &mut Writer, quirks: &Quirks) {
+    w.bool(quirks.shift_vx);
+    w.byte(match quirks.index_increment {
+        IndexIncrement::XPlusOne => 0,
+        IndexIncrement::X => 1,
+        IndexIncrement::Unchanged => 2
+    });
+    w.bool(quirks.jump_vx);
+    w.bool(quirks.clip_sprites);
+    w.bool(quirks.logic_reset_vf);
+    w.bool(quirks.display_wait);
+}
+
+fn read_quirks(r: &mut Reader) -> Result<Quirks> {
+    Ok(Quirks {
+        shift_vx: r.bool()?,
+        index_increment: match r.byte()? {
+            0 => IndexIncrement::XPlusOne,
+            1 => IndexIncrement::X,
+            2 => IndexIncrement::Unchanged,
+            _ => return Err(Error::BadSaveState("unknown index increment"))
+        },
+        jump_vx: r.bool()?,
+        clip_sprites: r.bool()?,
+        logic_reset_vf: r.bool()?,
+        display_wait: r.bool()?
+    })
+}
+
+fn read_pointer(r: &mut Reader, mut pointer: Pointer) -> Result<Pointer> {
+    pointer.set(r.address()?)?;
+    Ok(pointer)
+}
+
+/// The display as stored in a save state. It is read in full before any of
+/// it is applied so a bad save leaves the display untouched.
+struct Screen {
+    high_res: bool,
+    planes: Byte,
+    pixels: Vec<Vec<bool>>,
+    colours: Vec<Colour>,
+    background: Colour,
+    /// Stored at the end of the save from version 4 on; older saves leave
+    /// it to be inferred from the colours.
+    coloured: Option<bool>
+}
+
+impl Screen {
+    /// Colour zones are always stored at low resolution, so in high
+    /// resolution each zone is addressed through the pixels it covers.
+    fn colour_scale(high_res: bool) -> usize {
+        if high_res { 2 } else { 1 }
+    }
+
+    fn save<G>(w: &mut Writer, graphics: &G) where G: GraphicsOutput {
+        let (width, height) = (graphics.width(), graphics.height());
+        w.bool(graphics.high_res());
+        w.byte(graphics.planes());
+        for plane in 0..graphics::NUM_PLANES {
+            w.bits((0..width * height).map(|i| graphics.read_plane_pixel(plane, i % width, i / width)));
+        }
+
+        let scale = Screen::colour_scale(graphics.high_res());
+        for y in 0..graphics::SCREEN_HEIGHT {
+            for column in 0..COLOUR_COLUMNS {
+                let x = column * graphics::COLOUR_ZONE_WIDTH;
+                w.byte(graphics.foreground(x * scale, y * scale) as Byte);
+            }
+        }
+        w.byte(graphics.background() as Byte);
+    }
+
+    fn read(r: &mut Reader) -> Result<Screen> {
+        let high_res = r.bool()?;
+        let planes = r.byte()?;
+        let size = if high_res {
+            graphics::HIRES_SCREEN_WIDTH * graphics::HIRES_SCREEN_HEIGHT
+        } else {
+            graphics::SCREEN_WIDTH * graphics::SCREEN_HEIGHT
+        };
+
+        let mut pixels = Vec::new();
+        for _ in 0..graphics::NUM_PLANES {
+            let plane = r.bits()?;
+            if plane.len() != size {
+                return Err(Error::BadSaveState("display size does not match resolution"));
+            }
+            pixels.push(plane);
+        }
+
+        let colours = r.take(COLOUR_COLUMNS * graphics::SCREEN_HEIGHT)?
+            .iter().map(|byte| Colour::from_byte(*byte)).collect();
+        let background = Colour::from_byte(r.byte()?);
+        if !colour::BACKGROUND_CYCLE.contains(&background) {
+            return Err(Error::BadSaveState("invalid background colour"));
+        }
+
+        Ok(Screen { high_res, planes, pixels, colours, background, coloured: None })
+    }
+
+    /// Only colours that differ from the display's are set, so restoring
+    /// a program that never used colour does not turn colour on. The
+    /// stored flag, where there is one, then overrides that guess.
+    fn restore<G>(&self, graphics: &mut G) where G: GraphicsOutput {
+        graphics.set_high_res(self.high_res);
+        let width = graphics.width();
+        for (plane, pixels) in self.pixels.iter().enumerate() {
+            for (i, pixel) in pixels.iter().enumerate() {
+                if *pixel { graphics.update_plane_pixel(plane, i % width, i / width, true); }
+            }
+        }
+        graphics.select_planes(self.planes);
+
+        let scale = Screen::colour_scale(self.high_res);
+        for (i, colour) in self.colours.iter().enumerate() {
+            let x = (i % COLOUR_COLUMNS) * graphics::COLOUR_ZONE_WIDTH;
+            let y = i / COLOUR_COLUMNS;
+            if graphics.foreground(x * scale, y * scale) != *colour {
+                graphics.set_foreground(x * scale, y * scale, *colour);
+            }
+        }
+        while graphics.background() != self.background {
+            graphics.cycle_background();
+        }
+        if let Some(coloured) = self.coloured { graphics.set_coloured(coloured); }
+    }
+}
+
+impl<'a, G, I> Cpu<'a, G, I> where G: GraphicsOutput, I: Input {
+    /// Serializes the whole machine, including the display and keypad, to
+    /// a versioned binary snapshot.
+    pub fn save_state(&self) -> Vec<Byte> {
+        let mut w = Writer::new();
+        w.bytes.extend_from_slice(MAGIC);
+        w.u16(VERSION);
+
+        w.byte(self.platform.id());
+        write_quirks(&mut w, &self.quirks);
+        w.bool(self.exit);
+        w.bool(self.beep);
+        w.address(self.pc.current);
+        w.address(self.sp.current);
+        w.address(self.i.current);
+        w.byte(self.dt.current);
+        w.byte(self.st.current);
+        w.slice(&self.v);
+        w.slice(&self.rpl);
+        w.slice(&self.audio.buffer);
+        w.byte(self.audio.pitch);
+        w.slice(&self.memory);
+        w.bool(self.key_wait.is_some());
+        w.byte(self.key_wait.unwrap_or(0x0));
+        w.bool(self.vblank);
+        w.bool(self.vblank_wait);
+        w.byte(self.port_out);
+        w.bool(self.port_in.is_some());
+        w.byte(self.port_in.unwrap_or(0x0));
+        w.u16(self.input.state());
+        Screen::save(&mut w, &*self.graphics);
+        w.u64(self.random.seed());
+        w.u32(self.clock.progress());
+        w.u16(self.input.second_state());
+        w.bool(self.graphics.coloured());
+        w.bytes
+    }
+
+    /// Restores a snapshot made by `save_state`. Nothing is changed if the
+    /// snapshot is malformed or from a newer version.
+    pub fn load_state(&mut self, data: &[Byte]) -> Result<()> {
+        let mut r = Reader::new(data);
+        if r.take(MAGIC.len())? != MAGIC {
+            return Err(Error::BadSaveState("missing header"));
+        }
+        let version = r.u16()?;
+        if version == 0 || version > VERSION {
+            return Err(Error::UnsupportedSaveVersion(version));
+        }
+
+        let platform = Platform::from_id(r.byte()?).ok_or(Error::BadSaveState("unknown platform"))?;
+        let quirks = read_quirks(&mut r)?;
+        let exit = r.bool()?;
+        let beep = r.bool()?;
+        let pc = read_pointer(&mut r, Pointer::new(platform.rom_range()))?;
+        let sp = read_pointer(&mut r, Pointer::new(platform.stack_range()))?;
+        let i = read_pointer(&mut r, Pointer::new(platform.i_range()))?;
+        let dt = r.byte()?;
+        let st = r.byte()?;
+        let mut v = [0x0; NUM_REGISTERS];
+        let mut rpl = [0x0; NUM_REGISTERS];
+        let mut audio = sound::AudioPattern::new();
+        copy_exact(&mut v, r.slice()?)?;
+        copy_exact(&mut rpl, r.slice()?)?;
+        copy_exact(&mut audio.buffer, r.slice()?)?;
+        audio.pitch = r.byte()?;
+        let data = r.slice()?;
+        if data.len() != platform.memory_size() {
+            return Err(Error::BadSaveState("memory size does not match platform"));
+        }
+        let mut memory = Memory::new(data.len(), 0x0);
+        memory.load(data, 0x0..data.len())?;
+        let key_wait = optional(r.bool()?, r.byte()?);
+        let vblank = r.bool()?;
+        let vblank_wait = r.bool()?;
+        let port_out = r.byte()?;
+        let port_in = optional(r.bool()?, r.byte()?);
+        let keys = r.u16()?;
+        let mut screen = Screen::read(&mut r)?;
+        let seed = if version >= 2 { Some(r.u64()?) } else { None };
+        let (progress, second_keys) = if version >= 3 { (Some(r.u32()?), Some(r.u16()?)) } else { (None, None) };
+        if version >= 4 { screen.coloured = Some(r.bool()?); }
+        r.finish()?;
+
+        self.platform = platform;
+        self.quirks = quirks;
+        self.exit = exit;
+        self.beep = beep;
+        self.pc = pc;
+        self.sp = sp;
+        self.i = i;
+        self.dt.set(dt);
+        self.st.set(st);
+        self.v = v;
+        self.rpl = rpl;
+        self.audio = audio;
+        memory.set_watchpoints(self.memory.watchpoints().to_vec());
+        self.memory = memory;
+        self.key_wait = key_wait;
+        self.vblank = vblank;
+        self.vblank_wait = vblank_wait;
+        self.port_out = port_out;
+        self.port_in = port_in;
+        self.input.set_state(keys);
+        if let Some(second_keys) = second_keys { self.input.set_second_state(second_keys); }
+        screen.restore(self.graphics);
+        if let Some(seed) = seed { self.random.reseed(seed); }
+        if let Some(progress) = progress { self.clock.set_progress(progress); }
+        Ok(())
+    }
+
+    pub fn save_state_file<P>(&self, path: P) -> Result<()> where P: AsRef<Path> {
+        let mut file = File::create(path)?;
+        file.write_all(&self.save_state())?;
+        Ok(())
+    }
+
+    pub fn load_state_file<P>(&mut self, path: P) -> Result<()> where P: AsRef<Path> {
+        let mut data = Vec::new();
+        File::open(path)?.read_to_end(&mut data)?;
+        self.load_state(&data)
+    }
+}
+
+fn copy_exact(dest: &mut [Byte], src: &[Byte]) -> Result<()> {
+    if dest.len() != src.len() {
+        return Err(Error::BadSaveState("unexpected field length"));
+    }
+    dest.copy_from_slice(src);
+    Ok(())
+}
+
+fn optional(present: bool, val: Byte) -> Option<Byte> {
+    if present { Some(val) } else { None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cpu::clock::InstructionClock;
+    use cpu::random::SeededRandom;
+    use input::keypad::{DualKeypad, Keypad};
+    use output::graphics::Display;
+
+    fn busy_cpu<'a>(graphics: &'a mut Display, keypad: &'a mut Keypad) -> Cpu<'a, Display, Keypad> {
+        let rom = vec![0x60, 0x2A, 0xA0, 0x00, 0x22, 0x08, 0x00, 0x00, 0xD0, 0x15, 0x00, 0xEE];
+        let mut cpu = Cpu::new(&rom, graphics, keypad, Platform::SuperChip, Quirks::super_chip()).unwrap();
+        for _ in 0..4 {
+            cpu.step().unwrap();
+        }
+        cpu.rpl[0x3] = 0x77;
+        cpu.dt.set(30);
+        cpu.key_wait = Some(0x4);
+        cpu.graphics.set_foreground(16, 2, Colour::Aqua);
+        cpu.graphics.cycle_background();
+        cpu.input.press(0xA);
+        cpu
+    }
+
+    #[test]
+    fn save_and_load_round_trips() {
+        let mut graphics = Display::new();
+        let mut keypad = Keypad::new();
+        let cpu = busy_cpu(&mut graphics, &mut keypad);
+        let state = cpu.save_state();
+
+        let mut other_graphics = Display::new();
+        let mut other_keypad = Keypad::new();
+        let rom = Vec::new();
+        let mut other = Cpu::new(&rom, &mut other_graphics, &mut other_keypad, Platform::Chip8, Quirks::default()).unwrap();
+        other.load_state(&state).unwrap();
+
+        assert_eq!(state, other.save_state());
+        assert_eq!(cpu.platform, other.platform);
+        assert_eq!(cpu.quirks, other.quirks);
+        assert_eq!(cpu.pc, other.pc);
+        assert_eq!(cpu.sp, other.sp);
+        assert_eq!(cpu.i, other.i);
+        assert_eq!(cpu.v, other.v);
+        assert_eq!(cpu.rpl, other.rpl);
+        assert_eq!(cpu.memory, other.memory);
+        assert_eq!(30, other.dt.current);
+        assert_eq!(Some(0x4), other.key_wait);
+        assert!(other.input.key_pressed(0xA));
+        assert_eq!(Colour::Aqua, other.graphics.foreground(20, 2));
+        assert_eq!(Colour::Black, other.graphics.background());
+        assert!(other.graphics.read_pixel(0x2A, 0));
+    }
+
+    #[test]
+    fn load_plain_state_keeps_display_uncoloured() {
+        let mut graphics = Display::new();
+        let mut keypad = Keypad::new();
+        let rom = Vec::new();
+        let state = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap().save_state();
+
+        let mut other_graphics = Display::new();
+        let mut other_keypad = Keypad::new();
+        Cpu::new(&rom, &mut other_graphics, &mut other_keypad, Platform::Chip8, Quirks::default()).unwrap()
+            .load_state(&state).unwrap();
+        assert!(!other_graphics.coloured());
+    }
+
+    #[test]
+    fn save_and_load_keeps_colour_set_to_the_default() {
+        let mut graphics = Display::new();
+        let mut keypad = Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8X, Platform::Chip8X.default_quirks()).unwrap();
+        cpu.graphics_mut().set_foreground(0, 0, colour::DEFAULT_FOREGROUND);
+        let state = cpu.save_state();
+
+        let mut other_graphics = Display::new();
+        let mut other_keypad = Keypad::new();
+        Cpu::new(&rom, &mut other_graphics, &mut other_keypad, Platform::Chip8X, Platform::Chip8X.default_quirks()).unwrap()
+            .load_state(&state).unwrap();
+        assert!(other_graphics.coloured());
+    }
+
+    #[test]
+    fn load_plain_state_turns_colour_off() {
+        let mut graphics = Display::new();
+        let mut keypad = Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8X, Platform::Chip8X.default_quirks()).unwrap();
+        let state = cpu.save_state();
+
+        cpu.graphics_mut().cycle_background();
+        cpu.load_state(&state).unwrap();
+        assert!(!cpu.graphics().coloured());
+    }
+
+    #[test]
+    fn load_version_3_infers_colour() {
+        let mut graphics = Display::new();
+        let mut keypad = Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8X, Platform::Chip8X.default_quirks()).unwrap();
+        cpu.graphics_mut().set_foreground(0, 0, Colour::Aqua);
+
+        let mut state = cpu.save_state();
+        state.truncate(state.len() - 1);
+        state[4..6].copy_from_slice(&3u16.to_le_bytes());
+        let mut other_graphics = Display::new();
+        let mut other_keypad = Keypad::new();
+        Cpu::new(&rom, &mut other_graphics, &mut other_keypad, Platform::Chip8X, Platform::Chip8X.default_quirks()).unwrap()
+            .load_state(&state).unwrap();
+        assert!(other_graphics.coloured());
+    }
+
+    #[test]
+    fn save_starts_with_header_and_version() {
+        let mut graphics = Display::new();
+        let mut keypad = Keypad::new();
+        let rom = Vec::new();
+        let cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+
+        let state = cpu.save_state();
+        assert_eq!(MAGIC, &state[0..4]);
+        assert_eq!(VERSION.to_le_bytes(), state[4..6]);
+    }
+
+    #[test]
+    fn save_and_load_resumes_random_sequence() {
+        let mut graphics = Display::new();
+        let mut keypad = Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        cpu.set_random(SeededRandom::new(99));
+        cpu.random.next_byte();
+
+        let state = cpu.save_state();
+        let expected: Vec<Byte> = (0..4).map(|_| cpu.random.next_byte()).collect();
+
+        cpu.load_state(&state).unwrap();
+        let resumed: Vec<Byte> = (0..4).map(|_| cpu.random.next_byte()).collect();
+        assert_eq!(expected, resumed);
+    }
+
+    #[test]
+    fn load_version_1_keeps_random_sequence() {
+        let mut graphics = Display::new();
+        let mut keypad = Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        cpu.set_random(SeededRandom::new(5));
+
+        let mut state = cpu.save_state();
+        state.truncate(state.len() - 15);
+        state[4..6].copy_from_slice(&1u16.to_le_bytes());
+        cpu.random.next_byte();
+        let seed = cpu.random.seed();
+
+        cpu.load_state(&state).unwrap();
+        assert_eq!(seed, cpu.random.seed());
+    }
+
+    #[test]
+    fn save_and_load_resumes_frame_progress() {
+        let mut graphics = Display::new();
+        let mut keypad = Keypad::new();
+        // Add 1 to V0 forever.
+        let rom = vec![0x70, 0x01, 0x12, 0x00];
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        cpu.set_clock(InstructionClock::new(4));
+        cpu.dt.set(10);
+        for _ in 0..3 {
+            cpu.step().unwrap();
+        }
+
+        let state = cpu.save_state();
+        cpu.set_clock(InstructionClock::new(4));
+        cpu.load_state(&state).unwrap();
+        cpu.step().unwrap();
+        assert_eq!(9, cpu.read_delay_timer());
+    }
+
+    #[test]
+    fn save_and_load_keeps_second_keypad() {
+        let mut graphics = Display::new();
+        let mut keypad = DualKeypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8X, Platform::Chip8X.default_quirks()).unwrap();
+        cpu.input_mut().first.press(0x1);
+        cpu.input_mut().second.press(0x2);
+
+        let state = cpu.save_state();
+        cpu.input_mut().first.release_all();
+        cpu.input_mut().second.release_all();
+        cpu.load_state(&state).unwrap();
+        assert!(cpu.input().key_pressed(0x1));
+        assert!(cpu.input().second_key_pressed(0x2));
+        assert!(!cpu.input().second_key_pressed(0x1));
+    }
+
+    #[test]
+    fn load_version_2_keeps_frame_progress() {
+        let mut graphics = Display::new();
+        let mut keypad = Keypad::new();
+        let rom = vec![0x70, 0x01, 0x12, 0x00];
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        cpu.set_clock(InstructionClock::new(4));
+
+        let mut state = cpu.save_state();
+        state.truncate(state.len() - 7);
+        state[4..6].copy_from_slice(&2u16.to_le_bytes());
+        cpu.step().unwrap();
+
+        cpu.load_state(&state).unwrap();
+        assert_eq!(1, cpu.clock.progress());
+    }
+
+    #[test]
+    fn load_newer_version_errors() {
+        let mut graphics = Display::new();
+        let mut keypad = Keypad::new();
+        let rom = Vec::new();
+        let mut cpu = Cpu::new(&rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+
+        let mut state = cpu.save_state();
+        state[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        match cpu.load_state(&state) {
+            Err(Error::UnsupportedSaveVersion(version)) => assert_eq!(VERSION + 1, version),
+            _ => panic!("expected Error::UnsupportedSaveVersion")
+        }
+    }
+
+    #[test]
+    fn load_truncated_state_errors_and_leaves_cpu_unchanged() {
+        let mut graphics = Display::new();
+        let mut keypad = Keypad::new();
+        let state = busy_cpu(&mut graphics, &mut keypad).save_state();
+
+        let mut other_graphics = Display::new();
+        let mut other_keypad = Keypad::new();
+        let rom = Vec::new();
+        let mut other = Cpu::new(&rom, &mut other_graphics, &mut other_keypad, Platform::Chip8, Quirks::default()).unwrap();
+        let before = other.save_state();
+
+        assert!(other.load_state(&state[..state.len() - 1]).is_err());
+        assert!(other.load_state(b"nope").is_err());
+        assert_eq!(before, other.save_state());
+    }
+}
@@ -1,18 +1,12 @@
-use std::time::{Duration, Instant};
-
 #[derive(Clone, Debug, PartialEq)]
 pub struct Timer {
-    pub current: u8,
-    last_set: Instant,
-    interval: Duration
+    pub current: u8
 }
 
 impl Timer {
-    pub fn new(initial: u8, rate: u32) -> Timer {
+    pub fn new(initial: u8) -> Timer {
         Timer {
-            current: initial,
-            last_set: Instant::now(),
-            interval: Duration::new(1, 0) / rate
+            current: initial
         }
     }
 
@@ -20,87 +14,63 @@ impl Timer {
         self.current > 0
     }
 
-    pub fn tick(&mut self) {
-        if !self.active() { return; }
-
-        let duration_passed = Instant::now().duration_since(self.last_set);
-        if duration_passed < self.interval { return; }
-
-        let intervals_passed = (duration_passed.subsec_nanos() / self.interval.subsec_nanos()) as u8;
-        let updated = self.current.saturating_sub(intervals_passed);
-        self.set(updated);
+    /// Counts down once for each of `frames` elapsed 60 Hz frames.
+    pub fn tick(&mut self, frames: u32) {
+        let frames = frames.min(u8::MAX as u32) as u8;
+        self.current = self.current.saturating_sub(frames);
     }
 
     pub fn set(&mut self, value: u8) {
         self.current = value;
-        self.last_set = Instant::now();
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::thread::sleep;
     use super::*;
 
     #[test]
     fn init_active() {
         let val = 42;
-        let t = Timer::new(val, 60);
+        let t = Timer::new(val);
         assert_eq!(val, t.current);
         assert!(t.active());
     }
 
     #[test]
     fn init_inactive() {
-        let t = Timer::new(0, 60);
+        let t = Timer::new(0);
         assert!(!t.active());
     }
 
     #[test]
     fn tick_inactive_no_op() {
-        let mut t = Timer::new(0, 60);
-        let first_set = t.last_set;
-        t.tick();
+        let mut t = Timer::new(0);
+        t.tick(1);
 
         assert_eq!(0, t.current);
-        assert_eq!(first_set, t.last_set);
         assert!(!t.active());
     }
 
     #[test]
-    fn tick_active_decrements_current_at_rate() {
-        let rate = 60;
-        let mut t = Timer::new(60, rate);
-        let quarter_second = t.interval * rate / 4;
-
-        sleep(quarter_second);
-        t.tick();
-
-        assert_eq!(45, t.current);
-
-        sleep(quarter_second);
-        t.tick();
+    fn tick_decrements_once_per_frame() {
+        let mut t = Timer::new(60);
 
-        assert_eq!(30, t.current);
+        t.tick(1);
+        assert_eq!(59, t.current);
 
-        sleep(quarter_second);
-        t.tick();
+        t.tick(0);
+        assert_eq!(59, t.current);
 
-        assert_eq!(15, t.current);
-
-        sleep(quarter_second);
-        t.tick();
-
-        assert_eq!(0, t.current);
+        t.tick(14);
+        assert_eq!(45, t.current);
     }
 
     #[test]
     fn tick_deactivates_at_zero() {
-        let mut t = Timer::new(1, 60);
-
-        sleep(t.interval);
-        t.tick();
+        let mut t = Timer::new(1);
 
+        t.tick(1000);
         assert_eq!(0, t.current);
         assert!(!t.active());
     }
@@ -108,11 +78,9 @@ mod tests {
     #[test]
     fn set_current_value() {
         let val: u8 = 42;
-        let mut t = Timer::new(24, 60);
-        let first_set = t.last_set;
+        let mut t = Timer::new(24);
         t.set(val);
 
         assert_eq!(val, t.current);
-        assert!(t.last_set > first_set);
-    }    
+    }
 }
@@ -0,0 +1,251 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::ops::Range;
+
+use cpu::NUM_REGISTERS;
+use {Address, Byte};
+
+/// What one executed instruction did.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceRecord {
+    pub pc: Address,
+    pub opcode: u16,
+    pub instruction: String,
+    pub v_before: [Byte; NUM_REGISTERS],
+    pub v_after: [Byte; NUM_REGISTERS],
+    pub i_before: Address,
+    pub i_after: Address,
+    pub writes: Vec<(Address, Byte)>
+}
+
+/// Receives a record for each instruction the CPU executes. `wants` is
+/// asked first, so records a sink would discard are never built.
+pub trait TraceSink: fmt::Debug {
+    fn wants(&self, _pc: Address, _opcode: u16) -> bool {
+        true
+    }
+
+    fn record(&mut self, record: &TraceRecord);
+}
+
+/// Turns tracing off. A CPU without a sink skips tracing entirely; this
+/// is for swapping a sink out without changing the CPU's type.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NullSink;
+
+impl TraceSink for NullSink {
+    fn wants(&self, _pc: Address, _opcode: u16) -> bool {
+        false
+    }
+
+    fn record(&mut self, _record: &TraceRecord) {}
+}
+
+/// One line per instruction, showing only the registers and memory it
+/// changed:
+///
+/// `0204  6a02  LD VA, 02          VA 00->02`
+#[derive(Debug)]
+pub struct TextSink<W> where W: Write + fmt::Debug {
+    out: W
+}
+
+impl<W> TextSink<W> where W: Write + fmt::Debug {
+    pub fn new(out: W) -> TextSink<W> {
+        TextSink {
+            out
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl TextSink<io::Stdout> {
+    pub fn stdout() -> TextSink<io::Stdout> {
+        TextSink::new(io::stdout())
+    }
+}
+
+impl<W> TraceSink for TextSink<W> where W: Write + fmt::Debug {
+    fn record(&mut self, record: &TraceRecord) {
+        let mut line = format!("{:04x}  {:04x}  {:<18}", record.pc, record.opcode, record.instruction);
+        for (r, (before, after)) in record.v_before.iter().zip(record.v_after.iter()).enumerate() {
+            if before != after {
+                line.push_str(&format!(" V{:X} {:02x}->{:02x}", r, before, after));
+            }
+        }
+        if record.i_before != record.i_after {
+            line.push_str(&format!(" I {:03x}->{:03x}", record.i_before, record.i_after));
+        }
+        for (addr, byte) in &record.writes {
+            line.push_str(&format!(" [{:03x}]={:02x}", addr, byte));
+        }
+        // A trace that cannot be written is dropped rather than stopping
+        // the program being traced.
+        let _ = writeln!(self.out, "{}", line.trim_end());
+    }
+}
+
+/// One JSON object per line, with every register before and after.
+#[derive(Debug)]
+pub struct JsonSink<W> where W: Write + fmt::Debug {
+    out: W
+}
+
+impl<W> JsonSink<W> where W: Write + fmt::Debug {
+    pub fn new(out: W) -> JsonSink<W> {
+        JsonSink {
+            out
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c)
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn json_bytes(bytes: &[Byte]) -> String {
+    let values: Vec<String> = bytes.iter().map(|byte| byte.to_string()).collect();
+    format!("[{}]", values.join(","))
+}
+
+impl<W> TraceSink for JsonSink<W> where W: Write + fmt::Debug {
+    fn record(&mut self, record: &TraceRecord) {
+        let writes: Vec<String> = record.writes.iter()
+            .map(|(addr, byte)| format!("{{\"addr\":{},\"value\":{}}}", addr, byte))
+            .collect();
+        let _ = writeln!(self.out,
+            "{{\"pc\":{},\"opcode\":{},\"instruction\":{},\"v_before\":{},\"v_after\":{},\"i_before\":{},\"i_after\":{},\"writes\":[{}]}}",
+            record.pc, json_string(&format!("{:04x}", record.opcode)), json_string(&record.instruction),
+            json_bytes(&record.v_before), json_bytes(&record.v_after),
+            record.i_before, record.i_after, writes.join(",")
+        );
+    }
+}
+
+/// Passes on only the records from a range of addresses and from chosen
+/// opcode classes, where class N is the opcodes NXXX.
+#[derive(Debug)]
+pub struct Filter<S> where S: TraceSink {
+    sink: S,
+    addresses: Option<Range<Address>>,
+    classes: u16
+}
+
+impl<S> Filter<S> where S: TraceSink {
+    pub fn new(sink: S) -> Filter<S> {
+        Filter {
+            sink,
+            addresses: None,
+            classes: 0xFFFF
+        }
+    }
+
+    pub fn addresses(mut self, range: Range<Address>) -> Filter<S> {
+        self.addresses = Some(range);
+        self
+    }
+
+    /// Restricts the trace to the given classes, e.g. `&[0x8, 0xD]` for
+    /// arithmetic and drawing.
+    pub fn classes(mut self, classes: &[Byte]) -> Filter<S> {
+        self.classes = classes.iter().fold(0x0, |acc, class| acc | 0b1 << (class & 0xF));
+        self
+    }
+
+    pub fn into_inner(self) -> S {
+        self.sink
+    }
+}
+
+impl<S> TraceSink for Filter<S> where S: TraceSink {
+    fn wants(&self, pc: Address, opcode: u16) -> bool {
+        let in_range = self.addresses.as_ref().is_none_or(|range| range.contains(&pc));
+        let in_class = self.classes & (0b1 << (opcode >> 12)) != 0;
+        in_range && in_class && self.sink.wants(pc, opcode)
+    }
+
+    fn record(&mut self, record: &TraceRecord) {
+        self.sink.record(record);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> TraceRecord {
+        let mut v_after = [0x0; NUM_REGISTERS];
+        v_after[0xA] = 0x02;
+        TraceRecord {
+            pc: 0x204,
+            opcode: 0x6A02,
+            instruction: "LD VA, 02".to_string(),
+            v_before: [0x0; NUM_REGISTERS],
+            v_after,
+            i_before: 0x300,
+            i_after: 0x300,
+            writes: vec![(0x300, 0x7)]
+        }
+    }
+
+    #[test]
+    fn text_sink_shows_changes() {
+        let mut sink = TextSink::new(Vec::new());
+        sink.record(&record());
+
+        let text = String::from_utf8(sink.into_inner()).unwrap();
+        assert_eq!("0204  6a02  LD VA, 02          VA 00->02 [300]=07\n", text);
+    }
+
+    #[test]
+    fn json_sink_writes_one_object_per_line() {
+        let mut sink = JsonSink::new(Vec::new());
+        sink.record(&record());
+        sink.record(&record());
+
+        let text = String::from_utf8(sink.into_inner()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(2, lines.len());
+        assert!(lines[0].starts_with("{\"pc\":516,\"opcode\":\"6a02\",\"instruction\":\"LD VA, 02\","));
+        assert!(lines[0].ends_with("\"i_before\":768,\"i_after\":768,\"writes\":[{\"addr\":768,\"value\":7}]}"));
+    }
+
+    #[test]
+    fn json_string_escapes() {
+        assert_eq!("\"a\\\"b\\\\c\\u000a\"", json_string("a\"b\\c\n"));
+    }
+
+    #[test]
+    fn null_sink_wants_nothing() {
+        assert!(!NullSink.wants(0x200, 0x00E0));
+    }
+
+    #[test]
+    fn filter_by_address_and_class() {
+        let filter = Filter::new(TextSink::new(Vec::new()))
+            .addresses(0x200..0x300)
+            .classes(&[0x8, 0xD]);
+
+        assert!(filter.wants(0x200, 0x8124));
+        assert!(filter.wants(0x2FE, 0xD015));
+        assert!(!filter.wants(0x300, 0x8124));
+        assert!(!filter.wants(0x200, 0x6A02));
+    }
+}
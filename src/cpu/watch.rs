@@ -0,0 +1,107 @@
+use std::fmt;
+use std::ops::Range;
+
+use {Address, Byte};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    Execute
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Access::Read => "read",
+            Access::Write => "write",
+            Access::Execute => "execute"
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A range of memory to watch for reads, writes and/or instruction fetches.
+/// Built up from `Watchpoint::new(range)` with `read()`, `write()` and
+/// `execute()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub range: Range<Address>,
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool
+}
+
+impl Watchpoint {
+    pub fn new(range: Range<Address>) -> Watchpoint {
+        Watchpoint { range, read: false, write: false, execute: false }
+    }
+
+    pub fn read(mut self) -> Watchpoint {
+        self.read = true;
+        self
+    }
+
+    pub fn write(mut self) -> Watchpoint {
+        self.write = true;
+        self
+    }
+
+    pub fn execute(mut self) -> Watchpoint {
+        self.execute = true;
+        self
+    }
+
+    pub fn watches(&self, addr: Address, access: Access) -> bool {
+        let wanted = match access {
+            Access::Read => self.read,
+            Access::Write => self.write,
+            Access::Execute => self.execute
+        };
+        wanted && self.range.start <= addr && addr < self.range.end
+    }
+}
+
+/// One watched access made by the instruction at `pc`. Reads and fetches
+/// have `old == new`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WatchHit {
+    pub pc: Address,
+    pub addr: Address,
+    pub access: Access,
+    pub old: Byte,
+    pub new: Byte
+}
+
+impl fmt::Display for WatchHit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.access {
+            Access::Write => write!(f, "{} {:04x}: {:02x} -> {:02x} by {:04x}", self.access, self.addr, self.old, self.new, self.pc),
+            _ => write!(f, "{} {:04x}: {:02x} by {:04x}", self.access, self.addr, self.new, self.pc)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watches_only_requested_access_in_range() {
+        let watchpoint = Watchpoint::new(0x300..0x302).write();
+        assert!(watchpoint.watches(0x300, Access::Write));
+        assert!(watchpoint.watches(0x301, Access::Write));
+        assert!(!watchpoint.watches(0x302, Access::Write));
+        assert!(!watchpoint.watches(0x2FF, Access::Write));
+        assert!(!watchpoint.watches(0x300, Access::Read));
+        assert!(!watchpoint.watches(0x300, Access::Execute));
+    }
+
+    #[test]
+    fn display_hits() {
+        let write = WatchHit { pc: 0x204, addr: 0x300, access: Access::Write, old: 0x00, new: 0x07 };
+        assert_eq!("write 0300: 00 -> 07 by 0204", write.to_string());
+        let read = WatchHit { access: Access::Read, old: 0x07, ..write };
+        assert_eq!("read 0300: 07 by 0204", read.to_string());
+    }
+}
@@ -0,0 +1,674 @@
+use std::collections::BTreeSet;
+use std::io::{BufRead, Write};
+use std::result;
+use std::str::FromStr;
+
+use cpu::{Cpu, Register};
+use cpu::instruction::Instruction;
+use cpu::opcode::Opcode;
+use cpu::watch::{Watchpoint, WatchHit};
+use error::{Error, Result};
+use input::Input;
+use output::graphics::GraphicsOutput;
+use {Address, Byte};
+
+/// Instructions `continue`, `next` and `finish` run before handing back
+/// control, so a program spinning in a loop cannot hang the prompt.
+const RUN_LIMIT: u64 = 1_000_000;
+const LIST_BEFORE: usize = 4;
+const LIST_AFTER: usize = 8;
+const DUMP_WIDTH: usize = 16;
+
+const HELP: &str = "\
+Addresses and values are hex; counts are decimal.
+  s, step [n]          execute n instructions
+  n, next              step over a CALL
+  f, finish            run until the current subroutine returns
+  c, continue          run until a breakpoint or exit
+  b, break <addr>      set a breakpoint
+  d, delete <addr>     remove a breakpoint
+  breaks               list breakpoints
+  watch <addr>[-<end>] [rwx]
+                       watch reads, writes and/or execution (default w)
+  unwatch <addr>       remove watchpoints covering addr
+  watches              list watchpoints
+  r, regs              show registers
+  set <reg> <value>    set V0-VF, I, PC, DT or ST
+  x, mem <addr> [len]  dump memory
+  w, write <addr> <byte>...
+                       write memory
+  bt, stack            show the call stack
+  l, list [addr]       disassemble around PC or addr
+  q, quit              leave the debugger";
+
+/// Why running stopped.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stop {
+    Stepped,
+    Breakpoint(Address),
+    /// The first watched access made by the last instruction.
+    Watchpoint(WatchHit),
+    Returned,
+    Exited,
+    Limit
+}
+
+/// A register the debugger can set.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Target {
+    V(Register),
+    I,
+    Pc,
+    Dt,
+    St
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Step(u32),
+    Next,
+    Finish,
+    Continue,
+    Break(Address),
+    Delete(Address),
+    Breakpoints,
+    Watch(Watchpoint),
+    Unwatch(Address),
+    Watchpoints,
+    Registers,
+    Set(Target, Address),
+    Memory(Address, usize),
+    Write(Address, Vec<Byte>),
+    Stack,
+    List(Option<Address>),
+    Help,
+    Quit
+}
+
+fn hex(text: &str) -> result::Result<usize, String> {
+    let digits = text.strip_prefix("0x").unwrap_or(text);
+    usize::from_str_radix(digits, 16).map_err(|_| format!("Not a hex number: {}", text))
+}
+
+fn count<T>(text: &str) -> result::Result<T, String> where T: FromStr {
+    text.parse().map_err(|_| format!("Not a count: {}", text))
+}
+
+fn byte(text: &str) -> result::Result<Byte, String> {
+    match hex(text)? {
+        value if value <= 0xFF => Ok(value as Byte),
+        _ => Err(format!("Not a byte: {}", text))
+    }
+}
+
+/// Parses `addr` or an inclusive `start-end` range.
+fn range(text: &str) -> result::Result<(Address, Address), String> {
+    let (start, end) = match text.find('-') {
+        Some(dash) => {
+            let (start, end) = (hex(&text[..dash])?, hex(&text[dash + 1..])?);
+            if end < start { return Err(format!("Empty range: {}", text)); }
+            (start, end)
+        },
+        None => {
+            let addr = hex(text)?;
+            (addr, addr)
+        }
+    };
+    match end.checked_add(1) {
+        Some(end) => Ok((start, end)),
+        None => Err("Address out of range".to_string())
+    }
+}
+
+impl Command {
+    pub fn parse(line: &str) -> result::Result<Command, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let arg = |n: usize| words.get(n).cloned().ok_or_else(|| format!("Missing argument to {}", words[0]));
+        let command = match words.first().cloned().unwrap_or("") {
+            "s" | "step" => {
+                let n = match words.get(1) {
+                    Some(n) => count(n)?,
+                    None => 1
+                };
+                Command::Step(n)
+            },
+            "n" | "next" => Command::Next,
+            "f" | "finish" => Command::Finish,
+            "c" | "continue" => Command::Continue,
+            "b" | "break" => Command::Break(hex(arg(1)?)?),
+            "d" | "delete" => Command::Delete(hex(arg(1)?)?),
+            "breaks" => Command::Breakpoints,
+            "watch" => {
+                let (start, end) = range(arg(1)?)?;
+                let mut watchpoint = Watchpoint::new(start..end);
+                for access in words.get(2).cloned().unwrap_or("w").chars() {
+                    watchpoint = match access {
+                        'r' => watchpoint.read(),
+                        'w' => watchpoint.write(),
+                        'x' => watchpoint.execute(),
+                        _ => return Err(format!("Unknown access: {} (use r, w or x)", access))
+                    };
+                }
+                Command::Watch(watchpoint)
+            },
+            "unwatch" => Command::Unwatch(hex(arg(1)?)?),
+            "watches" => Command::Watchpoints,
+            "r" | "regs" => Command::Registers,
+            "set" => {
+                let name = arg(1)?.to_lowercase();
+                let value = arg(2)?;
+                let target = match name.as_str() {
+                    "i" => Target::I,
+                    "pc" => Target::Pc,
+                    "dt" => Target::Dt,
+                    "st" => Target::St,
+                    _ => match name.strip_prefix('v').and_then(|x| usize::from_str_radix(x, 16).ok()) {
+                        Some(x) if x < 0x10 && name.len() == 2 => Target::V(x),
+                        _ => return Err(format!("Unknown register: {}", name))
+                    }
+                };
+                match target {
+                    Target::I | Target::Pc => Command::Set(target, hex(value)?),
+                    _ => Command::Set(target, byte(value)? as Address)
+                }
+            },
+            "x" | "mem" => {
+                let len = match words.get(2) {
+                    Some(len) => count(len)?,
+                    None => DUMP_WIDTH
+                };
+                Command::Memory(hex(arg(1)?)?, len)
+            },
+            "w" | "write" => {
+                let bytes = words[2.min(words.len())..].iter()
+                    .map(|word| byte(word))
+                    .collect::<result::Result<Vec<Byte>, String>>()?;
+                if bytes.is_empty() { return Err("Missing bytes to write".to_string()); }
+                Command::Write(hex(arg(1)?)?, bytes)
+            },
+            "bt" | "stack" => Command::Stack,
+            "l" | "list" => Command::List(match words.get(1) {
+                Some(addr) => Some(hex(addr)?),
+                None => None
+            }),
+            "h" | "help" => Command::Help,
+            "q" | "quit" => Command::Quit,
+            other => return Err(format!("Unknown command: {} (try help)", other))
+        };
+        Ok(command)
+    }
+}
+
+/// A watchpoint as `0300-0302 rw`.
+fn describe(watchpoint: &Watchpoint) -> String {
+    let range = &watchpoint.range;
+    let addrs = if range.len() == 1 {
+        format!("{:04x}", range.start)
+    } else {
+        format!("{:04x}-{:04x}", range.start, range.end - 1)
+    };
+    let access: String = [(watchpoint.read, 'r'), (watchpoint.write, 'w'), (watchpoint.execute, 'x')].iter()
+        .filter(|&&(on, _)| on)
+        .map(|&(_, flag)| flag)
+        .collect();
+    format!("{} {}", addrs, access)
+}
+
+/// Interactive control over a `Cpu`: stepping, breakpoints, and reading
+/// and changing its state between instructions.
+#[derive(Debug)]
+pub struct Debugger<'a, G: 'a, I: 'a> where G: GraphicsOutput, I: Input {
+    cpu: Cpu<'a, G, I>,
+    breakpoints: BTreeSet<Address>,
+    hits: Vec<WatchHit>
+}
+
+impl<'a, G, I> Debugger<'a, G, I> where G: GraphicsOutput, I: Input {
+    pub fn new(cpu: Cpu<'a, G, I>) -> Debugger<'a, G, I> {
+        Debugger {
+            cpu,
+            breakpoints: BTreeSet::new(),
+            hits: Vec::new()
+        }
+    }
+
+    pub fn cpu(&self) -> &Cpu<'a, G, I> {
+        &self.cpu
+    }
+
+    pub fn cpu_mut(&mut self) -> &mut Cpu<'a, G, I> {
+        &mut self.cpu
+    }
+
+    /// Returns false if there was already a breakpoint at `addr`.
+    pub fn add_breakpoint(&mut self, addr: Address) -> bool {
+        self.breakpoints.insert(addr)
+    }
+
+    /// Returns false if there was no breakpoint at `addr`.
+    pub fn remove_breakpoint(&mut self, addr: Address) -> bool {
+        self.breakpoints.remove(&addr)
+    }
+
+    pub fn breakpoints(&self) -> Vec<Address> {
+        self.breakpoints.iter().cloned().collect()
+    }
+
+    /// Watched accesses made by the instruction that last stopped on a
+    /// watchpoint.
+    pub fn watch_hits(&self) -> &[WatchHit] {
+        &self.hits
+    }
+
+    pub fn step(&mut self) -> Result<Stop> {
+        if self.cpu.exit { return Ok(Stop::Exited); }
+        self.cpu.step()?;
+        if let Some(stop) = self.watched() { return Ok(stop); }
+        Ok(if self.cpu.exit { Stop::Exited } else { Stop::Stepped })
+    }
+
+    /// Steps, running a CALL through to its return as one step.
+    pub fn step_over(&mut self) -> Result<Stop> {
+        match self.current() {
+            Instruction::Call(_) => {
+                let (ret, sp) = (self.cpu.pc() + 2, self.cpu.sp());
+                self.run_until(|cpu| cpu.pc() == ret && cpu.sp() == sp, Stop::Stepped)
+            },
+            _ => self.step()
+        }
+    }
+
+    /// Runs until the subroutine PC is in returns to its caller.
+    pub fn finish(&mut self) -> Result<Stop> {
+        let sp = self.cpu.sp();
+        self.run_until(|cpu| cpu.sp() < sp, Stop::Returned)
+    }
+
+    /// Runs until a breakpoint, exit or the run limit.
+    pub fn resume(&mut self) -> Result<Stop> {
+        self.run_until(|_| false, Stop::Stepped)
+    }
+
+    fn run_until<F>(&mut self, done: F, stop: Stop) -> Result<Stop> where F: Fn(&Cpu<'a, G, I>) -> bool {
+        for _ in 0..RUN_LIMIT {
+            if self.cpu.exit { return Ok(Stop::Exited); }
+            self.cpu.step()?;
+            if let Some(stop) = self.watched() { return Ok(stop); }
+            if done(&self.cpu) { return Ok(stop); }
+            let pc = self.cpu.pc();
+            if self.breakpoints.contains(&pc) { return Ok(Stop::Breakpoint(pc)); }
+        }
+        Ok(Stop::Limit)
+    }
+
+    fn watched(&mut self) -> Option<Stop> {
+        let hits = self.cpu.take_watch_hits();
+        let stop = hits.first().map(|hit| Stop::Watchpoint(*hit));
+        if stop.is_some() { self.hits = hits; }
+        stop
+    }
+
+    fn current(&self) -> Instruction {
+        self.instruction(self.cpu.pc()).unwrap_or(Instruction::Unknown(0x0))
+    }
+
+    fn instruction(&self, addr: Address) -> Option<Instruction> {
+        let memory = self.cpu.memory();
+        if addr + 1 >= memory.len() { return None; }
+        let opcode = Opcode::from_bytes((memory[addr], memory[addr + 1]));
+        Some(opcode.decode_for(self.cpu.platform()))
+    }
+
+    /// The instruction at `addr` as the trace shows it.
+    fn line(&self, addr: Address) -> String {
+        match self.instruction(addr) {
+            Some(instruction) => format!("{:04x}  {:04x}  {}", addr, instruction.encode().code(), instruction),
+            None => format!("{:04x}  ----", addr)
+        }
+    }
+
+    fn stopped(&self, stop: Stop) -> String {
+        let pc = self.cpu.pc();
+        match stop {
+            Stop::Stepped | Stop::Returned => self.line(pc),
+            Stop::Breakpoint(addr) => format!("Breakpoint at {:04x}\n{}", addr, self.line(pc)),
+            Stop::Watchpoint(hit) => {
+                let hits: Vec<String> = self.hits.iter().map(|hit| format!("Watchpoint: {}", hit)).collect();
+                format!("{}\n{}\n{}", hits.join("\n"), self.line(hit.pc), self.line(pc))
+            },
+            Stop::Exited => "Program exited".to_string(),
+            Stop::Limit => format!("Stopped after {} instructions\n{}", RUN_LIMIT, self.line(pc))
+        }
+    }
+
+    fn registers(&self) -> String {
+        let v: Vec<String> = (0x0..0x10)
+            .map(|x| format!("V{:X} {:02x}", x, self.cpu.read_register(x)))
+            .collect();
+        format!("{}\n{}\nPC {:04x}  I {:04x}  SP {:04x}  DT {:02x}  ST {:02x}",
+            v[..8].join("  "), v[8..].join("  "),
+            self.cpu.pc(), self.cpu.read_i(), self.cpu.sp(),
+            self.cpu.read_delay_timer(), self.cpu.read_sound_timer()
+        )
+    }
+
+    fn dump(&self, addr: Address, len: usize) -> Result<String> {
+        let memory = self.cpu.memory();
+        let end = match addr.checked_add(len) {
+            Some(end) if end <= memory.len() => end,
+            _ => return Err(Error::AddressOutOfRange { addr: addr.saturating_add(len.saturating_sub(1)), range: 0x0..memory.len() })
+        };
+        let lines: Vec<String> = memory[addr..end].chunks(DUMP_WIDTH).enumerate()
+            .map(|(n, bytes)| {
+                let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+                format!("{:04x}  {}", addr + n * DUMP_WIDTH, hex.join(" "))
+            })
+            .collect();
+        Ok(lines.join("\n"))
+    }
+
+    fn list(&self, addr: Address) -> String {
+        let start = addr.saturating_sub(LIST_BEFORE * 2);
+        (0..LIST_BEFORE + LIST_AFTER + 1)
+            .map(|n| start + n * 2)
+            .filter(|addr| self.instruction(*addr).is_some())
+            .map(|addr| {
+                let here = if addr == self.cpu.pc() { '>' } else { ' ' };
+                let breakpoint = if self.breakpoints.contains(&addr) { '*' } else { ' ' };
+                format!("{}{} {}", here, breakpoint, self.line(addr))
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn stack(&self) -> String {
+        let frames = self.cpu.call_stack();
+        if frames.is_empty() { return "Stack is empty".to_string(); }
+        frames.iter().enumerate()
+            .map(|(n, addr)| format!("#{:<2} {}", n, self.line(*addr)))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Runs `command`, returning what to show the user.
+    pub fn execute(&mut self, command: &Command) -> Result<String> {
+        let text = match *command {
+            Command::Step(count) => {
+                let mut stop = Stop::Stepped;
+                for _ in 0..count.max(1) {
+                    stop = self.step()?;
+                    if stop != Stop::Stepped { break; }
+                }
+                self.stopped(stop)
+            },
+            Command::Next => {
+                let stop = self.step_over()?;
+                self.stopped(stop)
+            },
+            Command::Finish => {
+                if self.cpu.call_stack().is_empty() { return Ok("Not in a subroutine".to_string()); }
+                let stop = self.finish()?;
+                self.stopped(stop)
+            },
+            Command::Continue => {
+                let stop = self.resume()?;
+                self.stopped(stop)
+            },
+            Command::Break(addr) => {
+                self.add_breakpoint(addr);
+                format!("Breakpoint at {:04x}", addr)
+            },
+            Command::Delete(addr) => {
+                if self.remove_breakpoint(addr) {
+                    format!("Deleted breakpoint at {:04x}", addr)
+                } else {
+                    format!("No breakpoint at {:04x}", addr)
+                }
+            },
+            Command::Breakpoints => {
+                if self.breakpoints.is_empty() { return Ok("No breakpoints".to_string()); }
+                self.breakpoints.iter().map(|addr| self.line(*addr)).collect::<Vec<String>>().join("\n")
+            },
+            Command::Watch(ref watchpoint) => {
+                self.cpu.add_watchpoint(watchpoint.clone());
+                format!("Watchpoint {}", describe(watchpoint))
+            },
+            Command::Unwatch(addr) => {
+                if self.cpu.remove_watchpoint(addr) {
+                    format!("Deleted watchpoints at {:04x}", addr)
+                } else {
+                    format!("No watchpoint at {:04x}", addr)
+                }
+            },
+            Command::Watchpoints => {
+                if self.cpu.watchpoints().is_empty() { return Ok("No watchpoints".to_string()); }
+                self.cpu.watchpoints().iter().map(describe).collect::<Vec<String>>().join("\n")
+            },
+            Command::Registers => self.registers(),
+            Command::Set(target, value) => {
+                match target {
+                    Target::V(x) => self.cpu.load_register(x, value as Byte),
+                    Target::I => self.cpu.load_i(value)?,
+                    Target::Pc => self.cpu.set_pc(value)?,
+                    Target::Dt => self.cpu.load_delay_timer(value as Byte),
+                    Target::St => self.cpu.load_sound_timer(value as Byte)
+                }
+                self.registers()
+            },
+            Command::Memory(addr, len) => self.dump(addr, len)?,
+            Command::Write(addr, ref bytes) => {
+                self.cpu.write_memory(addr, bytes)?;
+                self.dump(addr, bytes.len())?
+            },
+            Command::Stack => self.stack(),
+            Command::List(addr) => self.list(addr.unwrap_or_else(|| self.cpu.pc())),
+            Command::Help => HELP.to_string(),
+            Command::Quit => String::new()
+        };
+        Ok(text)
+    }
+
+    /// Reads commands from `input` until it ends or `quit`, writing results
+    /// to `output`. An empty line repeats the previous command.
+    pub fn repl<R, W>(&mut self, input: R, output: &mut W) -> Result<()> where R: BufRead, W: Write {
+        let mut last = None;
+        writeln!(output, "{}", self.line(self.cpu.pc()))?;
+        write!(output, "(debug) ")?;
+        output.flush()?;
+        for line in input.lines() {
+            let line = line?;
+            let command = if line.trim().is_empty() {
+                last.clone()
+            } else {
+                match Command::parse(&line) {
+                    Ok(command) => Some(command),
+                    Err(message) => {
+                        writeln!(output, "{}", message)?;
+                        None
+                    }
+                }
+            };
+            if let Some(command) = command {
+                if command == Command::Quit { return Ok(()); }
+                match self.execute(&command) {
+                    Ok(text) => writeln!(output, "{}", text)?,
+                    Err(err) => writeln!(output, "{}", err)?
+                }
+                last = Some(command);
+            }
+            write!(output, "(debug) ")?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cpu::platform::Platform;
+    use cpu::quirks::Quirks;
+    use cpu::watch::Access;
+    use input::keypad::Keypad;
+    use output::graphics::Display;
+
+    /// main: V0 += 1, CALL 206, JP 200. 206: V1 += 1, CALL 20E, RET.
+    /// 20E: V2 += 1, RET.
+    const ROM: [Byte; 18] = [
+        0x70, 0x01, 0x22, 0x06, 0x12, 0x00,
+        0x71, 0x01, 0x22, 0x0E, 0x00, 0xEE,
+        0x00, 0x00,
+        0x72, 0x01, 0x00, 0xEE
+    ];
+
+    macro_rules! debugger {
+        ($name:ident) => {
+            let mut graphics = Display::new();
+            let mut keypad = Keypad::new();
+            let cpu = Cpu::new(&ROM, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+            let mut $name = Debugger::new(cpu);
+        };
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(Ok(Command::Step(1)), Command::parse("s"));
+        assert_eq!(Ok(Command::Step(10)), Command::parse("step 10"));
+        assert_eq!(Ok(Command::Break(0x20A)), Command::parse("b 0x20a"));
+        assert_eq!(Ok(Command::Set(Target::V(0xA), 0x12)), Command::parse("set VA 12"));
+        assert_eq!(Ok(Command::Set(Target::I, 0x300)), Command::parse("set i 300"));
+        assert_eq!(Ok(Command::Memory(0x200, 20)), Command::parse("x 200 20"));
+        assert!(Command::parse("x 200 1f").is_err());
+        assert_eq!(Ok(Command::Write(0x300, vec![0x01, 0xFF])), Command::parse("w 300 1 ff"));
+        assert!(Command::parse("set v3 100").is_err());
+        assert!(Command::parse("set vg 1").is_err());
+        assert!(Command::parse("break").is_err());
+        assert!(Command::parse("frobnicate").is_err());
+    }
+
+    #[test]
+    fn parse_watch_commands() {
+        assert_eq!(Ok(Command::Watch(Watchpoint::new(0x300..0x301).write())), Command::parse("watch 300"));
+        assert_eq!(Ok(Command::Watch(Watchpoint::new(0x300..0x310).read().execute())), Command::parse("watch 300-30f rx"));
+        assert_eq!(Ok(Command::Unwatch(0x305)), Command::parse("unwatch 305"));
+        assert_eq!(Err("Address out of range".to_string()), Command::parse("watch ffffffffffffffff"));
+        assert_eq!(Err("Address out of range".to_string()), Command::parse("watch 0-ffffffffffffffff"));
+        assert!(Command::parse("watch 310-300").is_err());
+        assert!(Command::parse("watch 300 q").is_err());
+        assert!(Command::parse("watch").is_err());
+    }
+
+    #[test]
+    fn continue_stops_at_stack_write() {
+        debugger!(debugger);
+        let frame = debugger.cpu().sp() + 4;
+        debugger.execute(&Command::Watch(Watchpoint::new(frame..frame + 2).write())).unwrap();
+
+        let stop = debugger.resume().unwrap();
+        let hit = WatchHit { pc: 0x208, addr: frame, access: Access::Write, old: 0x00, new: 0x02 };
+        assert_eq!(Stop::Watchpoint(hit), stop);
+        assert_eq!(0x20E, debugger.cpu().pc());
+        assert_eq!(2, debugger.watch_hits().len());
+    }
+
+    #[test]
+    fn execute_reports_watch_hits() {
+        debugger!(debugger);
+        let set = debugger.execute(&Command::Watch(Watchpoint::new(0x200..0x202).execute())).unwrap();
+        assert_eq!("Watchpoint 0200-0201 x", set);
+        assert_eq!("0200-0201 x", debugger.execute(&Command::Watchpoints).unwrap());
+
+        let stopped = debugger.execute(&Command::Continue).unwrap();
+        assert!(stopped.starts_with("Watchpoint: execute 0200: 70 by 0200\nWatchpoint: execute 0201: 01 by 0200\n"));
+        assert_eq!("Deleted watchpoints at 0201", debugger.execute(&Command::Unwatch(0x201)).unwrap());
+        assert_eq!("No watchpoints", debugger.execute(&Command::Watchpoints).unwrap());
+    }
+
+    #[test]
+    fn step_over_runs_whole_call() {
+        debugger!(debugger);
+        debugger.step().unwrap();
+        assert_eq!(Stop::Stepped, debugger.step_over().unwrap());
+        assert_eq!(0x204, debugger.cpu().pc());
+        assert_eq!(0x1, debugger.cpu().read_register(0x1));
+        assert_eq!(0x1, debugger.cpu().read_register(0x2));
+    }
+
+    #[test]
+    fn finish_returns_to_caller() {
+        debugger!(debugger);
+        debugger.step().unwrap();
+        debugger.step().unwrap();
+        assert_eq!(0x206, debugger.cpu().pc());
+
+        assert_eq!(Stop::Returned, debugger.finish().unwrap());
+        assert_eq!(0x204, debugger.cpu().pc());
+        assert!(debugger.cpu().call_stack().is_empty());
+    }
+
+    #[test]
+    fn continue_stops_at_breakpoint() {
+        debugger!(debugger);
+        debugger.add_breakpoint(0x20E);
+        assert_eq!(Stop::Breakpoint(0x20E), debugger.resume().unwrap());
+        assert_eq!(vec![0x208, 0x202], debugger.cpu().call_stack());
+
+        assert_eq!(Stop::Breakpoint(0x20E), debugger.resume().unwrap());
+        assert_eq!(0x2, debugger.cpu().read_register(0x0));
+    }
+
+    #[test]
+    fn continue_stops_at_exit() {
+        let mut graphics = Display::new();
+        let mut keypad = Keypad::new();
+        let cpu = Cpu::new(&[0x00, 0xFD], &mut graphics, &mut keypad, Platform::SuperChip, Quirks::default()).unwrap();
+        let mut debugger = Debugger::new(cpu);
+        assert_eq!(Stop::Exited, debugger.resume().unwrap());
+    }
+
+    #[test]
+    fn execute_shows_and_changes_state() {
+        debugger!(debugger);
+        debugger.execute(&Command::Set(Target::V(0x3), 0x42)).unwrap();
+        debugger.execute(&Command::Write(0x300, vec![0xAB, 0xCD])).unwrap();
+
+        let registers = debugger.execute(&Command::Registers).unwrap();
+        assert!(registers.contains("V3 42"));
+        assert!(registers.contains("PC 0200"));
+        assert_eq!("0300  ab cd", debugger.execute(&Command::Memory(0x300, 2)).unwrap());
+        assert!(debugger.execute(&Command::Memory(0xFFF, 2)).is_err());
+        assert!(debugger.execute(&Command::Memory(0x200, usize::MAX)).is_err());
+    }
+
+    #[test]
+    fn list_marks_pc_and_breakpoints() {
+        debugger!(debugger);
+        debugger.add_breakpoint(0x202);
+        let listing = debugger.execute(&Command::List(None)).unwrap();
+        let lines: Vec<&str> = listing.lines().collect();
+
+        assert_eq!(">  0200  7001  ADD V0, 01", lines[4]);
+        assert_eq!(" * 0202  2206  CALL 206", lines[5]);
+    }
+
+    #[test]
+    fn stack_shows_call_sites() {
+        debugger!(debugger);
+        debugger.execute(&Command::Step(4)).unwrap();
+        assert_eq!("#0  0208  220e  CALL 20e\n#1  0202  2206  CALL 206", debugger.execute(&Command::Stack).unwrap());
+    }
+
+    #[test]
+    fn repl_runs_script_and_repeats_empty_line() {
+        debugger!(debugger);
+        let script = b"step\n\nregs\nbogus\nquit\nstep\n";
+        let mut output = Vec::new();
+        debugger.repl(&script[..], &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("0202  2206  CALL 206"));
+        assert!(output.contains("0206  7101  ADD V1, 01"));
+        assert!(output.contains("Unknown command: bogus"));
+        assert_eq!(0x206, debugger.cpu().pc());
+    }
+}
@@ -0,0 +1,94 @@
+use std::{error, fmt, io, result};
+use std::ops::Range;
+
+use Address;
+
+pub type Result<T> = result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    DataTooLarge { len: usize, range: Range<Address> },
+    AddressOutOfRange { addr: Address, range: Range<Address> },
+    UnknownOpcode(u16),
+    BadSaveState(&'static str),
+    UnsupportedSaveVersion(u16),
+    Fault { pc: Address, opcode: u16, cause: Box<Error> },
+    Assembly { line: usize, column: usize, message: String },
+    BadImage(&'static str),
+    ImageMismatch(String)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref err) => write!(f, "I/O error: {}", err),
+            Error::DataTooLarge { len, ref range } => write!(f,
+                "Data length {:x} is greater than range size {:x} ({:x}..{:x})",
+                len, range.len(), range.start, range.end
+            ),
+            Error::AddressOutOfRange { addr, ref range } => write!(f,
+                "Address {:x} out of range ({:x}..{:x})",
+                addr, range.start, range.end
+            ),
+            Error::UnknownOpcode(code) => write!(f, "Unknown opcode {:04x}", code),
+            Error::BadSaveState(reason) => write!(f, "Invalid save state: {}", reason),
+            Error::UnsupportedSaveVersion(version) => write!(f, "Unsupported save state version {}", version),
+            Error::Fault { pc, opcode, ref cause } => write!(f,
+                "Fault at {:x} executing {:04x}: {}",
+                pc, opcode, cause
+            ),
+            Error::Assembly { line, column, ref message } => write!(f, "Line {}, column {}: {}", line, column, message),
+            Error::BadImage(reason) => write!(f, "Invalid image: {}", reason),
+            Error::ImageMismatch(ref diff) => write!(f, "Display does not match golden image: {}", diff)
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref err) => Some(err),
+            Error::Fault { ref cause, .. } => Some(cause.as_ref()),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as StdError;
+
+    #[test]
+    fn display_fault_includes_pc_opcode_and_cause() {
+        let err = Error::Fault {
+            pc: 0x204,
+            opcode: 0x0123,
+            cause: Box::new(Error::UnknownOpcode(0x0123))
+        };
+        assert_eq!("Fault at 204 executing 0123: Unknown opcode 0123", err.to_string());
+        assert!(err.source().is_some());
+    }
+
+    #[test]
+    fn display_address_out_of_range() {
+        let err = Error::AddressOutOfRange { addr: 0x1000, range: 0x200..0xFA0 };
+        assert_eq!("Address 1000 out of range (200..fa0)", err.to_string());
+    }
+
+    #[test]
+    fn from_io_error() {
+        let err: Error = io::Error::new(io::ErrorKind::NotFound, "missing").into();
+        match err {
+            Error::Io(ref io_err) => assert_eq!(io::ErrorKind::NotFound, io_err.kind()),
+            _ => panic!("expected Error::Io")
+        }
+    }
+}
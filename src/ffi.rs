@@ -0,0 +1,452 @@
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use cpu::platform::Platform;
+use error::Error;
+use input::keypad::NUM_KEYS;
+use machine::Machine;
+use Byte;
+
+/// What every C ABI function returns. `include/rusty_chip.h` mirrors it.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Ok = 0,
+    NullPointer = 1,
+    BadPlatform = 2,
+    BadKey = 3,
+    RomTooLarge = 4,
+    Fault = 5,
+    BadState = 6,
+    BufferTooSmall = 7,
+    Panic = 8
+}
+
+impl Status {
+    const ALL: [Status; 9] = [Status::Ok, Status::NullPointer, Status::BadPlatform, Status::BadKey, Status::RomTooLarge,
+        Status::Fault, Status::BadState, Status::BufferTooSmall, Status::Panic];
+
+    fn message(self) -> &'static [u8] {
+        match self {
+            Status::Ok => b"ok\0",
+            Status::NullPointer => b"a required pointer was null\0",
+            Status::BadPlatform => b"unknown platform\0",
+            Status::BadKey => b"key is not on the keypad\0",
+            Status::RomTooLarge => b"ROM does not fit in memory\0",
+            Status::Fault => b"the program faulted\0",
+            Status::BadState => b"invalid or unsupported save state\0",
+            Status::BufferTooSmall => b"buffer is too small\0",
+            Status::Panic => b"internal error\0"
+        }
+    }
+}
+
+impl From<Error> for Status {
+    fn from(err: Error) -> Status {
+        match err {
+            Error::DataTooLarge { .. } => Status::RomTooLarge,
+            Error::BadSaveState(_) | Error::UnsupportedSaveVersion(_) => Status::BadState,
+            _ => Status::Fault
+        }
+    }
+}
+
+/// The opaque handle C holds. It is only ever behind a pointer from
+/// `rusty_chip_create`.
+pub struct RustyChip {
+    machine: Machine
+}
+
+/// Runs `f` on the machine behind `chip`, turning null pointers, errors and
+/// panics into statuses so none of them unwind into C.
+unsafe fn with_chip<F>(chip: *mut RustyChip, f: F) -> Status where F: FnOnce(&mut Machine) -> Result<(), Status> {
+    let chip = match chip.as_mut() {
+        Some(chip) => chip,
+        None => return Status::NullPointer
+    };
+    match panic::catch_unwind(AssertUnwindSafe(|| f(&mut chip.machine))) {
+        Ok(Ok(())) => Status::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => Status::Panic
+    }
+}
+
+unsafe fn bytes<'a>(ptr: *const Byte, len: usize) -> Result<&'a [Byte], Status> {
+    if len == 0 { return Ok(&[]); }
+    if ptr.is_null() { return Err(Status::NullPointer); }
+    Ok(slice::from_raw_parts(ptr, len))
+}
+
+/// Creates a machine for the platform with the given id (0 CHIP-8,
+/// 1 SUPER-CHIP, 2 XO-CHIP, 3 CHIP-8X) and its default quirks, with no
+/// program loaded, and stores its handle in `out`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn rusty_chip_create(platform: Byte, out: *mut *mut RustyChip) -> Status {
+    if out.is_null() { return Status::NullPointer; }
+    let platform = match Platform::from_id(platform) {
+        Some(platform) => platform,
+        None => return Status::BadPlatform
+    };
+    match panic::catch_unwind(|| Machine::new(&[], platform, platform.default_quirks())) {
+        Ok(Ok(machine)) => {
+            *out = Box::into_raw(Box::new(RustyChip { machine }));
+            Status::Ok
+        },
+        Ok(Err(err)) => err.into(),
+        Err(_) => Status::Panic
+    }
+}
+
+/// Frees a machine. Null is ignored.
+///
+/// # Safety
+///
+/// `chip` must be null or a handle from `rusty_chip_create` that has not
+/// been destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn rusty_chip_destroy(chip: *mut RustyChip) {
+    if !chip.is_null() {
+        drop(Box::from_raw(chip));
+    }
+}
+
+/// Loads a ROM on the machine's platform and restarts. On error the old
+/// program keeps running.
+///
+/// # Safety
+///
+/// `chip` must be null or a live handle, and `rom` must point to `len`
+/// readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rusty_chip_load_rom(chip: *mut RustyChip, rom: *const Byte, len: usize) -> Status {
+    with_chip(chip, |machine| {
+        let rom = bytes(rom, len)?;
+        let platform = machine.platform();
+        Ok(machine.load_rom(rom, platform, platform.default_quirks())?)
+    })
+}
+
+/// Restarts the loaded ROM from power on.
+///
+/// # Safety
+///
+/// `chip` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn rusty_chip_reset(chip: *mut RustyChip) -> Status {
+    with_chip(chip, |machine| Ok(machine.reset()?))
+}
+
+/// Runs up to `n` instructions without ticking the timers, stopping early
+/// if the program exits, and stores how many ran in `ran` unless it is
+/// null.
+///
+/// # Safety
+///
+/// `chip` must be null or a live handle, and `ran` null or valid for a
+/// write.
+#[no_mangle]
+pub unsafe extern "C" fn rusty_chip_step(chip: *mut RustyChip, n: u32, ran: *mut u32) -> Status {
+    with_chip(chip, |machine| {
+        let count = machine.step(n)?;
+        if !ran.is_null() { *ran = count; }
+        Ok(())
+    })
+}
+
+/// Runs one 60 Hz frame: the configured instructions and a timer tick.
+///
+/// # Safety
+///
+/// `chip` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn rusty_chip_run_frame(chip: *mut RustyChip) -> Status {
+    with_chip(chip, |machine| Ok(machine.run_frame()?))
+}
+
+/// Points `pixels` at the display, `width * height` bytes row by row with
+/// bit 0 set for plane 0 and bit 1 for plane 1. The pixels stay valid
+/// until the next call on the machine.
+///
+/// # Safety
+///
+/// `chip` must be null or a live handle, and the other pointers valid for
+/// a write.
+#[no_mangle]
+pub unsafe extern "C" fn rusty_chip_framebuffer(chip: *mut RustyChip, pixels: *mut *const Byte, width: *mut u32, height: *mut u32) -> Status {
+    with_chip(chip, |machine| {
+        if pixels.is_null() || width.is_null() || height.is_null() { return Err(Status::NullPointer); }
+        *width = machine.width() as u32;
+        *height = machine.height() as u32;
+        *pixels = machine.framebuffer().as_ptr();
+        Ok(())
+    })
+}
+
+/// Presses or releases a key, 0x0 to 0xF.
+///
+/// # Safety
+///
+/// `chip` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn rusty_chip_set_key(chip: *mut RustyChip, key: Byte, pressed: bool) -> Status {
+    with_chip(chip, |machine| {
+        if key as usize >= NUM_KEYS { return Err(Status::BadKey); }
+        machine.set_key(key, pressed);
+        Ok(())
+    })
+}
+
+/// Stores whether the sound timer is running in `beep`.
+///
+/// # Safety
+///
+/// `chip` must be null or a live handle, and `beep` valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn rusty_chip_beep(chip: *mut RustyChip, beep: *mut bool) -> Status {
+    with_chip(chip, |machine| {
+        if beep.is_null() { return Err(Status::NullPointer); }
+        *beep = machine.beep();
+        Ok(())
+    })
+}
+
+/// Stores the state's size in `len` and copies it into `buffer`. If the
+/// buffer is null or smaller than the state nothing is copied and
+/// `BufferTooSmall` is returned, so call with a null buffer to size one.
+///
+/// # Safety
+///
+/// `chip` must be null or a live handle, `buffer` null or valid for
+/// `capacity` bytes of writes, and `len` valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn rusty_chip_save_state(chip: *mut RustyChip, buffer: *mut Byte, capacity: usize, len: *mut usize) -> Status {
+    with_chip(chip, |machine| {
+        if len.is_null() { return Err(Status::NullPointer); }
+        let state = machine.save_state();
+        *len = state.len();
+        if buffer.is_null() || capacity < state.len() { return Err(Status::BufferTooSmall); }
+        ptr::copy_nonoverlapping(state.as_ptr(), buffer, state.len());
+        Ok(())
+    })
+}
+
+/// Restores a state from `rusty_chip_save_state`. On error the machine is
+/// unchanged.
+///
+/// # Safety
+///
+/// `chip` must be null or a live handle, and `state` must point to `len`
+/// readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rusty_chip_load_state(chip: *mut RustyChip, state: *const Byte, len: usize) -> Status {
+    with_chip(chip, |machine| Ok(machine.load_state(bytes(state, len)?)?))
+}
+
+/// A static, NUL-terminated description of a status. Takes a plain int so
+/// that C passing a value outside the enum is not undefined behaviour.
+#[no_mangle]
+pub extern "C" fn rusty_chip_status_message(status: i32) -> *const c_char {
+    let message = Status::ALL.iter()
+        .find(|&&known| known as i32 == status)
+        .map_or(&b"unknown status\0"[..], |known| known.message());
+    message.as_ptr() as *const c_char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+    use std::ptr;
+    use machine::TEST_ROM;
+
+    fn create() -> *mut RustyChip {
+        let mut chip = ptr::null_mut();
+        unsafe {
+            assert_eq!(Status::Ok, rusty_chip_create(0, &mut chip));
+            assert_eq!(Status::Ok, rusty_chip_load_rom(chip, TEST_ROM.as_ptr(), TEST_ROM.len()));
+        }
+        chip
+    }
+
+    #[test]
+    fn out_parameters_are_filled_or_rejected() {
+        let chip = create();
+        let (mut pixels, mut width, mut height, mut beep) = (ptr::null(), 0, 0, false);
+        unsafe {
+            assert_eq!(Status::Ok, rusty_chip_run_frame(chip));
+            assert_eq!(Status::Ok, rusty_chip_framebuffer(chip, &mut pixels, &mut width, &mut height));
+            assert!(!pixels.is_null());
+            assert_eq!((64, 32), (width, height));
+            assert_eq!(Status::NullPointer, rusty_chip_framebuffer(chip, ptr::null_mut(), &mut width, &mut height));
+            assert_eq!(Status::Ok, rusty_chip_beep(chip, &mut beep));
+            assert!(beep);
+            assert_eq!(Status::NullPointer, rusty_chip_beep(chip, ptr::null_mut()));
+            rusty_chip_destroy(chip);
+        }
+    }
+
+    #[test]
+    fn step_reports_instructions_run() {
+        let chip = create();
+        // V0 := 1, then stop.
+        let rom = [0x60, 0x01, 0x12, 0x02];
+        let mut ran = 0;
+        unsafe {
+            assert_eq!(Status::Ok, rusty_chip_load_rom(chip, rom.as_ptr(), rom.len()));
+            assert_eq!(Status::Ok, rusty_chip_step(chip, 1, &mut ran));
+            assert_eq!(1, ran);
+            assert_eq!(Status::Ok, rusty_chip_step(chip, 100, &mut ran));
+            assert_eq!(1, ran);
+            assert_eq!(Status::Ok, rusty_chip_reset(chip));
+            assert_eq!(Status::Ok, rusty_chip_step(chip, 100, ptr::null_mut()));
+            rusty_chip_destroy(chip);
+        }
+    }
+
+    #[test]
+    fn save_state_sizes_then_copies() {
+        let chip = create();
+        let mut len = 0;
+        unsafe {
+            assert_eq!(Status::Ok, rusty_chip_step(chip, 1, ptr::null_mut()));
+            assert_eq!(Status::BufferTooSmall, rusty_chip_save_state(chip, ptr::null_mut(), 0, &mut len));
+            let mut state = vec![0; len];
+            assert_eq!(Status::Ok, rusty_chip_save_state(chip, state.as_mut_ptr(), state.len(), &mut len));
+
+            assert_eq!(Status::Ok, rusty_chip_reset(chip));
+            assert_eq!(Status::Ok, rusty_chip_load_state(chip, state.as_ptr(), state.len()));
+            assert_eq!(0x07, (*chip).machine.read_register(0x0));
+            assert_eq!(Status::BadState, rusty_chip_load_state(chip, state.as_ptr(), 4));
+            rusty_chip_destroy(chip);
+        }
+    }
+
+    #[test]
+    fn errors_come_back_as_statuses() {
+        let chip = create();
+        let mut out = ptr::null_mut();
+        let huge = vec![0x0; 0x1000];
+        unsafe {
+            assert_eq!(Status::BadPlatform, rusty_chip_create(7, &mut out));
+            assert_eq!(Status::NullPointer, rusty_chip_create(0, ptr::null_mut()));
+            assert_eq!(Status::NullPointer, rusty_chip_run_frame(ptr::null_mut()));
+            assert_eq!(Status::NullPointer, rusty_chip_load_rom(chip, ptr::null(), 2));
+            assert_eq!(Status::RomTooLarge, rusty_chip_load_rom(chip, huge.as_ptr(), huge.len()));
+            assert_eq!(Status::BadKey, rusty_chip_set_key(chip, 0x10, true));
+            assert_eq!(Status::Ok, rusty_chip_set_key(chip, 0xF, true));
+            rusty_chip_destroy(chip);
+            rusty_chip_destroy(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn faults_stop_with_a_status() {
+        let mut chip = ptr::null_mut();
+        // Return with nothing on the stack.
+        let rom = [0x00, 0xEE];
+        unsafe {
+            assert_eq!(Status::Ok, rusty_chip_create(0, &mut chip));
+            assert_eq!(Status::Ok, rusty_chip_load_rom(chip, rom.as_ptr(), rom.len()));
+            assert_eq!(Status::Fault, rusty_chip_run_frame(chip));
+            rusty_chip_destroy(chip);
+        }
+    }
+
+    #[test]
+    fn status_messages_are_c_strings() {
+        let message = unsafe { CStr::from_ptr(rusty_chip_status_message(Status::BufferTooSmall as i32)) };
+        assert_eq!("buffer is too small", message.to_str().unwrap());
+        let unknown = unsafe { CStr::from_ptr(rusty_chip_status_message(99)) };
+        assert_eq!("unknown status", unknown.to_str().unwrap());
+    }
+
+    /// How the header spells a Rust type from this file.
+    fn c_type(rust: &str) -> String {
+        let pointer = |inner: String| if inner.ends_with('*') { inner + "*" } else { inner + " *" };
+        match rust.trim() {
+            "Byte" => "uint8_t".to_string(),
+            "u32" => "uint32_t".to_string(),
+            "i32" => "int32_t".to_string(),
+            "usize" => "size_t".to_string(),
+            "bool" => "bool".to_string(),
+            "c_char" => "char".to_string(),
+            "Status" => "RustyChipStatus".to_string(),
+            "RustyChip" => "RustyChip".to_string(),
+            rust if rust.starts_with("*mut ") => pointer(c_type(&rust[5..])),
+            rust if rust.starts_with("*const ") => {
+                let inner = c_type(&rust[7..]);
+                pointer(if inner.ends_with('*') { inner } else { format!("const {}", inner) })
+            }
+            rust => panic!("no C spelling for {}", rust)
+        }
+    }
+
+    /// Joins a C type and a name the way the header does: `RustyChip *chip`.
+    fn declare(c_type: &str, name: &str) -> String {
+        if c_type.ends_with('*') { format!("{}{}", c_type, name) } else { format!("{} {}", c_type, name) }
+    }
+
+    /// The prototype the header should have for an exported function.
+    fn prototype(signature: &str) -> String {
+        let open = signature.find('(').unwrap();
+        let close = signature.rfind(')').unwrap();
+        let name = &signature[..open];
+        let params = signature[open + 1..close].split(", ")
+            .map(|param| {
+                let mut parts = param.splitn(2, ':');
+                let name = parts.next().unwrap().trim();
+                declare(&c_type(parts.next().unwrap()), name)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let ret = signature[close + 1..].trim_start_matches(&[' ', '-', '>'][..]);
+        let ret = ret.trim_end_matches('{').trim();
+        let ret = if ret.is_empty() { "void".to_string() } else { c_type(ret) };
+        format!("{}({});", declare(&ret, name), params)
+    }
+
+    #[test]
+    fn header_matches_every_function() {
+        let header = include_str!("../include/rusty_chip.h");
+        let source = include_str!("ffi.rs");
+        let exported = source.lines()
+            .filter_map(|line| line.split("extern \"C\" fn ").nth(1))
+            .filter(|signature| signature.starts_with("rusty_chip_"))
+            .map(prototype)
+            .collect::<Vec<_>>();
+        for prototype in &exported {
+            assert!(header.lines().any(|line| line == prototype), "the header does not declare {}", prototype);
+        }
+        let declared = header.lines().filter(|line| line.contains(" rusty_chip_") || line.contains("*rusty_chip_")).count();
+        assert_eq!(declared, exported.len(), "the header declares functions ffi.rs does not export");
+    }
+
+    #[test]
+    fn header_matches_status_values() {
+        let header = include_str!("../include/rusty_chip.h");
+        let start = header.find("typedef enum RustyChipStatus {").unwrap();
+        let end = start + header[start..].find("} RustyChipStatus;").unwrap();
+        let declared = header[start..end].lines().skip(1)
+            .map(|line| line.trim().trim_end_matches(','))
+            .collect::<Vec<_>>();
+        let expected = Status::ALL.iter()
+            .map(|&status| {
+                let mut name = "RUSTY_CHIP".to_string();
+                for c in format!("{:?}", status).chars() {
+                    if c.is_uppercase() {
+                        name.push('_');
+                    }
+                    name.push(c.to_ascii_uppercase());
+                }
+                format!("{} = {}", name, status as i32)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(declared, expected);
+    }
+}
@@ -0,0 +1,694 @@
+use std::collections::VecDeque;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+
+use cpu::Cpu;
+use cpu::watch::{Access, Watchpoint};
+use debugger::{Debugger, Stop};
+use error::{Error, Result};
+use input::Input;
+use output::graphics::GraphicsOutput;
+use {Address, Byte};
+
+const INTERRUPT: u8 = 0x03;
+const PACKET_SIZE: usize = 0x4000;
+
+/// Register numbers as GDB sees them: V0-VF, then I, PC, SP, DT and ST.
+const REG_I: usize = 0x10;
+const REG_PC: usize = 0x11;
+const REG_SP: usize = 0x12;
+const REG_DT: usize = 0x13;
+const REG_ST: usize = 0x14;
+const NUM_REGS: usize = 0x15;
+
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.rusty-chip.chip8">
+    <reg name="v0" bitsize="8" type="uint8" regnum="0"/>
+    <reg name="v1" bitsize="8" type="uint8"/>
+    <reg name="v2" bitsize="8" type="uint8"/>
+    <reg name="v3" bitsize="8" type="uint8"/>
+    <reg name="v4" bitsize="8" type="uint8"/>
+    <reg name="v5" bitsize="8" type="uint8"/>
+    <reg name="v6" bitsize="8" type="uint8"/>
+    <reg name="v7" bitsize="8" type="uint8"/>
+    <reg name="v8" bitsize="8" type="uint8"/>
+    <reg name="v9" bitsize="8" type="uint8"/>
+    <reg name="va" bitsize="8" type="uint8"/>
+    <reg name="vb" bitsize="8" type="uint8"/>
+    <reg name="vc" bitsize="8" type="uint8"/>
+    <reg name="vd" bitsize="8" type="uint8"/>
+    <reg name="ve" bitsize="8" type="uint8"/>
+    <reg name="vf" bitsize="8" type="uint8"/>
+    <reg name="i" bitsize="16" type="data_ptr"/>
+    <reg name="pc" bitsize="16" type="code_ptr"/>
+    <reg name="sp" bitsize="16" type="data_ptr"/>
+    <reg name="dt" bitsize="8" type="uint8"/>
+    <reg name="st" bitsize="8" type="uint8"/>
+  </feature>
+</target>
+"#;
+
+/// A client connection the stub can check for an interrupt while the
+/// program runs.
+pub trait Connection: Read + Write {
+    /// A byte the client has already sent, without blocking. A client that
+    /// has disconnected is an error.
+    fn poll_byte(&mut self) -> io::Result<Option<Byte>>;
+}
+
+fn poll<S>(stream: &mut S, set_nonblocking: fn(&S, bool) -> io::Result<()>) -> io::Result<Option<Byte>> where S: Read {
+    set_nonblocking(stream, true)?;
+    let mut byte = [0x0];
+    let result = stream.read(&mut byte);
+    set_nonblocking(stream, false)?;
+    match result {
+        Ok(0) => Err(io::Error::new(ErrorKind::UnexpectedEof, "client disconnected")),
+        Ok(_) => Ok(Some(byte[0])),
+        Err(ref err) if err.kind() == ErrorKind::WouldBlock => Ok(None),
+        Err(err) => Err(err)
+    }
+}
+
+impl Connection for TcpStream {
+    fn poll_byte(&mut self) -> io::Result<Option<Byte>> {
+        poll(self, TcpStream::set_nonblocking)
+    }
+}
+
+#[cfg(unix)]
+impl Connection for UnixStream {
+    fn poll_byte(&mut self) -> io::Result<Option<Byte>> {
+        poll(self, UnixStream::set_nonblocking)
+    }
+}
+
+fn checksum(data: &str) -> u8 {
+    data.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte))
+}
+
+fn to_hex(bytes: &[Byte]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<Byte>> {
+    if !text.len().is_multiple_of(2) { return None; }
+    (0..text.len()).step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn number(text: &str) -> Option<usize> {
+    usize::from_str_radix(text, 16).ok()
+}
+
+/// Parses `addr,len`.
+fn range(text: &str) -> Option<(Address, usize)> {
+    let mut parts = text.splitn(2, ',');
+    Some((number(parts.next()?)?, number(parts.next()?)?))
+}
+
+/// Parses `addr,len` as the range it covers, if the end does not overflow.
+fn span(text: &str) -> Option<(Address, Address)> {
+    let (addr, len) = range(text)?;
+    Some((addr, addr.checked_add(len)?))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Packet {
+    Data(String),
+    Interrupt
+}
+
+/// Serves the GDB remote serial protocol for one machine, so gdb or an
+/// IDE can set breakpoints, step and read and write registers and memory.
+#[derive(Debug)]
+pub struct GdbStub<'a, G: 'a, I: 'a> where G: GraphicsOutput, I: Input {
+    debugger: Debugger<'a, G, I>,
+    ack: bool,
+    /// Bytes that arrived while the program ran, still to be read.
+    pending: VecDeque<Byte>
+}
+
+impl<'a, G, I> GdbStub<'a, G, I> where G: GraphicsOutput, I: Input {
+    pub fn new(cpu: Cpu<'a, G, I>) -> GdbStub<'a, G, I> {
+        GdbStub {
+            debugger: Debugger::new(cpu),
+            ack: true,
+            pending: VecDeque::new()
+        }
+    }
+
+    pub fn debugger(&self) -> &Debugger<'a, G, I> {
+        &self.debugger
+    }
+
+    /// Waits for one client on `listener` and serves it.
+    pub fn accept_tcp(&mut self, listener: &TcpListener) -> Result<()> {
+        let (mut stream, _) = listener.accept()?;
+        stream.set_nodelay(true)?;
+        self.serve(&mut stream)
+    }
+
+    #[cfg(unix)]
+    pub fn accept_unix(&mut self, listener: &UnixListener) -> Result<()> {
+        let (mut stream, _) = listener.accept()?;
+        self.serve(&mut stream)
+    }
+
+    /// Handles packets until the client detaches, kills the session or
+    /// disconnects.
+    pub fn serve<C>(&mut self, conn: &mut C) -> Result<()> where C: Connection {
+        self.ack = true;
+        self.pending.clear();
+        while let Some(packet) = self.read_packet(conn)? {
+            let packet = match packet {
+                Packet::Data(packet) => packet,
+                Packet::Interrupt => {
+                    self.send(conn, "S02")?;
+                    continue;
+                }
+            };
+            if packet == "k" { break; }
+            let reply = self.handle(&packet, conn)?;
+            self.send(conn, &reply)?;
+            if packet.starts_with('D') { break; }
+        }
+        Ok(())
+    }
+
+    /// The next byte from the client, or `None` once it disconnects.
+    fn read_byte<C>(&mut self, conn: &mut C) -> Result<Option<Byte>> where C: Connection {
+        if let Some(byte) = self.pending.pop_front() { return Ok(Some(byte)); }
+        let mut byte = [0x0];
+        Ok(if conn.read(&mut byte)? == 0 { None } else { Some(byte[0]) })
+    }
+
+    /// Reads the next packet, acknowledging it, or `None` once the client
+    /// disconnects.
+    fn read_packet<C>(&mut self, conn: &mut C) -> Result<Option<Packet>> where C: Connection {
+        loop {
+            loop {
+                match self.read_byte(conn)? {
+                    None => return Ok(None),
+                    Some(INTERRUPT) => return Ok(Some(Packet::Interrupt)),
+                    Some(b'$') => break,
+                    Some(_) => {}
+                }
+            }
+            let mut data = Vec::new();
+            loop {
+                match self.read_byte(conn)? {
+                    None => return Ok(None),
+                    Some(b'#') => break,
+                    Some(byte) => data.push(byte)
+                }
+                if data.len() > PACKET_SIZE { break; }
+            }
+            if data.len() > PACKET_SIZE {
+                // Refuse it and look for the start of the next packet.
+                if self.ack { conn.write_all(b"-")?; }
+                continue;
+            }
+            let mut sum = [0x0; 2];
+            for digit in sum.iter_mut() {
+                match self.read_byte(conn)? {
+                    None => return Ok(None),
+                    Some(byte) => *digit = byte
+                }
+            }
+            let data = String::from_utf8_lossy(&data).into_owned();
+            let valid = std::str::from_utf8(&sum).ok()
+                .and_then(|sum| u8::from_str_radix(sum, 16).ok())
+                .is_some_and(|sum| sum == checksum(&data));
+            if self.ack {
+                conn.write_all(if valid { b"+" } else { b"-" })?;
+            }
+            if valid { return Ok(Some(Packet::Data(data))); }
+        }
+    }
+
+    /// Sends a packet, sending it again each time the client asks.
+    fn send<C>(&mut self, conn: &mut C, data: &str) -> Result<()> where C: Connection {
+        loop {
+            write!(conn, "${}#{:02x}", data, checksum(data))?;
+            conn.flush()?;
+            if !self.ack || self.read_ack(conn)? { return Ok(()); }
+        }
+    }
+
+    /// Waits for `+` or `-`, returning whether the client accepted the
+    /// packet. Packets the client sent before it, such as those queued
+    /// while the program ran, are kept for `read_packet`.
+    fn read_ack<C>(&mut self, conn: &mut C) -> Result<bool> where C: Connection {
+        let mut kept = Vec::new();
+        let (mut in_packet, mut sum_digits) = (false, 0);
+        let accepted = loop {
+            let byte = match self.read_byte(conn)? {
+                Some(byte) => byte,
+                None => return Err(Error::Io(io::Error::new(ErrorKind::UnexpectedEof, "client disconnected")))
+            };
+            if sum_digits > 0 {
+                sum_digits -= 1;
+            } else if in_packet {
+                if byte == b'#' {
+                    in_packet = false;
+                    sum_digits = 2;
+                }
+            } else {
+                match byte {
+                    b'+' => break true,
+                    b'-' => break false,
+                    b'$' => in_packet = true,
+                    _ => {}
+                }
+            }
+            kept.push(byte);
+        };
+        for byte in kept.into_iter().rev() {
+            self.pending.push_front(byte);
+        }
+        Ok(accepted)
+    }
+
+    fn handle<C>(&mut self, packet: &str, conn: &mut C) -> Result<String> where C: Connection {
+        let (command, args) = match packet.get(..1) {
+            Some(command) => (command, &packet[1..]),
+            None => return Ok(String::new())
+        };
+        let reply = match command {
+            "?" => "S05".to_string(),
+            "g" => to_hex(&(0..NUM_REGS).flat_map(|n| self.register(n)).collect::<Vec<Byte>>()),
+            "G" => match from_hex(args) {
+                Some(bytes) => {
+                    let mut offset = 0;
+                    let mut reply = "OK";
+                    for n in 0..NUM_REGS {
+                        let size = register_size(n);
+                        if let Some(value) = bytes.get(offset..offset + size) {
+                            if n != REG_SP && self.set_register(n, value).is_err() { reply = "E01"; }
+                        }
+                        offset += size;
+                    }
+                    reply.to_string()
+                },
+                None => "E01".to_string()
+            },
+            "p" => match number(args) {
+                Some(n) if n < NUM_REGS => to_hex(&self.register(n)),
+                _ => "E01".to_string()
+            },
+            "P" => {
+                let mut parts = args.splitn(2, '=');
+                match (parts.next().and_then(number), parts.next().and_then(from_hex)) {
+                    (Some(n), Some(ref value)) if n < NUM_REGS && n != REG_SP && value.len() == register_size(n) => {
+                        match self.set_register(n, value) {
+                            Ok(()) => "OK".to_string(),
+                            Err(_) => "E01".to_string()
+                        }
+                    },
+                    _ => "E01".to_string()
+                }
+            },
+            "m" => {
+                let memory = self.debugger.cpu().memory();
+                match span(args) {
+                    Some((start, end)) if end <= memory.len() => to_hex(&memory[start..end]),
+                    _ => "E01".to_string()
+                }
+            },
+            "M" => {
+                let mut parts = args.splitn(2, ':');
+                match (parts.next().and_then(span), parts.next().and_then(from_hex)) {
+                    (Some((start, end)), Some(ref bytes)) if bytes.len() == end - start => {
+                        match self.debugger.cpu_mut().write_memory(start, bytes) {
+                            Ok(()) => "OK".to_string(),
+                            Err(_) => "E01".to_string()
+                        }
+                    },
+                    _ => "E01".to_string()
+                }
+            },
+            "s" | "c" => {
+                if let Some(addr) = number(args) {
+                    if self.debugger.cpu_mut().set_pc(addr).is_err() { return Ok("E01".to_string()); }
+                }
+                let stop = if command == "s" { self.debugger.step() } else { self.resume(conn) };
+                match stop {
+                    Ok(Stop::Exited) => "W00".to_string(),
+                    Ok(Stop::Limit) => "S02".to_string(),
+                    Ok(Stop::Watchpoint(hit)) => match hit.access {
+                        Access::Write => format!("T05watch:{:x};", hit.addr),
+                        Access::Read => format!("T05rwatch:{:x};", hit.addr),
+                        Access::Execute => "S05".to_string()
+                    },
+                    Ok(_) => "S05".to_string(),
+                    Err(Error::Io(err)) => return Err(Error::Io(err)),
+                    Err(_) => "S04".to_string()
+                }
+            },
+            "Z" | "z" => {
+                let mut parts = args.split(',');
+                match (parts.next(), parts.next().and_then(number), parts.next().and_then(number)) {
+                    (Some("0"), Some(addr), _) | (Some("1"), Some(addr), _) => {
+                        if command == "Z" {
+                            self.debugger.add_breakpoint(addr);
+                        } else {
+                            self.debugger.remove_breakpoint(addr);
+                        }
+                        "OK".to_string()
+                    },
+                    (Some(kind @ "2"), Some(addr), Some(len)) | (Some(kind @ "3"), Some(addr), Some(len))
+                        | (Some(kind @ "4"), Some(addr), Some(len)) => {
+                        let end = match addr.checked_add(len.max(1)) {
+                            Some(end) => end,
+                            None => return Ok("E01".to_string())
+                        };
+                        let cpu = self.debugger.cpu_mut();
+                        if command == "Z" {
+                            let watchpoint = Watchpoint::new(addr..end);
+                            cpu.add_watchpoint(match kind {
+                                "2" => watchpoint.write(),
+                                "3" => watchpoint.read(),
+                                _ => watchpoint.read().write()
+                            });
+                        } else {
+                            cpu.remove_watchpoint(addr);
+                        }
+                        "OK".to_string()
+                    },
+                    _ => String::new()
+                }
+            },
+            "H" => "OK".to_string(),
+            "T" => "OK".to_string(),
+            "D" => "OK".to_string(),
+            "q" | "Q" => self.query(packet),
+            _ => String::new()
+        };
+        Ok(reply)
+    }
+
+    fn query(&mut self, packet: &str) -> String {
+        if packet.starts_with("qSupported") {
+            format!("PacketSize={:x};qXfer:features:read+;QStartNoAckMode+", PACKET_SIZE)
+        } else if packet == "QStartNoAckMode" {
+            self.ack = false;
+            "OK".to_string()
+        } else if packet == "qAttached" {
+            "1".to_string()
+        } else if packet == "qC" {
+            "QC1".to_string()
+        } else if packet == "qfThreadInfo" {
+            "m1".to_string()
+        } else if packet == "qsThreadInfo" {
+            "l".to_string()
+        } else if let Some(args) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            match range(args) {
+                Some((offset, len)) => {
+                    let xml = TARGET_XML.as_bytes();
+                    let start = offset.min(xml.len());
+                    let end = offset.saturating_add(len).min(xml.len());
+                    let chunk = String::from_utf8_lossy(&xml[start..end]);
+                    format!("{}{}", if end == xml.len() { "l" } else { "m" }, chunk)
+                },
+                None => "E01".to_string()
+            }
+        } else {
+            String::new()
+        }
+    }
+
+    /// Continues until a stop, checking for an interrupt each time the
+    /// debugger hands back control. Anything else the client sends in the
+    /// meantime is kept for `read_packet`.
+    fn resume<C>(&mut self, conn: &mut C) -> Result<Stop> where C: Connection {
+        loop {
+            match self.debugger.resume()? {
+                Stop::Limit => match conn.poll_byte()? {
+                    Some(INTERRUPT) => return Ok(Stop::Limit),
+                    Some(byte) => self.pending.push_back(byte),
+                    None => {}
+                },
+                stop => return Ok(stop)
+            }
+        }
+    }
+
+    /// The register's value as little-endian bytes.
+    fn register(&self, n: usize) -> Vec<Byte> {
+        let cpu = self.debugger.cpu();
+        let value = match n {
+            REG_I => cpu.read_i(),
+            REG_PC => cpu.pc(),
+            REG_SP => cpu.sp(),
+            REG_DT => cpu.read_delay_timer() as Address,
+            REG_ST => cpu.read_sound_timer() as Address,
+            x => cpu.read_register(x) as Address
+        };
+        (0..register_size(n)).map(|byte| (value >> (8 * byte)) as Byte).collect()
+    }
+
+    fn set_register(&mut self, n: usize, value: &[Byte]) -> Result<()> {
+        let value = value.iter().rev().fold(0, |acc, byte| acc << 8 | *byte as Address);
+        let cpu = self.debugger.cpu_mut();
+        match n {
+            REG_I => cpu.load_i(value)?,
+            REG_PC => cpu.set_pc(value)?,
+            REG_DT => cpu.load_delay_timer(value as Byte),
+            REG_ST => cpu.load_sound_timer(value as Byte),
+            REG_SP => {},
+            x => cpu.load_register(x, value as Byte)
+        }
+        Ok(())
+    }
+}
+
+fn register_size(n: usize) -> usize {
+    match n {
+        REG_I | REG_PC | REG_SP => 2,
+        _ => 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+    use cpu::platform::Platform;
+    use cpu::quirks::Quirks;
+    use input::keypad::Keypad;
+    use output::graphics::Display;
+
+    /// A minimal RSP client speaking to the stub over TCP.
+    struct Client {
+        stream: TcpStream
+    }
+
+    impl Client {
+        fn request(&mut self, data: &str) -> String {
+            write!(self.stream, "${}#{:02x}", data, checksum(data)).unwrap();
+            let mut byte = [0x0];
+            self.stream.read_exact(&mut byte).unwrap();
+            assert_eq!(b'+', byte[0]);
+            self.reply()
+        }
+
+        fn reply(&mut self) -> String {
+            let data = self.packet();
+            self.stream.write_all(b"+").unwrap();
+            data
+        }
+
+        /// Reads a packet without acknowledging it.
+        fn packet(&mut self) -> String {
+            let mut byte = [0x0];
+            let mut data = Vec::new();
+            loop {
+                self.stream.read_exact(&mut byte).unwrap();
+                if byte[0] == b'$' { break; }
+            }
+            loop {
+                self.stream.read_exact(&mut byte).unwrap();
+                if byte[0] == b'#' { break; }
+                data.push(byte[0]);
+            }
+            let mut sum = [0x0; 2];
+            self.stream.read_exact(&mut sum).unwrap();
+            let data = String::from_utf8(data).unwrap();
+            assert_eq!(format!("{:02x}", checksum(&data)).as_bytes(), &sum);
+            data
+        }
+    }
+
+    /// V0 := 5, then a loop adding 1 to V1.
+    const ROM: [Byte; 6] = [0x60, 0x05, 0x71, 0x01, 0x12, 0x02];
+
+    /// Runs `script` against a stub serving `rom`, returning how the stub
+    /// finished.
+    fn try_session<F>(rom: &'static [Byte], script: F) -> Result<()> where F: FnOnce(&mut Client) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let mut graphics = Display::new();
+            let mut keypad = Keypad::new();
+            let cpu = Cpu::new(rom, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+            GdbStub::new(cpu).accept_tcp(&listener)
+        });
+        let stream = TcpStream::connect(addr).unwrap();
+        stream.set_nodelay(true).unwrap();
+        let mut client = Client { stream };
+        script(&mut client);
+        drop(client);
+        server.join().unwrap()
+    }
+
+    fn session<F>(rom: &'static [Byte], script: F) where F: FnOnce(&mut Client) {
+        try_session(rom, script).unwrap();
+    }
+
+    #[test]
+    fn reads_registers_and_memory() {
+        session(&ROM, |client| {
+            assert!(client.request("qSupported:xmlRegisters=i386").contains("qXfer:features:read+"));
+            assert_eq!("S05", client.request("?"));
+            let registers = client.request("g");
+            assert_eq!(2 * (16 + 2 + 2 + 2 + 1 + 1), registers.len());
+            assert_eq!("0002", client.request("p11"));
+            assert_eq!("600571011202", client.request("m200,6"));
+            assert_eq!("E01", client.request("m10000,1"));
+            assert_eq!("E01", client.request("m200,ffffffffffffffff"));
+            client.request("D");
+        });
+    }
+
+    #[test]
+    fn malformed_packets_get_errors() {
+        session(&ROM, |client| {
+            assert_eq!("", client.request(""));
+            assert_eq!("", client.request("\u{e9}"));
+            assert_eq!("E01", client.request("Mffffffffffffffff,1:00"));
+            assert_eq!("E01", client.request("Z2,ffffffffffffffff,2"));
+            assert_eq!("S05", client.request("?"));
+            client.request("D");
+        });
+    }
+
+    #[test]
+    fn oversized_packets_are_refused() {
+        session(&ROM, |client| {
+            write!(client.stream, "${}", "0".repeat(PACKET_SIZE + 1)).unwrap();
+            let mut byte = [0x0];
+            client.stream.read_exact(&mut byte).unwrap();
+            assert_eq!(b'-', byte[0]);
+            assert_eq!("S05", client.request("?"));
+            client.request("D");
+        });
+    }
+
+    #[test]
+    fn refused_replies_are_sent_again() {
+        session(&ROM, |client| {
+            write!(client.stream, "$?#{:02x}", checksum("?")).unwrap();
+            let mut byte = [0x0];
+            client.stream.read_exact(&mut byte).unwrap();
+            assert_eq!(b'+', byte[0]);
+            assert_eq!("S05", client.packet());
+            client.stream.write_all(b"-").unwrap();
+            assert_eq!("S05", client.reply());
+            client.request("D");
+        });
+    }
+
+    #[test]
+    fn packets_sent_while_running_are_kept() {
+        session(&ROM, |client| {
+            write!(client.stream, "$c#{:02x}", checksum("c")).unwrap();
+            write!(client.stream, "$?#{:02x}", checksum("?")).unwrap();
+            client.stream.write_all(&[INTERRUPT]).unwrap();
+            assert_eq!("S02", client.reply());
+            assert_eq!("S05", client.reply());
+            client.request("D");
+        });
+    }
+
+    #[test]
+    fn disconnect_while_running_ends_the_session() {
+        let result = try_session(&ROM, |client| {
+            write!(client.stream, "$c#{:02x}", checksum("c")).unwrap();
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn step_and_write_registers() {
+        session(&ROM, |client| {
+            assert_eq!("S05", client.request("s"));
+            assert_eq!("05", client.request("p0"));
+            assert_eq!("OK", client.request("P1=2a"));
+            assert_eq!("2a", client.request("p1"));
+            assert_eq!("OK", client.request("P10=0003"));
+            assert_eq!("0003", client.request("p10"));
+            assert_eq!("E01", client.request("P12=0000"));
+            client.request("D");
+        });
+    }
+
+    #[test]
+    fn breakpoints_stop_continue() {
+        session(&ROM, |client| {
+            assert_eq!("OK", client.request("Z0,204,2"));
+            assert_eq!("S05", client.request("c"));
+            assert_eq!("0402", client.request("p11"));
+            assert_eq!("S05", client.request("c"));
+            assert_eq!("02", client.request("p1"));
+            assert_eq!("OK", client.request("z0,204,2"));
+            client.request("D");
+        });
+    }
+
+    /// V0 := 5, stores it at 300, then loads it back.
+    const STORE_LOAD_ROM: [Byte; 12] = [0x60, 0x05, 0xA3, 0x00, 0xF0, 0x55, 0xA3, 0x00, 0xF0, 0x65, 0x12, 0x00];
+
+    #[test]
+    fn watchpoints_report_accessed_address() {
+        session(&STORE_LOAD_ROM, |client| {
+            assert_eq!("OK", client.request("Z2,300,1"));
+            assert_eq!("T05watch:300;", client.request("c"));
+            assert_eq!("0602", client.request("p11"));
+            assert_eq!("OK", client.request("z2,300,1"));
+            assert_eq!("OK", client.request("Z3,300,1"));
+            assert_eq!("T05rwatch:300;", client.request("c"));
+            assert_eq!("0a02", client.request("p11"));
+            client.request("D");
+        });
+    }
+
+    #[test]
+    fn write_memory_then_kill() {
+        session(&ROM, |client| {
+            assert_eq!("OK", client.request("M202,2:6142"));
+            assert_eq!("6142", client.request("m202,2"));
+            assert_eq!("OK", client.request("QStartNoAckMode"));
+            write!(client.stream, "$k#{:02x}", checksum("k")).unwrap();
+        });
+    }
+
+    #[test]
+    fn serves_target_description() {
+        session(&ROM, |client| {
+            let mut xml = String::new();
+            loop {
+                let reply = client.request(&format!("qXfer:features:read:target.xml:{:x},100", xml.len()));
+                xml.push_str(&reply[1..]);
+                if reply.starts_with('l') { break; }
+            }
+            assert_eq!(TARGET_XML, xml);
+            client.request("D");
+        });
+    }
+}
@@ -0,0 +1,271 @@
+use std::fs;
+use std::path::Path;
+
+use cpu::Cpu;
+use cpu::clock::DEFAULT_INSTRUCTIONS_PER_FRAME;
+use cpu::platform::Platform;
+use cpu::quirks::Quirks;
+use cpu::random::SeededRandom;
+use error::{Error, Result};
+use input::keypad::Keypad;
+use output::graphics::{self, GraphicsOutput};
+use runner::Runner;
+use Byte;
+
+/// Seed for CXKK so runs with the same script draw the same screen.
+const SEED: u64 = 0;
+
+/// A monochrome capture of the display. A pixel is lit if it is set on
+/// any plane.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<bool>
+}
+
+impl Image {
+    pub fn capture<G>(graphics: &G) -> Image where G: GraphicsOutput {
+        let (width, height) = (graphics.width(), graphics.height());
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| (0..graphics::NUM_PLANES).any(|plane| graphics.read_plane_pixel(plane, x, y)))
+            .collect();
+        Image { width, height, pixels }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Plain (P1) PBM, one row of the display per line.
+    pub fn to_pbm(&self) -> String {
+        let mut pbm = format!("P1\n{} {}\n", self.width, self.height);
+        for row in self.pixels.chunks(self.width) {
+            pbm.extend(row.iter().map(|&lit| if lit { '1' } else { '0' }));
+            pbm.push('\n');
+        }
+        pbm
+    }
+
+    /// Reads a plain PBM. Whitespace between pixels is optional and `#`
+    /// starts a comment.
+    pub fn from_pbm(text: &str) -> Result<Image> {
+        let mut tokens = text.lines()
+            .map(|line| line.split('#').next().unwrap_or(""))
+            .flat_map(|line| line.split_whitespace());
+        if tokens.next() != Some("P1") {
+            return Err(Error::BadImage("not a plain PBM"));
+        }
+        let mut dimension = || tokens.next().and_then(|token| token.parse::<usize>().ok())
+            .ok_or(Error::BadImage("missing dimensions"));
+        let (width, height) = (dimension()?, dimension()?);
+
+        let pixels = tokens.flat_map(|token| token.chars())
+            .map(|c| match c {
+                '0' => Ok(false),
+                '1' => Ok(true),
+                _ => Err(Error::BadImage("pixels must be 0 or 1"))
+            })
+            .collect::<Result<Vec<bool>>>()?;
+        if pixels.len() != width * height {
+            return Err(Error::BadImage("pixel count does not match dimensions"));
+        }
+        Ok(Image { width, height, pixels })
+    }
+
+    /// Describes how this image differs from `expected`, or `None` if they
+    /// match. The map marks pixels that should be off with `+` and pixels
+    /// that should be on with `-`.
+    pub fn diff(&self, expected: &Image) -> Option<String> {
+        if (self.width, self.height) != (expected.width, expected.height) {
+            return Some(format!("Image is {}x{} but expected {}x{}",
+                self.width, self.height, expected.width, expected.height));
+        }
+        let wrong = self.pixels.iter().zip(&expected.pixels).filter(|&(got, want)| got != want).count();
+        if wrong == 0 { return None; }
+
+        let mut text = format!("{} of {} pixels differ", wrong, self.pixels.len());
+        for (got, want) in self.pixels.chunks(self.width).zip(expected.pixels.chunks(self.width)) {
+            text.push('\n');
+            text.extend(got.iter().zip(want).map(|pixel| match pixel {
+                (true, true) => '#',
+                (false, false) => '.',
+                (true, false) => '+',
+                (false, true) => '-'
+            }));
+        }
+        Some(text)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct KeyChange {
+    frame: u32,
+    key: Byte,
+    pressed: bool
+}
+
+/// Runs a ROM without a window for a fixed number of frames, pressing and
+/// releasing keys on the frames it is told to, then captures the display.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Harness {
+    platform: Platform,
+    quirks: Quirks,
+    frames: u32,
+    instructions_per_frame: u32,
+    script: Vec<KeyChange>
+}
+
+impl Harness {
+    pub fn new(platform: Platform) -> Harness {
+        Harness {
+            platform,
+            quirks: platform.default_quirks(),
+            frames: 60,
+            instructions_per_frame: DEFAULT_INSTRUCTIONS_PER_FRAME,
+            script: Vec::new()
+        }
+    }
+
+    pub fn quirks(mut self, quirks: Quirks) -> Harness {
+        self.quirks = quirks;
+        self
+    }
+
+    pub fn frames(mut self, frames: u32) -> Harness {
+        self.frames = frames;
+        self
+    }
+
+    pub fn instructions_per_frame(mut self, instructions: u32) -> Harness {
+        self.instructions_per_frame = instructions.max(1);
+        self
+    }
+
+    /// Presses `key` at the start of `frame`, counting from 0.
+    pub fn press(mut self, frame: u32, key: Byte) -> Harness {
+        self.script.push(KeyChange { frame, key, pressed: true });
+        self
+    }
+
+    pub fn release(mut self, frame: u32, key: Byte) -> Harness {
+        self.script.push(KeyChange { frame, key, pressed: false });
+        self
+    }
+
+    /// Runs the frames, stopping early if the program exits.
+    pub fn run(&self, rom: &[Byte]) -> Result<Image> {
+        let mut graphics = graphics::Display::quiet();
+        let mut keypad = Keypad::new();
+        let mut cpu = Cpu::new(rom, &mut graphics, &mut keypad, self.platform, self.quirks)?;
+        cpu.set_random(SeededRandom::new(SEED));
+        let mut runner = Runner::new(cpu);
+        runner.set_instructions_per_frame(self.instructions_per_frame);
+        self.run_runner(&mut runner)?;
+        Ok(Image::capture(runner.cpu().graphics()))
+    }
+
+    /// Runs the frames and script on a runner the caller has set up, for
+    /// runs that need their own seed or a trace. The platform, quirks and
+    /// instructions per frame are the runner's, not the harness's.
+    pub fn run_runner<G>(&self, runner: &mut Runner<G, Keypad>) -> Result<()> where G: GraphicsOutput {
+        for frame in 0..self.frames {
+            for change in self.script.iter().filter(|change| change.frame == frame) {
+                let keypad = runner.cpu_mut().input_mut();
+                if change.pressed { keypad.press(change.key); } else { keypad.release(change.key); }
+            }
+            runner.run_frame()?;
+        }
+        Ok(())
+    }
+
+    /// Runs the ROM and compares the display with the PBM at `golden`,
+    /// failing with the diff if they differ.
+    pub fn check<P>(&self, rom: &[Byte], golden: P) -> Result<()> where P: AsRef<Path> {
+        let expected = Image::from_pbm(&fs::read_to_string(golden)?)?;
+        match self.run(rom)?.diff(&expected) {
+            Some(diff) => Err(Error::ImageMismatch(diff)),
+            None => Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use load_rom;
+
+    const GOLDEN_DIR: &str = "rom/golden";
+
+    /// Checks a fixture on every platform that runs it. Set
+    /// `UPDATE_GOLDEN=1` to rewrite the golden image instead.
+    fn fixture(rom: &str, golden: &str, frames: u32) {
+        let rom = load_rom("rom", rom).unwrap();
+        let path = Path::new(GOLDEN_DIR).join(golden);
+        if env::var_os("UPDATE_GOLDEN").is_some() {
+            let image = Harness::new(Platform::Chip8).frames(frames).run(&rom).unwrap();
+            fs::write(&path, image.to_pbm()).unwrap();
+        }
+        for &platform in &[Platform::Chip8, Platform::SuperChip, Platform::XoChip] {
+            if let Err(err) = Harness::new(platform).frames(frames).check(&rom, &path) {
+                panic!("{} on {:?}: {}", golden, platform, err);
+            }
+        }
+    }
+
+    #[test]
+    fn ibm_logo_matches_golden() {
+        fixture("ibm.ch8", "ibm.pbm", 30);
+    }
+
+    #[test]
+    fn logo_matches_golden() {
+        fixture("logo.ch8", "logo.pbm", 120);
+    }
+
+    #[test]
+    fn pbm_round_trips() {
+        let pbm = "P1\n# comment\n4 2\n1001\n0 1 1 0\n";
+        let image = Image::from_pbm(pbm).unwrap();
+        assert!(image.pixel(0, 0) && image.pixel(1, 1) && !image.pixel(1, 0));
+        assert_eq!("P1\n4 2\n1001\n0110\n", image.to_pbm());
+        assert_eq!(image, Image::from_pbm(&image.to_pbm()).unwrap());
+    }
+
+    #[test]
+    fn bad_pbm_errors() {
+        assert!(Image::from_pbm("P4\n1 1\n").is_err());
+        assert!(Image::from_pbm("P1\n2 2\n101\n").is_err());
+        assert!(Image::from_pbm("P1\n1 1\n2\n").is_err());
+    }
+
+    #[test]
+    fn diff_marks_wrong_pixels() {
+        let got = Image::from_pbm("P1\n3 1\n110\n").unwrap();
+        let expected = Image::from_pbm("P1\n3 1\n011\n").unwrap();
+        assert_eq!(Some("2 of 3 pixels differ\n+#-".to_string()), got.diff(&expected));
+        assert_eq!(None, got.diff(&got));
+        let larger = Image::from_pbm("P1\n1 1\n0\n").unwrap();
+        assert_eq!(Some("Image is 3x1 but expected 1x1".to_string()), got.diff(&larger));
+    }
+
+    #[test]
+    fn scripted_keys_reach_the_program() {
+        // V0 := key, then draw the digit for it.
+        let rom = [0xF0, 0x0A, 0xF0, 0x29, 0xD1, 0x15, 0x12, 0x06];
+        let pressed = Harness::new(Platform::Chip8).frames(10).press(2, 0x7).release(4, 0x7).run(&rom).unwrap();
+        let idle = Harness::new(Platform::Chip8).frames(10).run(&rom).unwrap();
+        assert!(!idle.pixel(0, 0));
+        assert!(pressed.pixel(0, 0) && pressed.pixel(3, 0));
+    }
+}
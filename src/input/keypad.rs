@@ -0,0 +1,225 @@
+use std::fmt;
+
+use input::Input;
+use Byte;
+
+pub const NUM_KEYS: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyEvent {
+    Pressed(Byte),
+    Released(Byte)
+}
+
+#[derive(Clone, Default, PartialEq)]
+pub struct Keypad {
+    state: u16,
+    events: Vec<KeyEvent>
+}
+
+impl Keypad {
+    pub fn new() -> Keypad {
+        Keypad {
+            state: 0x0,
+            events: Vec::new()
+        }
+    }
+
+    pub fn press(&mut self, key: Byte) {
+        if self.key_pressed(key) { return; }
+
+        self.state |= Keypad::mask(key);
+        self.events.push(KeyEvent::Pressed(key & 0xF));
+    }
+
+    pub fn release(&mut self, key: Byte) {
+        if !self.key_pressed(key) { return; }
+
+        self.state &= !Keypad::mask(key);
+        self.events.push(KeyEvent::Released(key & 0xF));
+    }
+
+    pub fn release_all(&mut self) {
+        for key in 0..NUM_KEYS as Byte {
+            self.release(key);
+        }
+    }
+
+    pub fn state(&self) -> u16 {
+        self.state
+    }
+
+    pub fn set_state(&mut self, state: u16) {
+        for key in 0..NUM_KEYS as Byte {
+            if state & Keypad::mask(key) != 0 {
+                self.press(key);
+            } else {
+                self.release(key);
+            }
+        }
+    }
+
+    /// The presses and releases since the end of the last frame.
+    pub fn events(&self) -> &[KeyEvent] {
+        &self.events
+    }
+
+    pub fn drain_events(&mut self) -> Vec<KeyEvent> {
+        self.events.drain(..).collect()
+    }
+
+    fn mask(key: Byte) -> u16 {
+        0b1 << (key & 0xF)
+    }
+}
+
+impl Input for Keypad {
+    fn key_pressed(&self, key: Byte) -> bool {
+        self.state & Keypad::mask(key) != 0
+    }
+
+    fn set_state(&mut self, state: u16) {
+        Keypad::set_state(self, state);
+    }
+
+    fn state(&self) -> u16 {
+        self.state
+    }
+
+    fn end_frame(&mut self) {
+        self.events.clear();
+    }
+}
+
+/// The pair of keypads on a CHIP-8X machine. The first answers the classic
+/// key instructions and the second answers EXF2 and EXF5.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DualKeypad {
+    pub first: Keypad,
+    pub second: Keypad
+}
+
+impl DualKeypad {
+    pub fn new() -> DualKeypad {
+        DualKeypad {
+            first: Keypad::new(),
+            second: Keypad::new()
+        }
+    }
+}
+
+impl Input for DualKeypad {
+    fn key_pressed(&self, key: Byte) -> bool {
+        self.first.key_pressed(key)
+    }
+
+    fn second_key_pressed(&self, key: Byte) -> bool {
+        self.second.key_pressed(key)
+    }
+
+    fn set_state(&mut self, state: u16) {
+        self.first.set_state(state);
+    }
+
+    fn set_second_state(&mut self, state: u16) {
+        self.second.set_state(state);
+    }
+
+    fn end_frame(&mut self) {
+        self.first.end_frame();
+        self.second.end_frame();
+    }
+}
+
+impl fmt::Debug for Keypad {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Keypad {{ state: {:016b}, events: {:?} }}", self.state, self.events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_keypad_has_no_keys_pressed() {
+        let k = Keypad::new();
+        assert_eq!(0x0, k.state());
+        assert!((0..NUM_KEYS as Byte).all(|key| !k.key_pressed(key)));
+        assert!(k.events().is_empty());
+    }
+
+    #[test]
+    fn press_sets_key_bit() {
+        let mut k = Keypad::new();
+
+        k.press(0xA);
+        assert!(k.key_pressed(0xA));
+        assert_eq!(0b1 << 0xA, k.state());
+    }
+
+    #[test]
+    fn release_clears_key_bit() {
+        let mut k = Keypad::new();
+
+        k.press(0xA);
+        k.press(0x3);
+        k.release(0xA);
+        assert!(!k.key_pressed(0xA));
+        assert!(k.key_pressed(0x3));
+    }
+
+    #[test]
+    fn press_and_release_record_edge_events() {
+        let mut k = Keypad::new();
+
+        k.press(0x5);
+        k.press(0x5);
+        k.release(0x5);
+        k.release(0x5);
+        assert_eq!(vec![KeyEvent::Pressed(0x5), KeyEvent::Released(0x5)], k.drain_events());
+        assert!(k.events().is_empty());
+    }
+
+    #[test]
+    fn set_state_records_changed_keys_only() {
+        let mut k = Keypad::new();
+        k.press(0x1);
+        k.drain_events();
+
+        k.set_state(0b1 << 0x1 | 0b1 << 0xF);
+        assert_eq!(vec![KeyEvent::Pressed(0xF)], k.drain_events());
+
+        k.set_state(0x0);
+        assert_eq!(vec![KeyEvent::Released(0x1), KeyEvent::Released(0xF)], k.drain_events());
+    }
+
+    #[test]
+    fn release_all_clears_state() {
+        let mut k = Keypad::new();
+        k.press(0x0);
+        k.press(0xE);
+
+        k.release_all();
+        assert_eq!(0x0, k.state());
+    }
+
+    #[test]
+    fn key_masks_to_low_nibble() {
+        let mut k = Keypad::new();
+
+        k.press(0x1F);
+        assert!(k.key_pressed(0xF));
+        assert_eq!(vec![KeyEvent::Pressed(0xF)], k.drain_events());
+    }
+
+    #[test]
+    fn dual_keypad_reads_each_pad() {
+        let mut k = DualKeypad::new();
+        k.second.press(0x3);
+
+        assert!(!k.key_pressed(0x3));
+        assert!(k.second_key_pressed(0x3));
+        assert!(!Keypad::new().second_key_pressed(0x3));
+    }
+}
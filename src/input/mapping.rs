@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+
+use input::keypad::{Keypad, NUM_KEYS};
+use Byte;
+
+/// Keypad keys in the order they appear on the COSMAC VIP hex keypad,
+/// left to right and top to bottom.
+pub const LAYOUT: [Byte; NUM_KEYS] = [
+    0x1, 0x2, 0x3, 0xC,
+    0x4, 0x5, 0x6, 0xD,
+    0x7, 0x8, 0x9, 0xE,
+    0xA, 0x0, 0xB, 0xF
+];
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum HostKey {
+    Char(char),
+    Numpad(char)
+}
+
+impl HostKey {
+    pub fn from_char(c: char) -> HostKey {
+        HostKey::Char(c).normalised()
+    }
+
+    /// Letters are bound and looked up case-blind, so shift or caps lock
+    /// does not unbind them.
+    fn normalised(self) -> HostKey {
+        match self {
+            HostKey::Char(c) => HostKey::Char(c.to_ascii_lowercase()),
+            numpad => numpad
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KeyMap {
+    bindings: HashMap<HostKey, Byte>
+}
+
+impl KeyMap {
+    pub fn new() -> KeyMap {
+        KeyMap {
+            bindings: HashMap::new()
+        }
+    }
+
+    /// Binds each character of `rows` to the keypad key at the same
+    /// position in `LAYOUT`, ignoring whitespace.
+    pub fn from_layout(rows: &str) -> Option<KeyMap> {
+        let chars: Vec<char> = rows.chars().filter(|c| !c.is_whitespace()).collect();
+        if chars.len() != NUM_KEYS { return None; }
+
+        let mut map = KeyMap::new();
+        for (c, key) in chars.iter().zip(LAYOUT.iter()) {
+            map.bind(HostKey::from_char(*c), *key);
+        }
+        Some(map)
+    }
+
+    pub fn qwerty() -> KeyMap {
+        KeyMap::from_layout("1234 qwer asdf zxcv").unwrap()
+    }
+
+    pub fn azerty() -> KeyMap {
+        let mut map = KeyMap::from_layout("1234 azer qsdf wxcv").unwrap();
+        for (c, key) in "&é\"'".chars().zip(LAYOUT.iter()) {
+            map.bind(HostKey::Char(c), *key);
+        }
+        map
+    }
+
+    pub fn numpad() -> KeyMap {
+        let mut map = KeyMap::new();
+        for (c, key) in "789/456*123-0.\n+".chars().zip(LAYOUT.iter()) {
+            map.bind(HostKey::Numpad(c), *key);
+        }
+        map
+    }
+
+    pub fn bind(&mut self, host: HostKey, key: Byte) {
+        self.bindings.insert(host.normalised(), key & 0xF);
+    }
+
+    pub fn unbind(&mut self, host: HostKey) {
+        self.bindings.remove(&host.normalised());
+    }
+
+    pub fn key(&self, host: HostKey) -> Option<Byte> {
+        self.bindings.get(&host.normalised()).cloned()
+    }
+
+    /// Presses the keypad key bound to `host`, returning whether it was bound.
+    pub fn press(&self, keypad: &mut Keypad, host: HostKey) -> bool {
+        match self.key(host) {
+            Some(key) => { keypad.press(key); true },
+            None => false
+        }
+    }
+
+    /// Releases the keypad key bound to `host`, returning whether it was bound.
+    pub fn release(&self, keypad: &mut Keypad, host: HostKey) -> bool {
+        match self.key(host) {
+            Some(key) => { keypad.release(key); true },
+            None => false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use input::Input;
+
+    #[test]
+    fn qwerty_maps_left_hand_block() {
+        let map = KeyMap::qwerty();
+        assert_eq!(Some(0x1), map.key(HostKey::Char('1')));
+        assert_eq!(Some(0xC), map.key(HostKey::Char('4')));
+        assert_eq!(Some(0x5), map.key(HostKey::Char('w')));
+        assert_eq!(Some(0xE), map.key(HostKey::Char('f')));
+        assert_eq!(Some(0xA), map.key(HostKey::Char('z')));
+        assert_eq!(Some(0xF), map.key(HostKey::Char('v')));
+        assert_eq!(None, map.key(HostKey::Char('5')));
+    }
+
+    #[test]
+    fn letters_are_case_blind() {
+        let mut map = KeyMap::qwerty();
+        assert_eq!(Some(0x5), map.key(HostKey::Char('W')));
+
+        map.bind(HostKey::Char('K'), 0x0);
+        assert_eq!(Some(0x0), map.key(HostKey::Char('k')));
+        map.unbind(HostKey::Char('W'));
+        assert_eq!(None, map.key(HostKey::Char('w')));
+    }
+
+    #[test]
+    fn azerty_maps_same_positions() {
+        let map = KeyMap::azerty();
+        assert_eq!(Some(0x4), map.key(HostKey::Char('a')));
+        assert_eq!(Some(0x7), map.key(HostKey::Char('q')));
+        assert_eq!(Some(0xA), map.key(HostKey::Char('w')));
+        assert_eq!(Some(0x2), map.key(HostKey::Char('é')));
+    }
+
+    #[test]
+    fn numpad_maps_number_block() {
+        let map = KeyMap::numpad();
+        assert_eq!(Some(0x1), map.key(HostKey::Numpad('7')));
+        assert_eq!(Some(0xD), map.key(HostKey::Numpad('*')));
+        assert_eq!(Some(0x0), map.key(HostKey::Numpad('.')));
+        assert_eq!(Some(0xF), map.key(HostKey::Numpad('+')));
+        assert_eq!(None, map.key(HostKey::Char('7')));
+    }
+
+    #[test]
+    fn from_layout_requires_sixteen_keys() {
+        assert!(KeyMap::from_layout("1234 qwer").is_none());
+        assert!(KeyMap::from_layout("1234 qwer asdf zxcv b").is_none());
+    }
+
+    #[test]
+    fn from_layout_is_case_insensitive() {
+        let map = KeyMap::from_layout("UIOP JKL; M,./ 7890").unwrap();
+        assert_eq!(Some(0x1), map.key(HostKey::from_char('U')));
+        assert_eq!(Some(0xF), map.key(HostKey::from_char('0')));
+    }
+
+    #[test]
+    fn bind_and_unbind_custom_key() {
+        let mut map = KeyMap::new();
+
+        map.bind(HostKey::Char(' '), 0x5);
+        assert_eq!(Some(0x5), map.key(HostKey::Char(' ')));
+
+        map.unbind(HostKey::Char(' '));
+        assert_eq!(None, map.key(HostKey::Char(' ')));
+    }
+
+    #[test]
+    fn press_and_release_update_keypad() {
+        let map = KeyMap::qwerty();
+        let mut keypad = Keypad::new();
+
+        assert!(map.press(&mut keypad, HostKey::Char('s')));
+        assert!(keypad.key_pressed(0x8));
+
+        assert!(map.release(&mut keypad, HostKey::Char('s')));
+        assert!(!keypad.key_pressed(0x8));
+
+        assert!(!map.press(&mut keypad, HostKey::Char('p')));
+        assert_eq!(0x0, keypad.state());
+    }
+}
@@ -1,7 +1,37 @@
+pub mod keypad;
+pub mod mapping;
+
+use Byte;
+
 pub trait Input {
-    fn up(&self) -> bool;
-    fn down(&self) -> bool;
-    fn left(&self) -> bool;
-    fn right(&self) -> bool;
-    fn hex_digit(&self) -> u8;
+    fn key_pressed(&self, key: Byte) -> bool;
+
+    /// Replaces the held keys with `state`, where bit N is key N. Used to
+    /// restore save states.
+    fn set_state(&mut self, state: u16);
+
+    fn state(&self) -> u16 {
+        (0..keypad::NUM_KEYS as Byte)
+            .filter(|key| self.key_pressed(*key))
+            .fold(0x0, |acc, key| acc | 0b1 << key)
+    }
+
+    /// Whether `key` is held on the second keypad of a CHIP-8X machine.
+    fn second_key_pressed(&self, _key: Byte) -> bool {
+        false
+    }
+
+    /// The held keys of the second keypad, laid out like `state`.
+    fn second_state(&self) -> u16 {
+        (0..keypad::NUM_KEYS as Byte)
+            .filter(|key| self.second_key_pressed(*key))
+            .fold(0x0, |acc, key| acc | 0b1 << key)
+    }
+
+    /// Replaces the held keys of the second keypad, if there is one.
+    fn set_second_state(&mut self, _state: u16) {}
+
+    /// Called at the end of every frame, so a keypad can forget the key
+    /// events it recorded during it.
+    fn end_frame(&mut self) {}
 }
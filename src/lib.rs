@@ -1,32 +1,46 @@
-#![feature(range_contains)]
-
-mod cpu;
+pub mod asm;
+pub mod cpu;
+pub mod debugger;
 mod memory;
+pub mod error;
+pub mod ffi;
+pub mod gdb;
+pub mod golden;
+pub mod input;
+pub mod libretro;
+pub mod machine;
 pub mod output;
+pub mod runner;
+#[cfg(unix)]
+pub mod terminal;
+pub mod wasm;
 
 use std::io::{BufReader, Read};
 use std::fs::File;
 use std::path::Path;
 
 use cpu::Cpu;
+use cpu::platform::Platform;
+use cpu::quirks::Quirks;
+use error::Result;
 
 type Byte = u8;
 type Address = usize;
 
-pub fn init_cpu<'a, G: 'a>(rom: &Vec<Byte>, graphics: &'a mut G) -> Cpu<'a, G>
-    where G: output::graphics::GraphicsOutput {
-    Cpu::new(rom, graphics)
+pub fn init_cpu<'a, G, I>(rom: &[Byte], graphics: &'a mut G, input: &'a mut I, platform: Platform, quirks: Quirks) -> Result<Cpu<'a, G, I>>
+    where G: 'a + output::graphics::GraphicsOutput, I: 'a + input::Input {
+    Cpu::new(rom, graphics, input, platform, quirks)
 }
 
-pub fn load_rom(directory: &str, filename: &str) -> Vec<Byte> {
+pub fn load_rom(directory: &str, filename: &str) -> Result<Vec<Byte>> {
     let path = Path::new(directory).join(filename);
-    let mut file = File::open(path).expect("Unable to open file");
+    let mut file = File::open(path)?;
     read_bytes(&mut file)
 }
 
-fn read_bytes(file: &mut File) -> Vec<Byte> {
+fn read_bytes(file: &mut File) -> Result<Vec<Byte>> {
     let mut buf_reader = BufReader::new(file);
     let mut contents = Vec::new();
-    buf_reader.read_to_end(&mut contents).expect("Unable to read file");
-    contents
+    buf_reader.read_to_end(&mut contents)?;
+    Ok(contents)
 }
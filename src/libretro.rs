@@ -0,0 +1,501 @@
+use std::cell::{Cell, RefCell};
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_uint, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::ptr;
+use std::slice;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use cpu::platform::Platform;
+use machine::Machine;
+use output::graphics::{self, GraphicsOutput};
+use Byte;
+
+const RETRO_API_VERSION: c_uint = 1;
+const RETRO_ENVIRONMENT_SET_PIXEL_FORMAT: c_uint = 10;
+const RETRO_ENVIRONMENT_SET_INPUT_DESCRIPTORS: c_uint = 11;
+const RETRO_ENVIRONMENT_SET_GEOMETRY: c_uint = 37;
+const RETRO_PIXEL_FORMAT_XRGB8888: c_uint = 1;
+const RETRO_DEVICE_JOYPAD: c_uint = 1;
+const RETRO_REGION_NTSC: c_uint = 0;
+
+pub const SAMPLE_RATE: u32 = 44100;
+const SAMPLES_PER_FRAME: usize = 735;
+const AMPLITUDE: f32 = 0.25;
+
+/// Lit pixels by the planes they are on; CHIP-8X uses its colour board
+/// instead.
+const PALETTE: [u32; 4] = [0x000000, 0xFFFFFF, 0x808080, 0xC0C0C0];
+
+/// RetroPad buttons and the keys they press. The d-pad is on 2, 4, 6 and
+/// 8, which most programs use for directions, with 5 on A as the usual
+/// action key.
+const BUTTONS: [(c_uint, Byte, &[u8]); 16] = [
+    (4, 0x2, b"Up (2)\0"),
+    (5, 0x8, b"Down (8)\0"),
+    (6, 0x4, b"Left (4)\0"),
+    (7, 0x6, b"Right (6)\0"),
+    (8, 0x5, b"5\0"),
+    (0, 0x0, b"0\0"),
+    (9, 0x1, b"1\0"),
+    (1, 0x3, b"3\0"),
+    (10, 0x7, b"7\0"),
+    (11, 0x9, b"9\0"),
+    (2, 0xA, b"A\0"),
+    (3, 0xB, b"B\0"),
+    (12, 0xC, b"C\0"),
+    (13, 0xD, b"D\0"),
+    (14, 0xE, b"E\0"),
+    (15, 0xF, b"F\0")
+];
+
+#[repr(C)]
+pub struct SystemInfo {
+    pub library_name: *const c_char,
+    pub library_version: *const c_char,
+    pub valid_extensions: *const c_char,
+    pub need_fullpath: bool,
+    pub block_extract: bool
+}
+
+#[repr(C)]
+pub struct GameGeometry {
+    pub base_width: c_uint,
+    pub base_height: c_uint,
+    pub max_width: c_uint,
+    pub max_height: c_uint,
+    pub aspect_ratio: f32
+}
+
+#[repr(C)]
+pub struct SystemTiming {
+    pub fps: f64,
+    pub sample_rate: f64
+}
+
+#[repr(C)]
+pub struct SystemAvInfo {
+    pub geometry: GameGeometry,
+    pub timing: SystemTiming
+}
+
+#[repr(C)]
+pub struct GameInfo {
+    pub path: *const c_char,
+    pub data: *const c_void,
+    pub size: usize,
+    pub meta: *const c_char
+}
+
+#[repr(C)]
+pub struct InputDescriptor {
+    pub port: c_uint,
+    pub device: c_uint,
+    pub index: c_uint,
+    pub id: c_uint,
+    pub description: *const c_char
+}
+
+pub type EnvironmentFn = unsafe extern "C" fn(cmd: c_uint, data: *mut c_void) -> bool;
+pub type VideoRefreshFn = unsafe extern "C" fn(data: *const c_void, width: c_uint, height: c_uint, pitch: usize);
+pub type AudioSampleFn = unsafe extern "C" fn(left: i16, right: i16);
+pub type AudioSampleBatchFn = unsafe extern "C" fn(data: *const i16, frames: usize) -> usize;
+pub type InputPollFn = unsafe extern "C" fn();
+pub type InputStateFn = unsafe extern "C" fn(port: c_uint, device: c_uint, index: c_uint, id: c_uint) -> i16;
+
+#[derive(Clone, Copy, Default)]
+struct Callbacks {
+    environment: Option<EnvironmentFn>,
+    video_refresh: Option<VideoRefreshFn>,
+    audio_sample_batch: Option<AudioSampleBatchFn>,
+    input_poll: Option<InputPollFn>,
+    input_state: Option<InputStateFn>
+}
+
+/// The loaded game and the buffers handed to the frontend each frame.
+struct Core {
+    machine: Machine,
+    faulted: bool,
+    size: (usize, usize),
+    video: Vec<u32>,
+    samples: Vec<f32>,
+    audio: Vec<i16>,
+    state_size: usize
+}
+
+impl Core {
+    fn new(machine: Machine) -> Core {
+        // States only grow when the display goes high resolution, so leave
+        // room for that and the size stays fixed for rewind.
+        let screen = graphics::NUM_PLANES * graphics::HIRES_SCREEN_WIDTH * graphics::HIRES_SCREEN_HEIGHT / 8;
+        let state_size = 4 + machine.save_state().len() + screen;
+        Core {
+            size: (machine.width(), machine.height()),
+            machine,
+            faulted: false,
+            video: Vec::new(),
+            samples: vec![0.0; SAMPLES_PER_FRAME],
+            audio: vec![0; SAMPLES_PER_FRAME * 2],
+            state_size
+        }
+    }
+
+    fn render_video(&mut self) {
+        let graphics = self.machine.graphics();
+        let coloured = self.machine.platform() == Platform::Chip8X;
+        let background = graphics.background().rgb();
+        self.video.clear();
+        for y in 0..graphics.height() {
+            for x in 0..graphics.width() {
+                let planes = (0..graphics::NUM_PLANES)
+                    .filter(|&plane| graphics.read_plane_pixel(plane, x, y))
+                    .fold(0, |acc, plane| acc | 0b1 << plane);
+                self.video.push(match (coloured, planes) {
+                    (true, 0) => background,
+                    (true, _) => graphics.foreground(x, y).rgb(),
+                    (false, planes) => PALETTE[planes]
+                });
+            }
+        }
+    }
+
+    /// A frame of the beep as interleaved stereo, silent while the sound
+    /// timer is off. The tone is the XO-CHIP audio pattern, which is a
+    /// square wave on the other platforms.
+    fn render_audio(&mut self) {
+        if self.machine.beep() {
+            self.machine.audio_mut().render(&mut self.samples, SAMPLE_RATE, AMPLITUDE);
+        } else {
+            for sample in self.samples.iter_mut() { *sample = 0.0; }
+        }
+        for (frame, &sample) in self.audio.chunks_mut(2).zip(&self.samples) {
+            let sample = (sample * i16::MAX as f32) as i16;
+            frame[0] = sample;
+            frame[1] = sample;
+        }
+    }
+}
+
+// Frontends call a core from one thread.
+thread_local! {
+    static CALLBACKS: Cell<Callbacks> = Cell::new(Callbacks::default());
+    static CORE: RefCell<Option<Core>> = const { RefCell::new(None) };
+}
+
+fn set_callbacks<F>(f: F) where F: FnOnce(&mut Callbacks) {
+    CALLBACKS.with(|callbacks| {
+        let mut current = callbacks.get();
+        f(&mut current);
+        callbacks.set(current);
+    });
+}
+
+fn callbacks() -> Callbacks {
+    CALLBACKS.with(Cell::get)
+}
+
+/// Runs `f`, giving `default` rather than unwinding into the frontend if
+/// it panics.
+fn guard<T, F>(default: T, f: F) -> T where F: FnOnce() -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(default)
+}
+
+/// Runs `f` on the loaded game, giving `default` without one. A panic
+/// marks the core faulted, so the game stops until it is reset or a state
+/// is loaded.
+fn with_core<T, F>(default: T, f: F) -> T where F: FnOnce(&mut Core) -> T {
+    CORE.with(|core| match *core.borrow_mut() {
+        Some(ref mut core) => match panic::catch_unwind(AssertUnwindSafe(|| f(core))) {
+            Ok(result) => result,
+            Err(_) => {
+                core.faulted = true;
+                default
+            }
+        },
+        None => default
+    })
+}
+
+unsafe fn environment<T>(cmd: c_uint, data: &mut T) -> bool {
+    match callbacks().environment {
+        Some(environment) => environment(cmd, data as *mut T as *mut c_void),
+        None => false
+    }
+}
+
+fn geometry(width: usize, height: usize) -> GameGeometry {
+    GameGeometry {
+        base_width: width as c_uint,
+        base_height: height as c_uint,
+        max_width: graphics::HIRES_SCREEN_WIDTH as c_uint,
+        max_height: graphics::HIRES_SCREEN_HEIGHT as c_uint,
+        aspect_ratio: 2.0
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_api_version() -> c_uint {
+    RETRO_API_VERSION
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_environment(callback: EnvironmentFn) {
+    set_callbacks(|callbacks| callbacks.environment = Some(callback));
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_video_refresh(callback: VideoRefreshFn) {
+    set_callbacks(|callbacks| callbacks.video_refresh = Some(callback));
+}
+
+/// Audio goes out a frame at a time through the batch callback.
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample(_callback: AudioSampleFn) {}
+
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample_batch(callback: AudioSampleBatchFn) {
+    set_callbacks(|callbacks| callbacks.audio_sample_batch = Some(callback));
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_poll(callback: InputPollFn) {
+    set_callbacks(|callbacks| callbacks.input_poll = Some(callback));
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_state(callback: InputStateFn) {
+    set_callbacks(|callbacks| callbacks.input_state = Some(callback));
+}
+
+#[no_mangle]
+pub extern "C" fn retro_init() {}
+
+#[no_mangle]
+pub extern "C" fn retro_deinit() {
+    guard((), || CORE.with(|core| *core.borrow_mut() = None));
+}
+
+/// # Safety
+///
+/// `info` must be valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_info(info: *mut SystemInfo) {
+    *info = SystemInfo {
+        library_name: b"Rusty CHIP\0".as_ptr() as *const c_char,
+        library_version: concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char,
+        valid_extensions: b"ch8|c8|sc8|xo8|c8x\0".as_ptr() as *const c_char,
+        need_fullpath: false,
+        block_extract: false
+    };
+}
+
+/// # Safety
+///
+/// `info` must be valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_av_info(info: *mut SystemAvInfo) {
+    let (width, height) = with_core((graphics::SCREEN_WIDTH, graphics::SCREEN_HEIGHT), |core| core.size);
+    *info = SystemAvInfo {
+        geometry: geometry(width, height),
+        timing: SystemTiming { fps: 60.0, sample_rate: SAMPLE_RATE as f64 }
+    };
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_controller_port_device(_port: c_uint, _device: c_uint) {}
+
+#[no_mangle]
+pub extern "C" fn retro_reset() {
+    with_core((), |core| core.faulted = core.machine.reset().is_err());
+}
+
+/// Reads the RetroPad, runs a frame unless the program has faulted, and
+/// sends the display and a frame of audio.
+#[no_mangle]
+pub extern "C" fn retro_run() {
+    let callbacks = callbacks();
+    with_core((), |core| {
+        unsafe {
+            if let Some(input_poll) = callbacks.input_poll { input_poll(); }
+            if let Some(input_state) = callbacks.input_state {
+                for &(button, key, _) in &BUTTONS {
+                    core.machine.set_key(key, input_state(0, RETRO_DEVICE_JOYPAD, 0, button) != 0);
+                }
+            }
+        }
+
+        if !core.faulted {
+            core.faulted = core.machine.run_frame().is_err();
+        }
+
+        let size = (core.machine.width(), core.machine.height());
+        if size != core.size {
+            core.size = size;
+            unsafe { environment(RETRO_ENVIRONMENT_SET_GEOMETRY, &mut geometry(size.0, size.1)); }
+        }
+        core.render_video();
+        if let Some(video_refresh) = callbacks.video_refresh {
+            unsafe { video_refresh(core.video.as_ptr() as *const c_void, size.0 as c_uint, size.1 as c_uint, size.0 * 4); }
+        }
+
+        core.render_audio();
+        if let Some(audio_sample_batch) = callbacks.audio_sample_batch {
+            unsafe { audio_sample_batch(core.audio.as_ptr(), SAMPLES_PER_FRAME); }
+        }
+    });
+}
+
+/// The same for every state of the loaded game, and 0 without one.
+#[no_mangle]
+pub extern "C" fn retro_serialize_size() -> usize {
+    with_core(0, |core| core.state_size)
+}
+
+/// Writes the state's length as four little-endian bytes, then the state,
+/// then zeros to the fixed size.
+///
+/// # Safety
+///
+/// `data` must be valid for `size` bytes of writes.
+#[no_mangle]
+pub unsafe extern "C" fn retro_serialize(data: *mut c_void, size: usize) -> bool {
+    with_core(false, |core| {
+        let state = core.machine.save_state();
+        if data.is_null() || size < 4 + state.len() { return false; }
+        let out = slice::from_raw_parts_mut(data as *mut Byte, size);
+        out[..4].copy_from_slice(&(state.len() as u32).to_le_bytes());
+        out[4..4 + state.len()].copy_from_slice(&state);
+        for byte in out[4 + state.len()..].iter_mut() { *byte = 0; }
+        true
+    })
+}
+
+/// # Safety
+///
+/// `data` must point to `size` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn retro_unserialize(data: *const c_void, size: usize) -> bool {
+    if data.is_null() || size < 4 { return false; }
+    let data = slice::from_raw_parts(data as *const Byte, size);
+    let len = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    if size - 4 < len { return false; }
+    with_core(false, |core| {
+        let loaded = core.machine.load_state(&data[4..4 + len]).is_ok();
+        if loaded { core.faulted = false; }
+        loaded
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn retro_cheat_reset() {}
+
+#[no_mangle]
+pub extern "C" fn retro_cheat_set(_index: c_uint, _enabled: bool, _code: *const c_char) {}
+
+/// Runs the ROM in `game.data` on the platform its extension names,
+/// CHIP-8 otherwise. Fails if the frontend cannot take XRGB8888.
+///
+/// # Safety
+///
+/// `game` must be null or point to a game whose `data` holds `size`
+/// bytes and whose `path` is null or a C string.
+#[no_mangle]
+pub unsafe extern "C" fn retro_load_game(game: *const GameInfo) -> bool {
+    guard(false, || load_game(game))
+}
+
+unsafe fn load_game(game: *const GameInfo) -> bool {
+    let game = match game.as_ref() {
+        Some(game) if !game.data.is_null() => game,
+        _ => return false
+    };
+    let mut format = RETRO_PIXEL_FORMAT_XRGB8888;
+    if !environment(RETRO_ENVIRONMENT_SET_PIXEL_FORMAT, &mut format) {
+        return false;
+    }
+    let mut descriptors: Vec<InputDescriptor> = BUTTONS.iter().map(|&(id, _, description)| InputDescriptor {
+        port: 0,
+        device: RETRO_DEVICE_JOYPAD,
+        index: 0,
+        id,
+        description: description.as_ptr() as *const c_char
+    }).collect();
+    descriptors.push(InputDescriptor { port: 0, device: 0, index: 0, id: 0, description: ptr::null() });
+    environment(RETRO_ENVIRONMENT_SET_INPUT_DESCRIPTORS, &mut descriptors[0]);
+
+    let platform = if game.path.is_null() { None } else { CStr::from_ptr(game.path).to_str().ok() }
+        .and_then(|path| Path::new(path).extension())
+        .and_then(|extension| Platform::from_extension(&extension.to_string_lossy()))
+        .unwrap_or(Platform::Chip8);
+    let rom = slice::from_raw_parts(game.data as *const Byte, game.size);
+    let mut machine = match Machine::new(rom, platform, platform.default_quirks()) {
+        Ok(machine) => machine,
+        Err(_) => return false
+    };
+    // Seeded so rewind and run-ahead replay the same random numbers; the
+    // seed is saved with each state.
+    let seed = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+    machine.set_seed(Some(seed));
+    CORE.with(|core| *core.borrow_mut() = Some(Core::new(machine)));
+    true
+}
+
+#[no_mangle]
+pub extern "C" fn retro_load_game_special(_game_type: c_uint, _info: *const GameInfo, _num_info: usize) -> bool {
+    false
+}
+
+#[no_mangle]
+pub extern "C" fn retro_unload_game() {
+    guard((), || CORE.with(|core| *core.borrow_mut() = None));
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_region() -> c_uint {
+    RETRO_REGION_NTSC
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_memory_data(_id: c_uint) -> *mut c_void {
+    ptr::null_mut()
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_memory_size(_id: c_uint) -> usize {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panics_fault_the_core() {
+        let machine = Machine::new(&[0x12, 0x00], Platform::Chip8, Platform::Chip8.default_quirks()).unwrap();
+        CORE.with(|core| *core.borrow_mut() = Some(Core::new(machine)));
+
+        with_core((), |_| panic!("bad frame"));
+        assert!(with_core(false, |core| core.faulted));
+        retro_reset();
+        assert!(!with_core(true, |core| core.faulted));
+        retro_unload_game();
+    }
+
+    #[test]
+    fn silent_until_the_sound_timer_is_set() {
+        // Loop forever without touching the sound timer.
+        let machine = Machine::new(&[0x12, 0x00], Platform::Chip8, Platform::Chip8.default_quirks()).unwrap();
+        let mut core = Core::new(machine);
+        core.machine.run_frame().unwrap();
+        core.render_audio();
+        assert!(core.audio.iter().all(|&sample| sample == 0));
+    }
+
+    #[test]
+    fn nothing_happens_without_a_game() {
+        retro_run();
+        assert_eq!(0, retro_serialize_size());
+        assert!(!unsafe { retro_load_game(ptr::null()) });
+    }
+}
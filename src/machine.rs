@@ -0,0 +1,328 @@
+use std::mem::ManuallyDrop;
+
+use cpu::{Cpu, Register};
+use cpu::clock::DEFAULT_INSTRUCTIONS_PER_FRAME;
+use cpu::platform::Platform;
+use cpu::quirks::Quirks;
+use cpu::random::{OsRandom, SeededRandom};
+use error::Result;
+use input::keypad::{Keypad, NUM_KEYS};
+use output::graphics::{self, Display, GraphicsOutput};
+use output::sound::AudioPattern;
+use runner::Runner;
+use {Address, Byte};
+
+/// A whole interpreter that owns its display and keypad, for hosts that
+/// cannot lend a `Cpu` its graphics and input: the WebAssembly exports,
+/// the C ABI and the libretro core. Frames are run by the host's own loop
+/// rather than against the wall clock.
+pub struct Machine {
+    // Dropped by hand before the display and keypad it borrows.
+    runner: ManuallyDrop<Runner<'static, Display, Keypad>>,
+    graphics: *mut Display,
+    input: *mut Keypad,
+    rom: Vec<Byte>,
+    platform: Platform,
+    quirks: Quirks,
+    seed: Option<u64>,
+    framebuffer: Vec<Byte>
+}
+
+impl Machine {
+    pub fn new(rom: &[Byte], platform: Platform, quirks: Quirks) -> Result<Machine> {
+        let (runner, graphics, input) = Machine::boot(rom, platform, quirks, None, DEFAULT_INSTRUCTIONS_PER_FRAME)?;
+        Ok(Machine {
+            runner: ManuallyDrop::new(runner),
+            graphics,
+            input,
+            rom: rom.to_vec(),
+            platform,
+            quirks,
+            seed: None,
+            framebuffer: Vec::new()
+        })
+    }
+
+    fn boot(rom: &[Byte], platform: Platform, quirks: Quirks, seed: Option<u64>, instructions_per_frame: u32)
+        -> Result<(Runner<'static, Display, Keypad>, *mut Display, *mut Keypad)> {
+        let graphics = Box::into_raw(Box::new(Display::quiet()));
+        let input = Box::into_raw(Box::new(Keypad::new()));
+        // The boxes live until the runner borrowing them has been dropped,
+        // which `replace` and `drop` see to.
+        let cpu = unsafe { Cpu::new(rom, &mut *graphics, &mut *input, platform, quirks) };
+        let mut cpu = match cpu {
+            Ok(cpu) => cpu,
+            Err(err) => {
+                unsafe { Machine::free(graphics, input); }
+                return Err(err);
+            }
+        };
+        if let Some(seed) = seed { cpu.set_random(SeededRandom::new(seed)); }
+        let mut runner = Runner::new(cpu);
+        runner.set_instructions_per_frame(instructions_per_frame);
+        Ok((runner, graphics, input))
+    }
+
+    unsafe fn free(graphics: *mut Display, input: *mut Keypad) {
+        drop(Box::from_raw(graphics));
+        drop(Box::from_raw(input));
+    }
+
+    fn replace(&mut self, rom: &[Byte], platform: Platform, quirks: Quirks) -> Result<()> {
+        let (runner, graphics, input) = Machine::boot(rom, platform, quirks, self.seed, self.runner.instructions_per_frame())?;
+        unsafe {
+            ManuallyDrop::drop(&mut self.runner);
+            Machine::free(self.graphics, self.input);
+        }
+        self.runner = ManuallyDrop::new(runner);
+        self.graphics = graphics;
+        self.input = input;
+        Ok(())
+    }
+
+    /// Replaces the program and restarts. On error the old program keeps
+    /// running.
+    pub fn load_rom(&mut self, rom: &[Byte], platform: Platform, quirks: Quirks) -> Result<()> {
+        self.replace(rom, platform, quirks)?;
+        self.rom = rom.to_vec();
+        self.platform = platform;
+        self.quirks = quirks;
+        Ok(())
+    }
+
+    /// The platform of the ROM last loaded.
+    pub fn platform(&self) -> Platform {
+        self.platform
+    }
+
+    /// Restarts the current program from power on.
+    pub fn reset(&mut self) -> Result<()> {
+        let rom = self.rom.clone();
+        self.replace(&rom, self.platform, self.quirks)
+    }
+
+    /// Seeds CXKK now and after every reset, so runs repeat exactly.
+    /// Without a seed it draws from the operating system.
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+        match seed {
+            Some(seed) => self.cpu_mut().set_random(SeededRandom::new(seed)),
+            None => self.cpu_mut().set_random(OsRandom::new())
+        }
+    }
+
+    pub fn set_instructions_per_frame(&mut self, instructions: u32) {
+        self.runner.set_instructions_per_frame(instructions);
+    }
+
+    /// Runs up to `n` instructions without ticking the timers, stopping if
+    /// the program exits. Returns how many ran.
+    pub fn step(&mut self, n: u32) -> Result<u32> {
+        let cpu = self.runner.cpu_mut();
+        let mut ran = 0;
+        while ran < n && !cpu.exit {
+            cpu.step()?;
+            ran += 1;
+        }
+        Ok(ran)
+    }
+
+    /// Runs one 60 Hz frame: the configured instructions and a timer tick.
+    pub fn run_frame(&mut self) -> Result<()> {
+        self.runner.run_frame()
+    }
+
+    pub fn width(&self) -> usize {
+        self.cpu().graphics().width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.cpu().graphics().height()
+    }
+
+    /// One byte per pixel, row by row, with bit N set if the pixel is lit
+    /// on plane N. Its length is `width() * height()`.
+    pub fn framebuffer(&mut self) -> &[Byte] {
+        let graphics = self.runner.cpu().graphics();
+        let (width, height) = (graphics.width(), graphics.height());
+        self.framebuffer.clear();
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = (0..graphics::NUM_PLANES)
+                    .filter(|&plane| graphics.read_plane_pixel(plane, x, y))
+                    .fold(0x0, |acc, plane| acc | 0b1 << plane);
+                self.framebuffer.push(pixel);
+            }
+        }
+        &self.framebuffer
+    }
+
+    /// Presses or releases a key, 0x0 to 0xF. Keys off the keypad are
+    /// ignored rather than aliased onto one that is.
+    pub fn set_key(&mut self, key: Byte, pressed: bool) {
+        if key as usize >= NUM_KEYS { return; }
+        let keypad = self.runner.cpu_mut().input_mut();
+        if pressed { keypad.press(key); } else { keypad.release(key); }
+    }
+
+    pub fn beep(&self) -> bool {
+        self.cpu().beep
+    }
+
+    pub fn exited(&self) -> bool {
+        self.cpu().exit
+    }
+
+    pub fn save_state(&self) -> Vec<Byte> {
+        self.cpu().save_state()
+    }
+
+    /// Restores a state saved by any machine. The program it was saved
+    /// with comes back with it, but a reset still restarts the ROM this
+    /// machine last loaded.
+    pub fn load_state(&mut self, state: &[Byte]) -> Result<()> {
+        self.runner.cpu_mut().load_state(state)
+    }
+
+    pub fn pc(&self) -> Address {
+        self.cpu().pc()
+    }
+
+    pub fn read_register(&self, register: Register) -> Byte {
+        self.cpu().read_register(register)
+    }
+
+    pub fn graphics(&self) -> &Display {
+        self.cpu().graphics()
+    }
+
+    pub fn keypad(&self) -> &Keypad {
+        self.cpu().input()
+    }
+
+    /// The tone the sound timer plays, which rendering advances.
+    pub fn audio_mut(&mut self) -> &mut AudioPattern {
+        self.cpu_mut().audio_mut()
+    }
+
+    // The CPU's borrows claim to be static but point into this machine, so
+    // it must never be handed out beyond the crate.
+    pub(crate) fn cpu(&self) -> &Cpu<'static, Display, Keypad> {
+        self.runner.cpu()
+    }
+
+    pub(crate) fn cpu_mut(&mut self) -> &mut Cpu<'static, Display, Keypad> {
+        self.runner.cpu_mut()
+    }
+}
+
+/// Shared by the tests of the machine and the wrappers around it: V0 := 7,
+/// sound timer := V0, draw the digit in V0, then stop.
+#[cfg(test)]
+pub const TEST_ROM: [Byte; 10] = [0x60, 0x07, 0xF0, 0x18, 0xF0, 0x29, 0xD1, 0x15, 0x12, 0x08];
+
+impl Drop for Machine {
+    fn drop(&mut self) {
+        unsafe {
+            ManuallyDrop::drop(&mut self.runner);
+            Machine::free(self.graphics, self.input);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use input::Input;
+
+    fn machine() -> Machine {
+        Machine::new(&TEST_ROM, Platform::Chip8, Platform::Chip8.default_quirks()).unwrap()
+    }
+
+    /// The VIP waits for vertical blank to draw, so the digit appears on
+    /// the second frame.
+    fn run_frames(machine: &mut Machine) {
+        machine.run_frame().unwrap();
+        machine.run_frame().unwrap();
+    }
+
+    #[test]
+    fn runs_frames_into_the_framebuffer() {
+        let mut machine = machine();
+        assert_eq!((64, 32), (machine.width(), machine.height()));
+        assert!(machine.framebuffer().iter().all(|&pixel| pixel == 0));
+        run_frames(&mut machine);
+        let framebuffer = machine.framebuffer();
+        assert_eq!(64 * 32, framebuffer.len());
+        assert_eq!(&[1, 1, 1, 1, 0], &framebuffer[..5]);
+        assert!(machine.beep());
+    }
+
+    #[test]
+    fn step_stops_when_the_program_exits() {
+        let mut machine = Machine::new(&[0x60, 0x01, 0x12, 0x02], Platform::Chip8, Quirks::default()).unwrap();
+        assert_eq!(2, machine.step(10).unwrap());
+        assert!(machine.exited());
+        assert_eq!(0, machine.step(10).unwrap());
+    }
+
+    #[test]
+    fn reset_restarts_the_rom() {
+        let mut machine = machine();
+        machine.run_frame().unwrap();
+        machine.set_key(0x5, true);
+        machine.reset().unwrap();
+        assert_eq!(0x200, machine.pc());
+        assert!(machine.framebuffer().iter().all(|&pixel| pixel == 0));
+        assert!(!machine.keypad().key_pressed(0x5));
+    }
+
+    #[test]
+    fn keys_reach_the_keypad() {
+        let mut machine = machine();
+        machine.set_key(0xA, true);
+        assert!(machine.keypad().key_pressed(0xA));
+        machine.set_key(0xA, false);
+        assert!(!machine.keypad().key_pressed(0xA));
+    }
+
+    #[test]
+    fn keys_off_the_keypad_are_ignored() {
+        let mut machine = machine();
+        machine.set_key(0x1A, true);
+        assert!(!machine.keypad().key_pressed(0xA));
+        assert_eq!(0, machine.keypad().state());
+    }
+
+    #[test]
+    fn bad_rom_keeps_the_old_program() {
+        let mut machine = machine();
+        let huge = vec![0x0; 0x10000];
+        assert!(machine.load_rom(&huge, Platform::Chip8, Quirks::default()).is_err());
+        run_frames(&mut machine);
+        assert!(machine.framebuffer()[0] != 0);
+    }
+
+    #[test]
+    fn states_round_trip() {
+        let mut machine = machine();
+        run_frames(&mut machine);
+        let state = machine.save_state();
+        machine.reset().unwrap();
+        machine.load_state(&state).unwrap();
+        assert_eq!(0x208, machine.pc());
+        assert!(machine.framebuffer()[0] != 0);
+    }
+
+    #[test]
+    fn seeded_machines_repeat_after_reset() {
+        // V0 := random byte, then stop.
+        let mut machine = Machine::new(&[0xC0, 0xFF, 0x12, 0x02], Platform::Chip8, Quirks::default()).unwrap();
+        machine.set_seed(Some(42));
+        machine.step(1).unwrap();
+        let first = machine.read_register(0x0);
+        machine.reset().unwrap();
+        machine.step(1).unwrap();
+        assert_eq!(first, machine.read_register(0x0));
+    }
+}
@@ -1,15 +1,305 @@
 extern crate rusty_chip;
 
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::net::TcpListener;
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+#[cfg(unix)]
+use std::path::Path;
+use std::process;
+use std::result;
+
 use rusty_chip::*;
+use cpu::Cpu;
+use cpu::platform::Platform;
+use cpu::quirks::Quirks;
+use cpu::random::SeededRandom;
+use cpu::trace::{JsonSink, TextSink};
+use debugger::Debugger;
+use error::Result;
+use gdb::GdbStub;
+use golden::{Harness, Image};
 use output::graphics;
+use input::Input;
+use input::keypad::Keypad;
+#[cfg(unix)]
+use input::mapping::KeyMap;
+use runner::Runner;
+
+const USAGE: &str = "\
+usage: rusty_chip [options] <rom>
+       rusty_chip disasm <rom> [platform]
+       rusty_chip asm <source> <rom>
+       rusty_chip debug <rom> [platform]
+       rusty_chip gdb <rom> <host:port | socket path> [platform]
+
+options:
+  --platform <name>   chip8, schip, xochip or chip8x (default chip8)
+  --quirks <preset>   vip, chip48, schip or octo (default: the platform's)
+  --ipf <n>           instructions per frame (default 10)
+  --seed <n>          seed the random number generator for repeatable runs
+  --trace <file>      write an instruction trace, as JSON lines if it ends .json
+  --headless          run without a display and print the final screen as PBM
+  --frames <n>        frames to run headless (default 60)
+  --screen <file>     save the final headless screen to a PBM file
+  -h, --help          show this message
+
+Exits 0 on success, 1 if the ROM cannot be loaded or faults, 2 on bad usage.";
+
+/// Options for running a ROM.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Options {
+    rom: String,
+    platform: Platform,
+    quirks: Option<Quirks>,
+    instructions_per_frame: Option<u32>,
+    seed: Option<u64>,
+    trace: Option<String>,
+    headless: bool,
+    frames: Option<u32>,
+    screen: Option<String>
+}
+
+impl Options {
+    fn parse(args: &[String]) -> result::Result<Options, String> {
+        let mut options = Options::default();
+        let mut rom = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+            match arg.as_str() {
+                "--platform" => {
+                    let name = value()?;
+                    options.platform = Platform::from_name(name).ok_or_else(|| format!("Unknown platform: {}", name))?;
+                },
+                "--quirks" => {
+                    let name = value()?;
+                    options.quirks = Some(Quirks::from_name(name).ok_or_else(|| format!("Unknown quirks preset: {}", name))?);
+                },
+                "--ipf" => options.instructions_per_frame = Some(number(arg, value()?)?),
+                "--seed" => options.seed = Some(number(arg, value()?)?),
+                "--trace" => options.trace = Some(value()?.clone()),
+                "--headless" => options.headless = true,
+                "--frames" => options.frames = Some(number(arg, value()?)?),
+                "--screen" => options.screen = Some(value()?.clone()),
+                flag if flag.starts_with('-') => return Err(format!("Unknown option: {}", flag)),
+                path => {
+                    if rom.is_some() { return Err(format!("Unexpected argument: {}", path)); }
+                    rom = Some(path.to_string());
+                }
+            }
+        }
+        if !options.headless && (options.frames.is_some() || options.screen.is_some()) {
+            return Err("--frames and --screen need --headless".to_string());
+        }
+        options.rom = rom.ok_or("Missing ROM path")?;
+        Ok(options)
+    }
+
+    fn quirks(&self) -> Quirks {
+        self.quirks.unwrap_or_else(|| self.platform.default_quirks())
+    }
+}
+
+fn number<T>(option: &str, text: &str) -> result::Result<T, String> where T: std::str::FromStr {
+    text.parse().map_err(|_| format!("{} needs a number, not {}", option, text))
+}
 
 fn main() {
+    if let Err(err) = run() {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("disasm") => return disassemble(&args[1..]),
+        Some("asm") => return assemble(&args[1..]),
+        Some("debug") => return debug(&args[1..]),
+        Some("gdb") => return gdb(&args[1..]),
+        Some("-h") | Some("--help") => {
+            println!("{}", USAGE);
+            return Ok(());
+        },
+        _ => {}
+    }
+
+    let options = Options::parse(&args).unwrap_or_else(|err| {
+        eprintln!("{}\n\n{}", err, USAGE);
+        process::exit(2);
+    });
+    let rom = load_rom(".", &options.rom)?;
+    // Only the fallback frontend lets the display print itself.
+    let mut graphics = if options.headless || cfg!(unix) { graphics::Display::quiet() } else { graphics::Display::new() };
+    let mut keypad = Keypad::new();
+    let mut cpu = init_cpu(&rom, &mut graphics, &mut keypad, options.platform, options.quirks())?;
+    if let Some(seed) = options.seed { cpu.set_random(SeededRandom::new(seed)); }
+    if let Some(ref path) = options.trace { trace(&mut cpu, path)?; }
+
+    let mut runner = Runner::new(cpu);
+    if let Some(instructions) = options.instructions_per_frame { runner.set_instructions_per_frame(instructions); }
+    if options.headless { return headless(&options, &mut runner); }
+    interactive(&mut runner, &options.rom)
+}
+
+#[cfg(unix)]
+fn interactive(runner: &mut Runner<graphics::Display, Keypad>, rom: &str) -> Result<()> {
+    terminal::run(runner, Path::new(rom), KeyMap::qwerty())
+}
+
+#[cfg(not(unix))]
+fn interactive(runner: &mut Runner<graphics::Display, Keypad>, _rom: &str) -> Result<()> {
+    runner.run()
+}
+
+fn trace<G, I>(cpu: &mut Cpu<G, I>, path: &str) -> Result<()> where G: graphics::GraphicsOutput, I: Input {
+    let out = BufWriter::new(File::create(path)?);
+    if path.ends_with(".json") {
+        cpu.set_trace(JsonSink::new(out));
+    } else {
+        cpu.set_trace(TextSink::new(out));
+    }
+    Ok(())
+}
+
+/// Runs the frames without drawing, then prints or saves the screen.
+fn headless(options: &Options, runner: &mut Runner<graphics::Display, Keypad>) -> Result<()> {
+    Harness::new(options.platform).frames(options.frames.unwrap_or(60)).run_runner(runner)?;
+    let screen = Image::capture(runner.cpu().graphics()).to_pbm();
+    match options.screen {
+        Some(ref path) => fs::write(path, screen)?,
+        None => io::stdout().write_all(screen.as_bytes())?
+    }
+    Ok(())
+}
+
+fn usage(text: &str) -> ! {
+    eprintln!("usage: rusty_chip {}", text);
+    process::exit(2);
+}
+
+fn platform(name: Option<&String>) -> Platform {
+    match name {
+        Some(name) => Platform::from_name(name).unwrap_or_else(|| {
+            eprintln!("unknown platform: {}", name);
+            process::exit(2);
+        }),
+        None => Platform::default()
+    }
+}
+
+/// `disasm <rom> [platform]` prints an Octo listing of the ROM.
+fn disassemble(args: &[String]) -> Result<()> {
+    let path = args.first().unwrap_or_else(|| usage("disasm <rom> [platform]"));
+    let platform = platform(args.get(1));
+    let rom = load_rom(".", path)?;
+    print!("{}", asm::disassembler::disassemble(&rom, platform));
+    Ok(())
+}
+
+/// `asm <source> <rom>` assembles an Octo program into a ROM file.
+fn assemble(args: &[String]) -> Result<()> {
+    let (source, output) = match (args.first(), args.get(1)) {
+        (Some(source), Some(output)) => (source, output),
+        _ => usage("asm <source> <rom>")
+    };
+    let rom = asm::assembler::assemble(&fs::read_to_string(source)?)?;
+    fs::write(output, rom)?;
+    Ok(())
+}
+
+/// `debug <rom> [platform]` runs the ROM under the interactive debugger.
+fn debug(args: &[String]) -> Result<()> {
+    let path = args.first().unwrap_or_else(|| usage("debug <rom> [platform]"));
+    let platform = platform(args.get(1));
+    let rom = load_rom(".", path)?;
     let mut graphics = graphics::Display::new();
-    let rom = load_rom("rom" ,"logo.ch8");
-    let mut cpu = init_cpu(&rom, &mut graphics);
+    let mut keypad = Keypad::new();
+    let cpu = init_cpu(&rom, &mut graphics, &mut keypad, platform, platform.default_quirks())?;
+    let stdin = io::stdin();
+    Debugger::new(cpu).repl(stdin.lock(), &mut io::stdout())
+}
+
+/// `gdb <rom> <host:port | socket path> [platform]` waits for a GDB client
+/// and serves it the machine.
+fn gdb(args: &[String]) -> Result<()> {
+    let (path, listen) = match (args.first(), args.get(1)) {
+        (Some(path), Some(listen)) => (path, listen),
+        _ => usage("gdb <rom> <host:port | socket path> [platform]")
+    };
+    let platform = platform(args.get(2));
+    let rom = load_rom(".", path)?;
+    let mut graphics = graphics::Display::new();
+    let mut keypad = Keypad::new();
+    let cpu = init_cpu(&rom, &mut graphics, &mut keypad, platform, platform.default_quirks())?;
+    let mut stub = GdbStub::new(cpu);
+    if listen.contains(':') {
+        let listener = TcpListener::bind(listen)?;
+        eprintln!("Waiting for gdb on {}", listen);
+        return stub.accept_tcp(&listener);
+    }
+    serve_unix(&mut stub, listen)
+}
+
+#[cfg(unix)]
+fn serve_unix(stub: &mut GdbStub<graphics::Display, Keypad>, path: &str) -> Result<()> {
+    let listener = UnixListener::bind(path)?;
+    eprintln!("Waiting for gdb on {}", path);
+    let result = stub.accept_unix(&listener);
+    fs::remove_file(path)?;
+    result
+}
+
+#[cfg(not(unix))]
+fn serve_unix(_stub: &mut GdbStub<graphics::Display, Keypad>, _path: &str) -> Result<()> {
+    usage("gdb <rom> <host:port> [platform]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> result::Result<Options, String> {
+        let args: Vec<String> = line.split_whitespace().map(String::from).collect();
+        Options::parse(&args)
+    }
+
+    #[test]
+    fn parses_options() {
+        let options = parse("--platform schip --quirks vip --ipf 20 --seed 7 --trace out.json game.ch8").unwrap();
+        assert_eq!("game.ch8", options.rom);
+        assert_eq!(Platform::SuperChip, options.platform);
+        assert_eq!(Quirks::cosmac_vip(), options.quirks());
+        assert_eq!(Some(20), options.instructions_per_frame);
+        assert_eq!(Some(7), options.seed);
+        assert_eq!(Some("out.json".to_string()), options.trace);
+    }
+
+    #[test]
+    fn quirks_default_to_platform() {
+        assert_eq!(Quirks::super_chip(), parse("--platform schip a.ch8").unwrap().quirks());
+    }
+
+    #[test]
+    fn headless_options() {
+        let options = parse("a.ch8 --headless --frames 5 --screen out.pbm").unwrap();
+        assert!(options.headless);
+        assert_eq!(Some(5), options.frames);
+        assert_eq!(Some("out.pbm".to_string()), options.screen);
+        assert!(parse("a.ch8 --frames 5").is_err());
+    }
 
-    loop {
-        if cpu.exit { break; }
-        cpu.step();
+    #[test]
+    fn rejects_bad_options() {
+        assert!(parse("").is_err());
+        assert!(parse("a.ch8 b.ch8").is_err());
+        assert!(parse("a.ch8 --ipf").is_err());
+        assert!(parse("a.ch8 --ipf fast").is_err());
+        assert!(parse("a.ch8 --platform chip9").is_err());
+        assert!(parse("a.ch8 --bogus").is_err());
     }
 }
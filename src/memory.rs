@@ -1,11 +1,22 @@
 use std::{cmp, clone, fmt, ops};
+use std::cell::RefCell;
 use std::ops::Range;
 
+use cpu::watch::{Access, Watchpoint};
+use error::{Error, Result};
 use Address;
 
+/// A watched access: address, kind, old value and new value.
+pub type Hit<T> = (Address, Access, T, T);
+
+/// Indexing goes straight to the backing store. Accesses made through
+/// `read`, `fetch` and `write` are checked against the watchpoints and
+/// recorded for `take_hits`.
 #[derive(Clone)]
 pub struct Memory<T> {
-    memory: Vec<T>
+    memory: Vec<T>,
+    watchpoints: Vec<Watchpoint>,
+    hits: RefCell<Vec<Hit<T>>>
 }
 
 impl<T> Memory<T> where T: clone::Clone {
@@ -14,17 +25,66 @@ impl<T> Memory<T> where T: clone::Clone {
         memory.resize(size, default);
 
         Memory {
-            memory
+            memory,
+            watchpoints: Vec::new(),
+            hits: RefCell::new(Vec::new())
+        }
+    }
+
+    pub fn load(&mut self, data: &[T], range: Range<Address>) -> Result<()> {
+        if data.len() > range.len() || range.end > self.memory.len() {
+            return Err(Error::DataTooLarge { len: data.len(), range });
+        }
+
+        for (i, val) in data.iter().enumerate() {
+            self.memory[range.start + i] = val.clone();
         }
+        Ok(())
     }
 
-    pub fn load(&mut self, data: &[T], range: Range<Address>) {
-        for i in 0..data.len() {
-            if i >= range.end {
-                panic!("Data length {:?} is greater than range size {:?}", data.len(), range);
-            }
+    pub fn read(&self, addr: Address) -> T {
+        let val = self.memory[addr].clone();
+        self.record(addr, Access::Read, &val, &val);
+        val
+    }
+
+    pub fn fetch(&self, addr: Address) -> T {
+        let val = self.memory[addr].clone();
+        self.record(addr, Access::Execute, &val, &val);
+        val
+    }
+
+    pub fn write(&mut self, addr: Address, val: T) {
+        let old = ::std::mem::replace(&mut self.memory[addr], val.clone());
+        self.record(addr, Access::Write, &old, &val);
+    }
+
+    pub fn watch(&mut self, watchpoint: Watchpoint) {
+        self.watchpoints.push(watchpoint);
+    }
+
+    /// Removes every watchpoint covering `addr`, returning whether there were any.
+    pub fn unwatch(&mut self, addr: Address) -> bool {
+        let before = self.watchpoints.len();
+        self.watchpoints.retain(|watchpoint| !watchpoint.range.contains(&addr));
+        self.watchpoints.len() != before
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    pub fn set_watchpoints(&mut self, watchpoints: Vec<Watchpoint>) {
+        self.watchpoints = watchpoints;
+    }
+
+    pub fn take_hits(&self) -> Vec<Hit<T>> {
+        self.hits.take()
+    }
 
-            self.memory[range.start + i] = data[i].clone();
+    fn record(&self, addr: Address, access: Access, old: &T, new: &T) {
+        if self.watchpoints.iter().any(|watchpoint| watchpoint.watches(addr, access)) {
+            self.hits.borrow_mut().push((addr, access, old.clone(), new.clone()));
         }
     }
 }
@@ -71,8 +131,8 @@ impl<T> fmt::Debug for Memory<T> where T: fmt::LowerHex {
 
         let lines = hex_vals.enumerate()
             .fold(String::new(), |mut acc, (i, hex_val)| {
-                if i != 0 && i % 2 == 0 { acc.push_str(" "); }
-                if i % 16 == 0 { acc.push_str("\n"); }
+                if i != 0 && i % 2 == 0 { acc.push(' '); }
+                if i % 16 == 0 { acc.push('\n'); }
 
                 acc.push_str(&hex_val);
                 acc
@@ -93,7 +153,7 @@ mod tests {
         let range: Range<Address> = 0x0..0x5;
         let mut memory = Memory::new(0x10, 0x0);
 
-        memory.load(&data, range);
+        memory.load(&data, range).unwrap();
         assert_eq!(0xA, memory[0x0]);
         assert_eq!(0xB, memory[0x1]);
         assert_eq!(0xC, memory[0x2]);
@@ -107,19 +167,76 @@ mod tests {
         let range: Range<Address> = 0x0..0x5;
         let mut memory = Memory::new(0x10, 0x0);
 
-        memory.load(&data, range);
+        memory.load(&data, range).unwrap();
         assert_eq!(0xA, memory[0x0]);
         assert_eq!(0xB, memory[0x1]);
         assert_eq!(0xC, memory[0x2]);
     }
 
     #[test]
-    #[should_panic(expected = "Data length")]
-    fn load_data_with_len_greater_than_range_panics() {
+    fn load_data_with_len_greater_than_range_errors() {
         let data: Vec<Byte> = vec![0xA, 0xB, 0xC, 0xD, 0xE, 0xF];
         let range: Range<Address> = 0x0..0x5;
         let mut memory = Memory::new(0x10, 0x0);
 
-        memory.load(&data, range);
+        match memory.load(&data, range) {
+            Err(Error::DataTooLarge { len, .. }) => assert_eq!(6, len),
+            _ => panic!("expected Error::DataTooLarge")
+        }
+        assert_eq!(0x0, memory[0x0]);
+    }
+
+    #[test]
+    fn load_data_into_offset_range() {
+        let data: Vec<Byte> = vec![0xA, 0xB];
+        let range: Range<Address> = 0x8..0xA;
+        let mut memory = Memory::new(0x10, 0x0);
+
+        memory.load(&data, range).unwrap();
+        assert_eq!(0xA, memory[0x8]);
+        assert_eq!(0xB, memory[0x9]);
+    }
+
+    #[test]
+    fn load_data_with_range_past_end_errors() {
+        let data: Vec<Byte> = vec![0xA];
+        let range: Range<Address> = 0xF..0x11;
+        let mut memory = Memory::new(0x10, 0x0);
+
+        assert!(memory.load(&data, range).is_err());
+    }
+
+    #[test]
+    fn watched_accesses_are_recorded() {
+        let mut memory: Memory<Byte> = Memory::new(0x10, 0x0);
+        memory.watch(Watchpoint::new(0x4..0x6).read().write());
+
+        memory.write(0x4, 0xA);
+        memory.write(0x8, 0xB);
+        assert_eq!(0xA, memory.read(0x4));
+        assert_eq!(0x0, memory.fetch(0x5));
+        assert_eq!(vec![(0x4, Access::Write, 0x0, 0xA), (0x4, Access::Read, 0xA, 0xA)], memory.take_hits());
+        assert!(memory.take_hits().is_empty());
+    }
+
+    #[test]
+    fn indexing_is_not_watched() {
+        let mut memory: Memory<Byte> = Memory::new(0x10, 0x0);
+        memory.watch(Watchpoint::new(0x0..0x10).read().write().execute());
+
+        memory[0x3] = 0xC;
+        assert_eq!(0xC, memory[0x3]);
+        assert!(memory.take_hits().is_empty());
+    }
+
+    #[test]
+    fn unwatch_removes_covering_watchpoints() {
+        let mut memory: Memory<Byte> = Memory::new(0x10, 0x0);
+        memory.watch(Watchpoint::new(0x0..0x4).write());
+        memory.watch(Watchpoint::new(0x8..0x9).write());
+
+        assert!(memory.unwatch(0x2));
+        assert!(!memory.unwatch(0x2));
+        assert_eq!(&[Watchpoint::new(0x8..0x9).write()], memory.watchpoints());
     }
 }
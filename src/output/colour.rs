@@ -0,0 +1,77 @@
+use Byte;
+
+/// The eight colours of the VP-590 colour board used by CHIP-8X.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Colour {
+    Black,
+    Red,
+    Blue,
+    Violet,
+    Green,
+    Yellow,
+    Aqua,
+    White
+}
+
+/// The order 02A0 steps through background colours, starting from the
+/// colour shown at power on.
+pub const BACKGROUND_CYCLE: [Colour; 4] = [Colour::Blue, Colour::Black, Colour::Green, Colour::Red];
+
+pub const DEFAULT_FOREGROUND: Colour = Colour::Red;
+
+impl Colour {
+    /// Decodes the colour held in the low three bits of `byte`.
+    pub fn from_byte(byte: Byte) -> Colour {
+        match byte & 0b111 {
+            0 => Colour::Black,
+            1 => Colour::Red,
+            2 => Colour::Blue,
+            3 => Colour::Violet,
+            4 => Colour::Green,
+            5 => Colour::Yellow,
+            6 => Colour::Aqua,
+            _ => Colour::White
+        }
+    }
+
+    /// The ANSI foreground colour code closest to this colour.
+    pub fn ansi(&self) -> u8 {
+        match *self {
+            Colour::Black => 30,
+            Colour::Red => 31,
+            Colour::Green => 32,
+            Colour::Yellow => 33,
+            Colour::Blue => 34,
+            Colour::Violet => 35,
+            Colour::Aqua => 36,
+            Colour::White => 37
+        }
+    }
+
+    /// The colour as 0xRRGGBB.
+    pub fn rgb(&self) -> u32 {
+        match *self {
+            Colour::Black => 0x000000,
+            Colour::Red => 0xFF0000,
+            Colour::Blue => 0x0000FF,
+            Colour::Violet => 0xFF00FF,
+            Colour::Green => 0x00FF00,
+            Colour::Yellow => 0xFFFF00,
+            Colour::Aqua => 0x00FFFF,
+            Colour::White => 0xFFFFFF
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_byte_uses_low_three_bits() {
+        assert_eq!(Colour::Black, Colour::from_byte(0x0));
+        assert_eq!(Colour::Violet, Colour::from_byte(0x3));
+        assert_eq!(Colour::White, Colour::from_byte(0x7));
+        assert_eq!(Colour::Red, Colour::from_byte(0xF9));
+    }
+}
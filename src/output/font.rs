@@ -100,3 +100,183 @@ pub const FONT_SET: [Byte; NUM_SPRITES * SPRITE_HEIGHT] = [
     0b10000000,
     0b10000000
 ];
+
+pub const BIG_SPRITE_HEIGHT: usize = 10;
+pub const BIG_FONT_SET: [Byte; NUM_SPRITES * BIG_SPRITE_HEIGHT] = [
+    // 0
+    0b00111100,
+    0b01111110,
+    0b11100111,
+    0b11000011,
+    0b11000011,
+    0b11000011,
+    0b11000011,
+    0b11100111,
+    0b01111110,
+    0b00111100,
+    // 1
+    0b00011000,
+    0b00111000,
+    0b01011000,
+    0b00011000,
+    0b00011000,
+    0b00011000,
+    0b00011000,
+    0b00011000,
+    0b00011000,
+    0b00111100,
+    // 2
+    0b00111110,
+    0b01111111,
+    0b11000011,
+    0b00000110,
+    0b00001100,
+    0b00011000,
+    0b00110000,
+    0b01100000,
+    0b11111111,
+    0b11111111,
+    // 3
+    0b00111100,
+    0b01111110,
+    0b11000011,
+    0b00000011,
+    0b00001110,
+    0b00001110,
+    0b00000011,
+    0b11000011,
+    0b01111110,
+    0b00111100,
+    // 4
+    0b00000110,
+    0b00001110,
+    0b00011110,
+    0b00110110,
+    0b01100110,
+    0b11000110,
+    0b11111111,
+    0b11111111,
+    0b00000110,
+    0b00000110,
+    // 5
+    0b11111111,
+    0b11111111,
+    0b11000000,
+    0b11000000,
+    0b11111100,
+    0b11111110,
+    0b00000011,
+    0b11000011,
+    0b01111110,
+    0b00111100,
+    // 6
+    0b00111110,
+    0b01111100,
+    0b11100000,
+    0b11000000,
+    0b11111100,
+    0b11111110,
+    0b11000011,
+    0b11000011,
+    0b01111110,
+    0b00111100,
+    // 7
+    0b11111111,
+    0b11111111,
+    0b00000011,
+    0b00000110,
+    0b00001100,
+    0b00011000,
+    0b00110000,
+    0b01100000,
+    0b01100000,
+    0b01100000,
+    // 8
+    0b00111100,
+    0b01111110,
+    0b11000011,
+    0b11000011,
+    0b01111110,
+    0b01111110,
+    0b11000011,
+    0b11000011,
+    0b01111110,
+    0b00111100,
+    // 9
+    0b00111100,
+    0b01111110,
+    0b11000011,
+    0b11000011,
+    0b01111111,
+    0b00111111,
+    0b00000011,
+    0b00000011,
+    0b00111110,
+    0b01111100,
+    // A
+    0b01111110,
+    0b11111111,
+    0b11000011,
+    0b11000011,
+    0b11000011,
+    0b11111111,
+    0b11111111,
+    0b11000011,
+    0b11000011,
+    0b11000011,
+    // B
+    0b11111100,
+    0b11111100,
+    0b11000011,
+    0b11000011,
+    0b11111100,
+    0b11111100,
+    0b11000011,
+    0b11000011,
+    0b11111100,
+    0b11111100,
+    // C
+    0b00111100,
+    0b11111111,
+    0b11000011,
+    0b11000000,
+    0b11000000,
+    0b11000000,
+    0b11000000,
+    0b11000011,
+    0b11111111,
+    0b00111100,
+    // D
+    0b11111100,
+    0b11111110,
+    0b11000011,
+    0b11000011,
+    0b11000011,
+    0b11000011,
+    0b11000011,
+    0b11000011,
+    0b11111110,
+    0b11111100,
+    // E
+    0b11111111,
+    0b11111111,
+    0b11000000,
+    0b11000000,
+    0b11111111,
+    0b11111111,
+    0b11000000,
+    0b11000000,
+    0b11111111,
+    0b11111111,
+    // F
+    0b11111111,
+    0b11111111,
+    0b11000000,
+    0b11000000,
+    0b11111111,
+    0b11111111,
+    0b11000000,
+    0b11000000,
+    0b11000000,
+    0b11000000
+];
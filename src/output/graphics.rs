@@ -2,76 +2,257 @@ pub const SPRITE_WIDTH: usize = 8;
 pub const SCREEN_WIDTH: usize = 64;
 pub const SCREEN_HEIGHT: usize = 32;
 pub const SCREEN_WIDTH_SPRITES: usize = SCREEN_WIDTH / SPRITE_WIDTH;
+pub const HIRES_SCREEN_WIDTH: usize = 128;
+pub const HIRES_SCREEN_HEIGHT: usize = 64;
 const SCREEN_SIZE: usize = SCREEN_WIDTH * SCREEN_HEIGHT;
+const HIRES_SCREEN_SIZE: usize = HIRES_SCREEN_WIDTH * HIRES_SCREEN_HEIGHT;
+pub const NUM_PLANES: usize = 2;
+pub const COLOUR_ZONE_WIDTH: usize = 8;
+pub const COLOUR_ZONE_HEIGHT: usize = 4;
+const COLOUR_COLUMNS: usize = SCREEN_WIDTH / COLOUR_ZONE_WIDTH;
 
 use memory::Memory;
-use Address;
+use output::colour::{self, Colour};
+use {Address, Byte};
 
 pub trait GraphicsOutput {
-    fn read_pixel(&self, Address, Address) -> bool;
-    fn update_pixel(&mut self, Address, Address, bool) -> bool;
+    fn read_plane_pixel(&self, plane: usize, x: Address, y: Address) -> bool;
+    fn update_plane_pixel(&mut self, plane: usize, x: Address, y: Address, val: bool) -> bool;
     fn clear(&mut self);
     fn draw(&mut self);
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    fn high_res(&self) -> bool;
+    fn set_high_res(&mut self, high_res: bool);
+    fn scroll(&mut self, dx: isize, dy: isize);
+    fn planes(&self) -> Byte;
+    fn select_planes(&mut self, planes: Byte);
+    /// Foreground colour of the CHIP-8X colour zone containing the pixel.
+    fn foreground(&self, x: Address, y: Address) -> Colour;
+    /// Sets the foreground colour of the 8x1 pixel CHIP-8X colour zone
+    /// containing the pixel.
+    fn set_foreground(&mut self, x: Address, y: Address, colour: Colour);
+    fn background(&self) -> Colour;
+    fn cycle_background(&mut self);
+    /// Whether the program has set a CHIP-8X colour, which switches
+    /// printing to ANSI colour.
+    fn coloured(&self) -> bool;
+    fn set_coloured(&mut self, coloured: bool);
+
+    fn read_pixel(&self, x: Address, y: Address) -> bool {
+        self.read_plane_pixel(0, x, y)
+    }
+
+    fn update_pixel(&mut self, x: Address, y: Address, val: bool) -> bool {
+        self.update_plane_pixel(0, x, y, val)
+    }
 }
 
 pub struct Display {
     redraw: bool,
-    memory: Memory<bool>
+    print: bool,
+    high_res: bool,
+    planes: Byte,
+    memory: Memory<bool>,
+    second_plane: Memory<bool>,
+    coloured: bool,
+    background: usize,
+    colours: Memory<Colour>
 }
 
 impl Display {
     pub fn new() -> Display {
         Display {
             redraw: false,
-            memory: Memory::new(SCREEN_SIZE, false)
+            print: true,
+            high_res: false,
+            planes: 0b01,
+            memory: Memory::new(SCREEN_SIZE, false),
+            second_plane: Memory::new(SCREEN_SIZE, false),
+            coloured: false,
+            background: 0,
+            colours: Memory::new(COLOUR_COLUMNS * SCREEN_HEIGHT, colour::DEFAULT_FOREGROUND)
         }
     }
+
+    /// A display whose `draw` prints nothing, for frontends that render the
+    /// pixels themselves.
+    pub fn quiet() -> Display {
+        Display {
+            print: false,
+            ..Display::new()
+        }
+    }
+
+    fn size(&self) -> usize {
+        if self.high_res { HIRES_SCREEN_SIZE } else { SCREEN_SIZE }
+    }
+
+    fn plane_memory(&self, plane: usize) -> &Memory<bool> {
+        if plane == 0 { &self.memory } else { &self.second_plane }
+    }
+
+    fn plane_memory_mut(&mut self, plane: usize) -> &mut Memory<bool> {
+        if plane == 0 { &mut self.memory } else { &mut self.second_plane }
+    }
+
+    fn selected(&self, plane: usize) -> bool {
+        self.planes & (0b1 << plane) != 0
+    }
+
+    /// The colour layer always has low-resolution zones, so high-resolution
+    /// pixels share the zone of the low-resolution pixel they cover.
+    fn colour_index(&self, x: Address, y: Address) -> usize {
+        let (x, y) = if self.high_res { (x / 2, y / 2) } else { (x, y) };
+        (y % SCREEN_HEIGHT) * COLOUR_COLUMNS + (x % SCREEN_WIDTH) / COLOUR_ZONE_WIDTH
+    }
+}
+
+impl Default for Display {
+    fn default() -> Display {
+        Display::new()
+    }
 }
 
 impl GraphicsOutput for Display {
-    fn read_pixel(&self, x: Address, y: Address) -> bool {
-        let x = x % SCREEN_WIDTH;
-        let y = y % SCREEN_HEIGHT;
-        self.memory[y * SCREEN_WIDTH + x]
+    fn read_plane_pixel(&self, plane: usize, x: Address, y: Address) -> bool {
+        let x = x % self.width();
+        let y = y % self.height();
+        self.plane_memory(plane)[y * self.width() + x]
     }
 
-    fn update_pixel(&mut self, x: Address, y: Address, val: bool) -> bool {
-        let x = x % SCREEN_WIDTH;
-        let y = y % SCREEN_HEIGHT;
-        let old = self.read_pixel(x, y);
+    fn update_plane_pixel(&mut self, plane: usize, x: Address, y: Address, val: bool) -> bool {
+        let x = x % self.width();
+        let y = y % self.height();
+        let old = self.read_plane_pixel(plane, x, y);
         let collision = old & val;
         let new = old ^ val;
         if new != old {
-            self.memory[y * SCREEN_WIDTH + x] = new;
+            let width = self.width();
+            self.plane_memory_mut(plane)[y * width + x] = new;
             self.redraw = true;
         }
         collision
     }
 
     fn clear(&mut self) {
-        self.memory = Memory::new(SCREEN_SIZE, false);
+        let size = self.size();
+        for plane in 0..NUM_PLANES {
+            if self.selected(plane) {
+                *self.plane_memory_mut(plane) = Memory::new(size, false);
+            }
+        }
     }
 
     fn draw(&mut self) {
         if !self.redraw { return; }
+        if !self.print {
+            self.redraw = false;
+            return;
+        }
 
-        let lines = self.memory.iter().enumerate()
-            .fold(String::new(), |mut acc, (i, bit)| {
-                if (i % SCREEN_WIDTH) == 0 {
-                    acc.push_str(&format!("\n{:02} ", i / 64));
+        let width = self.width();
+        let lines = self.memory.iter().zip(self.second_plane.iter()).enumerate()
+            .fold(String::new(), |mut acc, (i, (first, second))| {
+                if (i % width) == 0 {
+                    acc.push_str(&format!("\n{:02} ", i / width));
                 }
 
-                let c = match *bit {
-                    true => "  ",
-                    false => "▓▓︎"
+                let c = match (*first, *second) {
+                    (true, true) => "░░",
+                    (true, false) => "  ",
+                    (false, true) => "▒▒",
+                    (false, false) => "▓▓︎"
                 };
 
-                acc.push_str(c);
+                if self.coloured && *first {
+                    let colour = self.foreground(i % width, i / width);
+                    acc.push_str(&format!("\x1b[{}m{}\x1b[0m", colour.ansi(), c));
+                } else {
+                    acc.push_str(c);
+                }
                 acc
             });
         println!("{}", lines);
         self.redraw = false;
     }
+
+    fn width(&self) -> usize {
+        if self.high_res { HIRES_SCREEN_WIDTH } else { SCREEN_WIDTH }
+    }
+
+    fn height(&self) -> usize {
+        if self.high_res { HIRES_SCREEN_HEIGHT } else { SCREEN_HEIGHT }
+    }
+
+    fn high_res(&self) -> bool {
+        self.high_res
+    }
+
+    fn set_high_res(&mut self, high_res: bool) {
+        self.high_res = high_res;
+        self.memory = Memory::new(self.size(), false);
+        self.second_plane = Memory::new(self.size(), false);
+        self.redraw = true;
+    }
+
+    fn scroll(&mut self, dx: isize, dy: isize) {
+        let width = self.width() as isize;
+        let height = self.height() as isize;
+        for plane in 0..NUM_PLANES {
+            if !self.selected(plane) { continue; }
+
+            let mut memory = Memory::new(self.size(), false);
+            for y in 0..height {
+                for x in 0..width {
+                    let (src_x, src_y) = (x - dx, y - dy);
+                    if src_x < 0 || src_x >= width || src_y < 0 || src_y >= height { continue; }
+
+                    memory[(y * width + x) as usize] = self.plane_memory(plane)[(src_y * width + src_x) as usize];
+                }
+            }
+            *self.plane_memory_mut(plane) = memory;
+        }
+        self.redraw = true;
+    }
+
+    fn planes(&self) -> Byte {
+        self.planes
+    }
+
+    fn select_planes(&mut self, planes: Byte) {
+        self.planes = planes & 0b11;
+    }
+
+    fn foreground(&self, x: Address, y: Address) -> Colour {
+        self.colours[self.colour_index(x, y)]
+    }
+
+    fn set_foreground(&mut self, x: Address, y: Address, colour: Colour) {
+        let i = self.colour_index(x, y);
+        self.colours[i] = colour;
+        self.coloured = true;
+        self.redraw = true;
+    }
+
+    fn background(&self) -> Colour {
+        colour::BACKGROUND_CYCLE[self.background]
+    }
+
+    fn cycle_background(&mut self) {
+        self.background = (self.background + 1) % colour::BACKGROUND_CYCLE.len();
+        self.coloured = true;
+        self.redraw = true;
+    }
+
+    fn coloured(&self) -> bool {
+        self.coloured
+    }
+
+    fn set_coloured(&mut self, coloured: bool) {
+        self.coloured = coloured;
+        self.redraw = true;
+    }
 }
 
 #[cfg(test)]
@@ -82,7 +263,7 @@ mod tests {
     fn new_display() {
         let d = Display::new();
         assert!(!d.redraw);
-        assert!(d.memory.iter().all(|bit| *bit == false));
+        assert!(d.memory.iter().all(|bit| !*bit));
         assert_eq!(SCREEN_SIZE, d.memory.len());
     }
 
@@ -104,7 +285,7 @@ mod tests {
         let mut d = Display::new();
         let x = 64;
         let y = 30;
-        let i = y * SCREEN_WIDTH + 0;
+        let i = y * SCREEN_WIDTH;
 
         assert!(!d.read_pixel(x, y));
 
@@ -171,7 +352,7 @@ mod tests {
         let mut d = Display::new();
         let x = 64;
         let y = 30;
-        let i = y * SCREEN_WIDTH + 0;
+        let i = y * SCREEN_WIDTH;
 
         d.memory[i] = true;
         let collision = d.update_pixel(x, y, true);
@@ -186,7 +367,131 @@ mod tests {
         d.memory = Memory::new(SCREEN_SIZE, true);
 
         d.clear();
-        assert!(d.memory.iter().all(|pixel| *pixel == false), "clear should set all pixels to false");
+        assert!(d.memory.iter().all(|pixel| !*pixel), "clear should set all pixels to false");
+    }
+
+    #[test]
+    fn set_high_res_resizes_display() {
+        let mut d = Display::new();
+
+        d.set_high_res(true);
+        assert_eq!(HIRES_SCREEN_WIDTH, d.width());
+        assert_eq!(HIRES_SCREEN_HEIGHT, d.height());
+        assert_eq!(HIRES_SCREEN_SIZE, d.memory.len());
+
+        d.set_high_res(false);
+        assert_eq!(SCREEN_WIDTH, d.width());
+        assert_eq!(SCREEN_SIZE, d.memory.len());
+    }
+
+    #[test]
+    fn update_pixel_high_res_wrap() {
+        let mut d = Display::new();
+        d.set_high_res(true);
+
+        d.update_pixel(HIRES_SCREEN_WIDTH + 1, HIRES_SCREEN_HEIGHT + 2, true);
+        assert!(d.read_pixel(1, 2));
+        assert!(d.memory[2 * HIRES_SCREEN_WIDTH + 1]);
+    }
+
+    #[test]
+    fn scroll_down_moves_pixels_and_fills_blank() {
+        let mut d = Display::new();
+        d.update_pixel(5, 0, true);
+        d.update_pixel(5, SCREEN_HEIGHT - 1, true);
+
+        d.scroll(0, 2);
+        assert!(d.read_pixel(5, 2));
+        assert!(!d.read_pixel(5, 0));
+        assert!(!d.memory.iter().skip(SCREEN_WIDTH * 3).any(|pixel| *pixel));
+    }
+
+    #[test]
+    fn scroll_left_and_right() {
+        let mut d = Display::new();
+        d.update_pixel(10, 3, true);
+
+        d.scroll(4, 0);
+        assert!(d.read_pixel(14, 3));
+
+        d.scroll(-4, 0);
+        assert!(d.read_pixel(10, 3));
+        assert!(!d.read_pixel(14, 3));
+    }
+
+    #[test]
+    fn update_second_plane_pixel() {
+        let mut d = Display::new();
+
+        let collision = d.update_plane_pixel(1, 3, 4, true);
+        assert!(!collision);
+        assert!(d.read_plane_pixel(1, 3, 4));
+        assert!(!d.read_pixel(3, 4));
+        assert!(d.second_plane[4 * SCREEN_WIDTH + 3]);
+    }
+
+    #[test]
+    fn clear_only_clears_selected_planes() {
+        let mut d = Display::new();
+        d.update_plane_pixel(0, 1, 1, true);
+        d.update_plane_pixel(1, 1, 1, true);
+
+        d.select_planes(0b10);
+        d.clear();
+        assert!(d.read_plane_pixel(0, 1, 1));
+        assert!(!d.read_plane_pixel(1, 1, 1));
+    }
+
+    #[test]
+    fn scroll_only_scrolls_selected_planes() {
+        let mut d = Display::new();
+        d.update_plane_pixel(0, 1, 1, true);
+        d.update_plane_pixel(1, 1, 1, true);
+
+        d.select_planes(0b11);
+        d.scroll(0, -1);
+        assert!(d.read_plane_pixel(0, 1, 0));
+        assert!(d.read_plane_pixel(1, 1, 0));
+
+        d.select_planes(0b01);
+        d.scroll(1, 0);
+        assert!(d.read_plane_pixel(0, 2, 0));
+        assert!(d.read_plane_pixel(1, 1, 0));
+    }
+
+    #[test]
+    fn select_planes_masks_to_two_planes() {
+        let mut d = Display::new();
+
+        d.select_planes(0b111);
+        assert_eq!(0b11, d.planes());
+    }
+
+    #[test]
+    fn set_foreground_colours_8x1_zone() {
+        let mut d = Display::new();
+        assert_eq!(colour::DEFAULT_FOREGROUND, d.foreground(0, 0));
+
+        d.set_foreground(10, 5, Colour::Green);
+        assert_eq!(Colour::Green, d.foreground(8, 5));
+        assert_eq!(Colour::Green, d.foreground(15, 5));
+        assert_eq!(colour::DEFAULT_FOREGROUND, d.foreground(16, 5));
+        assert_eq!(colour::DEFAULT_FOREGROUND, d.foreground(10, 6));
+        assert!(d.redraw);
+    }
+
+    #[test]
+    fn cycle_background_wraps() {
+        let mut d = Display::new();
+        assert_eq!(Colour::Blue, d.background());
+
+        d.cycle_background();
+        assert_eq!(Colour::Black, d.background());
+
+        d.cycle_background();
+        d.cycle_background();
+        d.cycle_background();
+        assert_eq!(Colour::Blue, d.background());
     }
 
     #[test]
@@ -197,4 +502,13 @@ mod tests {
         d.draw();
         assert!(!d.redraw, "draw should reset redraw to false");
     }
+
+    #[test]
+    fn quiet_draw_resets_redraw_to_false() {
+        let mut d = Display::quiet();
+        d.redraw = true;
+
+        d.draw();
+        assert!(!d.redraw, "draw should reset redraw to false");
+    }
 }
@@ -1,3 +1,4 @@
+pub mod colour;
 pub mod font;
 pub mod graphics;
 pub mod sound;
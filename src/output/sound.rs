@@ -1,3 +1,110 @@
+use Byte;
+
+pub const PATTERN_SIZE: usize = 16;
+pub const DEFAULT_PITCH: Byte = 64;
+const PATTERN_BITS: usize = PATTERN_SIZE * 8;
+const BASE_RATE: f64 = 4000.0;
+
 pub trait SoundOutput {
-    fn beep(&self, bool);
+    fn beep(&self, on: bool);
+}
+
+/// The XO-CHIP 1-bit audio pattern buffer. The default pattern plays a
+/// 1000 Hz square wave at the default pitch.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AudioPattern {
+    pub buffer: [Byte; PATTERN_SIZE],
+    pub pitch: Byte,
+    phase: f64
+}
+
+impl AudioPattern {
+    pub fn new() -> AudioPattern {
+        AudioPattern {
+            buffer: [0b11001100; PATTERN_SIZE],
+            pitch: DEFAULT_PITCH,
+            phase: 0.0
+        }
+    }
+
+    /// Playback rate of the pattern in bits per second.
+    pub fn rate(&self) -> f64 {
+        BASE_RATE * 2f64.powf((self.pitch as f64 - 64.0) / 48.0)
+    }
+
+    pub fn bit(&self, index: usize) -> bool {
+        let index = index % PATTERN_BITS;
+        self.buffer[index / 8] & (0b10000000 >> (index % 8)) != 0
+    }
+
+    /// Fills `samples` with the pattern played at `sample_rate`, continuing
+    /// from where the previous call stopped.
+    pub fn render(&mut self, samples: &mut [f32], sample_rate: u32, amplitude: f32) {
+        let step = self.rate() / sample_rate as f64;
+        for sample in samples.iter_mut() {
+            *sample = if self.bit(self.phase as usize) { amplitude } else { -amplitude };
+            self.phase = (self.phase + step) % PATTERN_BITS as f64;
+        }
+    }
+}
+
+impl Default for AudioPattern {
+    fn default() -> AudioPattern {
+        AudioPattern::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_pitch_plays_4000_bits_per_second() {
+        let a = AudioPattern::new();
+        assert_eq!(4000.0, a.rate());
+    }
+
+    #[test]
+    fn pitch_doubles_rate_every_48_steps() {
+        let mut a = AudioPattern::new();
+
+        a.pitch = DEFAULT_PITCH + 48;
+        assert!((a.rate() - 8000.0).abs() < 1e-6);
+
+        a.pitch = DEFAULT_PITCH - 48;
+        assert!((a.rate() - 2000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn bit_reads_most_significant_first() {
+        let mut a = AudioPattern::new();
+        a.buffer = [0x0; PATTERN_SIZE];
+        a.buffer[1] = 0b10000001;
+
+        assert!(!a.bit(7));
+        assert!(a.bit(8));
+        assert!(a.bit(15));
+        assert!(a.bit(PATTERN_BITS + 8));
+    }
+
+    #[test]
+    fn render_steps_through_pattern() {
+        let mut a = AudioPattern::new();
+        let mut samples = [0.0; 8];
+
+        a.render(&mut samples, 4000, 1.0);
+        assert_eq!([1.0, 1.0, -1.0, -1.0, 1.0, 1.0, -1.0, -1.0], samples);
+    }
+
+    #[test]
+    fn render_continues_phase() {
+        let mut a = AudioPattern::new();
+        let mut first = [0.0; 3];
+        let mut second = [0.0; 3];
+
+        a.render(&mut first, 4000, 0.5);
+        a.render(&mut second, 4000, 0.5);
+        assert_eq!([0.5, 0.5, -0.5], first);
+        assert_eq!([-0.5, 0.5, 0.5], second);
+    }
 }
@@ -0,0 +1,308 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cpu::Cpu;
+use cpu::clock::{ExternalClock, FRAME_RATE, DEFAULT_INSTRUCTIONS_PER_FRAME};
+use error::Result;
+use input::Input;
+use output::graphics::GraphicsOutput;
+
+/// Frames `update` will run at once to catch up. Anything beyond is
+/// dropped rather than run in a burst.
+const MAX_CATCH_UP_FRAMES: u32 = 10;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub instructions_per_second: f64,
+    pub frames_per_second: f64
+}
+
+/// Runs a `Cpu` in 60 Hz frames: a fixed number of instructions, one
+/// timer tick and one presented display per frame, paced against real
+/// time at an adjustable speed.
+#[derive(Debug)]
+pub struct Runner<'a, G: 'a, I: 'a> where G: GraphicsOutput, I: Input {
+    cpu: Cpu<'a, G, I>,
+    instructions_per_frame: u32,
+    speed: f64,
+    paused: bool,
+    last_update: Option<Instant>,
+    owed_frames: f64,
+    window_start: Option<Instant>,
+    window_instructions: u64,
+    window_frames: u64,
+    stats: Stats
+}
+
+impl<'a, G, I> Runner<'a, G, I> where G: GraphicsOutput, I: Input {
+    /// Takes over the CPU's timers; it should not be given another clock.
+    pub fn new(mut cpu: Cpu<'a, G, I>) -> Runner<'a, G, I> {
+        cpu.set_clock(ExternalClock);
+        Runner {
+            cpu,
+            instructions_per_frame: DEFAULT_INSTRUCTIONS_PER_FRAME,
+            speed: 1.0,
+            paused: false,
+            last_update: None,
+            owed_frames: 0.0,
+            window_start: None,
+            window_instructions: 0,
+            window_frames: 0,
+            stats: Stats::default()
+        }
+    }
+
+    pub fn cpu(&self) -> &Cpu<'a, G, I> {
+        &self.cpu
+    }
+
+    pub fn cpu_mut(&mut self) -> &mut Cpu<'a, G, I> {
+        &mut self.cpu
+    }
+
+    pub fn instructions_per_frame(&self) -> u32 {
+        self.instructions_per_frame
+    }
+
+    pub fn set_instructions_per_frame(&mut self, instructions: u32) {
+        self.instructions_per_frame = instructions.max(1);
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Scales how many frames run per real second: above 1.0 fast-forwards,
+    /// below 1.0 plays in slow motion.
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed.max(0.0);
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.last_update = None;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        if self.paused { self.resume(); } else { self.pause(); }
+    }
+
+    /// Runs exactly one frame and leaves the runner paused.
+    pub fn frame_advance(&mut self) -> Result<()> {
+        self.pause();
+        self.run_frame()
+    }
+
+    /// Measured rates over the last complete second of `update` calls.
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// Runs one frame: the configured instructions, stopping early if the
+    /// program exits, then a timer tick and a display update. A watchpoint
+    /// hit also ends the frame early and pauses; the hits are left on the
+    /// CPU for the frontend to take.
+    pub fn run_frame(&mut self) -> Result<()> {
+        for _ in 0..self.instructions_per_frame {
+            if self.cpu.exit { break; }
+            let hits = self.cpu.watch_hits().len();
+            self.cpu.step()?;
+            self.window_instructions += 1;
+            if self.cpu.watch_hits().len() > hits {
+                self.pause();
+                break;
+            }
+        }
+        self.cpu.end_frame();
+        self.cpu.graphics_mut().draw();
+        self.window_frames += 1;
+        Ok(())
+    }
+
+    /// Runs the frames due since the previous call at the current speed and
+    /// returns how many ran. Call it as often as the frontend likes.
+    pub fn update(&mut self, now: Instant) -> Result<u32> {
+        let elapsed = self.last_update.map_or(Duration::from_secs(0), |last| now.duration_since(last));
+        self.last_update = Some(now);
+        if self.paused {
+            self.update_stats(now);
+            return Ok(0);
+        }
+
+        self.owed_frames += elapsed.as_secs_f64() * FRAME_RATE as f64 * self.speed;
+        // Frame durations are rounded down to whole nanoseconds, so allow
+        // for a frame falling a hair short.
+        let due = (self.owed_frames + 1e-6).floor();
+        let frames = (due as u32).min(MAX_CATCH_UP_FRAMES);
+        self.owed_frames = if due as u32 > frames { 0.0 } else { (self.owed_frames - due).max(0.0) };
+
+        let mut ran = 0;
+        while ran < frames && !self.cpu.exit && !self.paused {
+            self.run_frame()?;
+            ran += 1;
+        }
+        self.update_stats(now);
+        Ok(ran)
+    }
+
+    /// Runs in real time until the program exits.
+    pub fn run(&mut self) -> Result<()> {
+        let frame = Duration::from_secs(1) / FRAME_RATE;
+        self.update(Instant::now())?;
+        while !self.cpu.exit {
+            thread::sleep(frame);
+            self.update(Instant::now())?;
+        }
+        Ok(())
+    }
+
+    fn update_stats(&mut self, now: Instant) {
+        let start = *self.window_start.get_or_insert(now);
+        let window = now.duration_since(start).as_secs_f64();
+        if window < 1.0 { return; }
+
+        self.stats = Stats {
+            instructions_per_second: self.window_instructions as f64 / window,
+            frames_per_second: self.window_frames as f64 / window
+        };
+        self.window_start = Some(now);
+        self.window_instructions = 0;
+        self.window_frames = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cpu::platform::Platform;
+    use cpu::quirks::Quirks;
+    use cpu::watch::Watchpoint;
+    use input::keypad::Keypad;
+    use output::graphics::Display;
+
+    const LOOP_ROM: [u8; 4] = [0x70, 0x01, 0x12, 0x00];
+
+    fn frame() -> Duration {
+        Duration::from_secs(1) / FRAME_RATE
+    }
+
+    #[test]
+    fn run_frame_runs_configured_instructions() {
+        let mut graphics = Display::new();
+        let mut keypad = Keypad::new();
+        let cpu = Cpu::new(&LOOP_ROM, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        let mut runner = Runner::new(cpu);
+        runner.set_instructions_per_frame(20);
+
+        runner.run_frame().unwrap();
+        runner.run_frame().unwrap();
+        assert_eq!(40, runner.window_instructions);
+        assert_eq!(2, runner.window_frames);
+    }
+
+    #[test]
+    fn watchpoint_hit_pauses() {
+        let mut graphics = Display::new();
+        let mut keypad = Keypad::new();
+        let mut cpu = Cpu::new(&LOOP_ROM, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        cpu.add_watchpoint(Watchpoint::new(0x202..0x203).execute());
+        let mut runner = Runner::new(cpu);
+        let start = Instant::now();
+
+        runner.update(start).unwrap();
+        assert_eq!(1, runner.update(start + frame() * 3).unwrap());
+        assert!(runner.paused());
+        assert_eq!(2, runner.window_instructions);
+        assert_eq!(0x202, runner.cpu_mut().take_watch_hits()[0].pc);
+    }
+
+    #[test]
+    fn update_runs_frames_for_elapsed_time() {
+        let mut graphics = Display::new();
+        let mut keypad = Keypad::new();
+        let cpu = Cpu::new(&LOOP_ROM, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        let mut runner = Runner::new(cpu);
+        let start = Instant::now();
+
+        assert_eq!(0, runner.update(start).unwrap());
+        assert_eq!(3, runner.update(start + frame() * 3).unwrap());
+        assert_eq!(0, runner.update(start + frame() * 3).unwrap());
+    }
+
+    #[test]
+    fn update_caps_catch_up() {
+        let mut graphics = Display::new();
+        let mut keypad = Keypad::new();
+        let cpu = Cpu::new(&LOOP_ROM, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        let mut runner = Runner::new(cpu);
+        let start = Instant::now();
+
+        runner.update(start).unwrap();
+        assert_eq!(MAX_CATCH_UP_FRAMES, runner.update(start + Duration::from_secs(5)).unwrap());
+        assert_eq!(1, runner.update(start + Duration::from_secs(5) + frame()).unwrap());
+    }
+
+    #[test]
+    fn speed_scales_frames() {
+        let mut graphics = Display::new();
+        let mut keypad = Keypad::new();
+        let cpu = Cpu::new(&LOOP_ROM, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        let mut runner = Runner::new(cpu);
+        let start = Instant::now();
+
+        runner.set_speed(2.0);
+        runner.update(start).unwrap();
+        assert_eq!(4, runner.update(start + frame() * 2).unwrap());
+
+        runner.set_speed(0.5);
+        assert_eq!(0, runner.update(start + frame() * 3).unwrap());
+        assert_eq!(1, runner.update(start + frame() * 4).unwrap());
+    }
+
+    #[test]
+    fn pause_stops_frames_and_frame_advance_runs_one() {
+        let mut graphics = Display::new();
+        let mut keypad = Keypad::new();
+        let cpu = Cpu::new(&LOOP_ROM, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        let mut runner = Runner::new(cpu);
+        let start = Instant::now();
+
+        runner.update(start).unwrap();
+        runner.pause();
+        assert_eq!(0, runner.update(start + frame() * 5).unwrap());
+
+        runner.frame_advance().unwrap();
+        assert!(runner.paused());
+        assert_eq!(1, runner.window_frames);
+
+        runner.resume();
+        assert_eq!(0, runner.update(start + frame() * 6).unwrap());
+        assert_eq!(1, runner.update(start + frame() * 7).unwrap());
+    }
+
+    #[test]
+    fn stats_measure_rates_over_a_second() {
+        let mut graphics = Display::new();
+        let mut keypad = Keypad::new();
+        let cpu = Cpu::new(&LOOP_ROM, &mut graphics, &mut keypad, Platform::Chip8, Quirks::default()).unwrap();
+        let mut runner = Runner::new(cpu);
+        let start = Instant::now();
+
+        runner.update(start).unwrap();
+        for n in 1..=7 {
+            runner.update(start + frame() * n * 10).unwrap();
+        }
+
+        let stats = runner.stats();
+        assert!((stats.frames_per_second - 60.0).abs() < 1.0, "{:?}", stats);
+        assert!((stats.instructions_per_second - 600.0).abs() < 10.0, "{:?}", stats);
+    }
+}
@@ -0,0 +1,335 @@
+extern crate libc;
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cpu::clock::FRAME_RATE;
+use cpu::rewind::Rewind;
+use error::{Error, Result};
+use golden::Image;
+use input::keypad::{Keypad, NUM_KEYS};
+use input::mapping::{HostKey, KeyMap};
+use output::graphics::{Display, GraphicsOutput};
+use runner::{Runner, Stats};
+use Byte;
+
+/// Frames a key stays down after its last byte arrives. Terminals only
+/// report presses, so a held key shows up as the autorepeat stream.
+const HOLD_FRAMES: u8 = 10;
+
+/// How far back rewinding can reach, and how far one press goes.
+const REWIND_SECONDS: usize = 60;
+const REWIND_STEP: usize = FRAME_RATE as usize;
+const REWIND_BYTES: usize = 16 << 20;
+
+const ESC: char = '\x1b';
+const CTRL_C: char = '\x03';
+const CTRL_L: char = '\x0c';
+const CTRL_R: char = '\x12';
+const CTRL_S: char = '\x13';
+const BACKSPACE: char = '\x08';
+const DELETE: char = '\x7f';
+
+const HOTKEYS: &str = "space pause  bksp rewind  ^R reset  ^S save  ^L load  esc quit";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Key(Byte),
+    Pause,
+    Rewind,
+    Reset,
+    Save,
+    Load,
+    Quit
+}
+
+/// Turns bytes typed at the terminal into keypad presses and hotkeys, and
+/// releases keys once they stop repeating.
+#[derive(Clone, Debug)]
+pub struct Keyboard {
+    map: KeyMap,
+    held: [u8; NUM_KEYS]
+}
+
+impl Keyboard {
+    pub fn new(map: KeyMap) -> Keyboard {
+        Keyboard {
+            map,
+            held: [0; NUM_KEYS]
+        }
+    }
+
+    /// Escape sequences such as arrow keys are skipped; a lone escape quits.
+    pub fn actions(&self, bytes: &[u8]) -> Vec<Action> {
+        let text = String::from_utf8_lossy(bytes);
+        let mut chars = text.chars().peekable();
+        let mut actions = Vec::new();
+        while let Some(c) = chars.next() {
+            let action = match c {
+                ESC => match chars.peek() {
+                    Some(&'[') | Some(&'O') => {
+                        chars.next();
+                        for c in chars.by_ref() {
+                            if ('\x40'..='\x7e').contains(&c) { break; }
+                        }
+                        None
+                    },
+                    _ => Some(Action::Quit)
+                },
+                CTRL_C => Some(Action::Quit),
+                ' ' => Some(Action::Pause),
+                BACKSPACE | DELETE => Some(Action::Rewind),
+                CTRL_R => Some(Action::Reset),
+                CTRL_S => Some(Action::Save),
+                CTRL_L => Some(Action::Load),
+                c => self.map.key(HostKey::from_char(c)).map(Action::Key)
+            };
+            actions.extend(action);
+        }
+        actions
+    }
+
+    pub fn press(&mut self, keypad: &mut Keypad, key: Byte) {
+        keypad.press(key);
+        self.held[key as usize & 0xF] = HOLD_FRAMES;
+    }
+
+    /// Counts down one frame, releasing keys that were not repeated.
+    pub fn tick(&mut self, keypad: &mut Keypad) {
+        for (key, frames) in self.held.iter_mut().enumerate() {
+            if *frames == 0 { continue; }
+            *frames -= 1;
+            if *frames == 0 { keypad.release(key as Byte); }
+        }
+    }
+}
+
+/// Paints the display with half blocks, two pixel rows to a character
+/// cell, and a status bar below it. Only cells that changed since the last
+/// call are repainted.
+#[derive(Clone, Debug, Default)]
+pub struct Renderer {
+    width: usize,
+    rows: usize,
+    cells: Vec<char>,
+    status: String
+}
+
+impl Renderer {
+    pub fn new() -> Renderer {
+        Renderer::default()
+    }
+
+    /// The escape sequences that bring the terminal up to date.
+    pub fn render<G>(&mut self, graphics: &G, status: &str) -> String where G: GraphicsOutput {
+        let image = Image::capture(graphics);
+        let (width, rows) = (image.width(), image.height().div_ceil(2));
+        let mut out = String::new();
+        if (width, rows) != (self.width, self.rows) {
+            out.push_str("\x1b[2J");
+            self.width = width;
+            self.rows = rows;
+            self.cells = vec!['\0'; width * rows];
+            self.status.clear();
+        }
+
+        let mut cursor = None;
+        for row in 0..rows {
+            for x in 0..width {
+                let top = image.pixel(x, row * 2);
+                let bottom = row * 2 + 1 < image.height() && image.pixel(x, row * 2 + 1);
+                let cell = match (top, bottom) {
+                    (false, false) => ' ',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (true, true) => '█'
+                };
+                let old = &mut self.cells[row * width + x];
+                if *old == cell { continue; }
+                *old = cell;
+                if cursor != Some((x, row)) { out.push_str(&format!("\x1b[{};{}H", row + 1, x + 1)); }
+                out.push(cell);
+                cursor = Some((x + 1, row));
+            }
+        }
+
+        if status != self.status {
+            out.push_str(&format!("\x1b[{};1H\x1b[2K\x1b[7m{}\x1b[0m", rows + 1, status));
+            self.status = status.to_string();
+        }
+        out
+    }
+}
+
+/// The status bar: ROM name, frame rate, beep indicator and either the last
+/// message or the hotkeys.
+pub fn status(rom: &str, stats: Stats, beep: bool, paused: bool, message: &str) -> String {
+    format!(" {}  {:>3.0} fps  {}  {}{}",
+        rom, stats.frames_per_second,
+        if beep { '♪' } else { ' ' },
+        if paused { "PAUSED  " } else { "" },
+        if message.is_empty() { HOTKEYS } else { message }
+    )
+}
+
+/// Puts the terminal in raw mode on the alternate screen with the cursor
+/// hidden, and restores it when dropped.
+pub struct RawTerminal {
+    original: libc::termios
+}
+
+impl RawTerminal {
+    pub fn enter() -> Result<RawTerminal> {
+        let original = unsafe {
+            let mut termios: libc::termios = mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut termios) != 0 {
+                return Err(Error::Io(io::Error::last_os_error()));
+            }
+            let original = termios;
+            libc::cfmakeraw(&mut termios);
+            // Reads return at once, with whatever has been typed.
+            termios.c_cc[libc::VMIN] = 0;
+            termios.c_cc[libc::VTIME] = 0;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &termios) != 0 {
+                return Err(Error::Io(io::Error::last_os_error()));
+            }
+            original
+        };
+        let mut out = io::stdout();
+        out.write_all(b"\x1b[?1049h\x1b[?25l\x1b[2J")?;
+        out.flush()?;
+        Ok(RawTerminal { original })
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let mut out = io::stdout();
+        let _ = out.write_all(b"\x1b[0m\x1b[?25h\x1b[?1049l");
+        let _ = out.flush();
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original); }
+    }
+}
+
+/// Runs the ROM full screen until the user quits. A program that exits
+/// leaves its last screen up. Save states are kept next to the ROM as
+/// `<rom>.state`; reset restores the state the runner started in. The last
+/// minute of play is recorded as it is drawn, and each rewind press steps
+/// back about a second of it.
+pub fn run(runner: &mut Runner<Display, Keypad>, rom: &Path, map: KeyMap) -> Result<()> {
+    let name = rom.file_name().map_or_else(|| rom.display().to_string(), |name| name.to_string_lossy().into_owned());
+    let state_path = format!("{}.state", rom.display());
+    let start = runner.cpu().save_state();
+    let mut keyboard = Keyboard::new(map);
+    let mut renderer = Renderer::new();
+    let mut rewind = Rewind::new(REWIND_SECONDS * FRAME_RATE as usize, REWIND_BYTES);
+    let mut message = String::new();
+    let mut typed = [0; 64];
+    let frame = Duration::from_secs(1) / FRAME_RATE;
+
+    let _terminal = RawTerminal::enter()?;
+    loop {
+        let n = io::stdin().read(&mut typed)?;
+        for action in keyboard.actions(&typed[..n]) {
+            match action {
+                Action::Key(key) => keyboard.press(runner.cpu_mut().input_mut(), key),
+                Action::Pause => runner.toggle_pause(),
+                Action::Rewind => message = if rewind.restore(runner.cpu_mut(), REWIND_STEP)? {
+                    "Rewound".to_string()
+                } else {
+                    "Nothing to rewind".to_string()
+                },
+                Action::Reset => {
+                    runner.cpu_mut().load_state(&start)?;
+                    message = "Reset".to_string();
+                },
+                Action::Save => message = match fs::write(&state_path, runner.cpu().save_state()) {
+                    Ok(()) => format!("Saved {}", state_path),
+                    Err(err) => format!("Could not save {}: {}", state_path, err)
+                },
+                Action::Load => message = match fs::read(&state_path).map_err(Error::from)
+                    .and_then(|state| runner.cpu_mut().load_state(&state)) {
+                    Ok(()) => format!("Loaded {}", state_path),
+                    Err(err) => format!("Could not load {}: {}", state_path, err)
+                },
+                Action::Quit => return Ok(())
+            }
+        }
+        keyboard.tick(runner.cpu_mut().input_mut());
+        if runner.update(Instant::now())? > 0 {
+            rewind.record(runner.cpu());
+        }
+
+        let shown = if runner.cpu().exit { "Program exited" } else { &message };
+        let bar = status(&name, runner.stats(), runner.cpu().beep, runner.paused(), shown);
+        let mut out = io::stdout();
+        out.write_all(renderer.render(runner.cpu().graphics(), &bar).as_bytes())?;
+        out.flush()?;
+        thread::sleep(frame);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use input::Input;
+
+    #[test]
+    fn maps_keys_and_hotkeys() {
+        let keyboard = Keyboard::new(KeyMap::qwerty());
+        assert_eq!(vec![Action::Key(0x1), Action::Key(0xF), Action::Pause, Action::Reset, Action::Save, Action::Load],
+            keyboard.actions(b"1V \x12\x13\x0c"));
+        assert_eq!(vec![Action::Rewind, Action::Rewind], keyboard.actions(b"\x7f\x08"));
+        assert!(keyboard.actions(b"5").is_empty());
+        assert_eq!(vec![Action::Quit], keyboard.actions(b"\x03"));
+    }
+
+    #[test]
+    fn skips_escape_sequences() {
+        let keyboard = Keyboard::new(KeyMap::qwerty());
+        assert_eq!(vec![Action::Key(0x7)], keyboard.actions(b"\x1b[A\x1bOPa\x1b[15~"));
+        assert_eq!(vec![Action::Quit], keyboard.actions(b"\x1b"));
+    }
+
+    #[test]
+    fn releases_keys_that_stop_repeating() {
+        let mut keyboard = Keyboard::new(KeyMap::qwerty());
+        let mut keypad = Keypad::new();
+        keyboard.press(&mut keypad, 0x5);
+        for _ in 0..HOLD_FRAMES - 1 { keyboard.tick(&mut keypad); }
+        assert!(keypad.key_pressed(0x5));
+
+        keyboard.press(&mut keypad, 0x5);
+        for _ in 0..HOLD_FRAMES { keyboard.tick(&mut keypad); }
+        assert!(!keypad.key_pressed(0x5));
+    }
+
+    #[test]
+    fn renders_half_blocks_and_only_changes() {
+        let mut display = Display::quiet();
+        let mut renderer = Renderer::new();
+        display.update_pixel(0, 0, true);
+        display.update_pixel(1, 1, true);
+        display.update_pixel(2, 0, true);
+        display.update_pixel(2, 1, true);
+
+        let first = renderer.render(&display, "status");
+        assert!(first.starts_with("\x1b[2J\x1b[1;1H▀▄█ "));
+        assert!(first.ends_with("\x1b[17;1H\x1b[2K\x1b[7mstatus\x1b[0m"));
+
+        assert_eq!("", renderer.render(&display, "status"));
+        display.update_pixel(5, 3, true);
+        assert_eq!("\x1b[2;6H▄", renderer.render(&display, "status"));
+    }
+
+    #[test]
+    fn status_shows_name_rate_beep_and_message() {
+        let stats = Stats { instructions_per_second: 600.0, frames_per_second: 60.0 };
+        assert_eq!(" ibm.ch8   60 fps  ♪  PAUSED  Saved", status("ibm.ch8", stats, true, true, "Saved"));
+        assert!(status("ibm.ch8", stats, false, false, "").ends_with(HOTKEYS));
+    }
+}
@@ -0,0 +1,160 @@
+use std::cell::RefCell;
+use std::mem;
+use std::ptr;
+use std::slice;
+
+use cpu::platform::Platform;
+use machine::Machine;
+use Byte;
+
+// One machine per module instance. The exports are only unmangled on
+// wasm32, so they cannot clash with the C ABI in a native library.
+thread_local! {
+    static MACHINE: RefCell<Option<Machine>> = const { RefCell::new(None) };
+}
+
+fn with_machine<T, F>(default: T, f: F) -> T where F: FnOnce(&mut Machine) -> T {
+    MACHINE.with(|machine| machine.borrow_mut().as_mut().map_or(default, f))
+}
+
+/// Reserves `len` bytes for JavaScript to copy a ROM into.
+#[cfg_attr(target_arch = "wasm32", no_mangle)]
+pub extern "C" fn alloc(len: usize) -> *mut Byte {
+    let mut buffer = Vec::with_capacity(len);
+    let ptr = buffer.as_mut_ptr();
+    mem::forget(buffer);
+    ptr
+}
+
+/// Frees a buffer from `alloc`.
+///
+/// # Safety
+///
+/// `ptr` and `len` must come from one call to `alloc`.
+#[cfg_attr(target_arch = "wasm32", no_mangle)]
+pub unsafe extern "C" fn dealloc(ptr: *mut Byte, len: usize) {
+    drop(Vec::from_raw_parts(ptr, 0, len));
+}
+
+/// Starts the `len` byte ROM at `ptr` on the platform with the given id
+/// (0 CHIP-8, 1 SUPER-CHIP, 2 XO-CHIP, 3 CHIP-8X) with its default
+/// quirks. CXKK is seeded with `seed`, as the operating system's generator
+/// is not available to WebAssembly. Returns 0, or -1 if the ROM does not
+/// fit; until a ROM loads the other exports do nothing.
+///
+/// # Safety
+///
+/// `ptr` must point to `len` readable bytes.
+#[cfg_attr(target_arch = "wasm32", no_mangle)]
+pub unsafe extern "C" fn load_rom(ptr: *const Byte, len: usize, platform: Byte, seed: u32) -> i32 {
+    let platform = match Platform::from_id(platform) {
+        Some(platform) => platform,
+        None => return -1
+    };
+    let rom = slice::from_raw_parts(ptr, len);
+    match Machine::new(rom, platform, platform.default_quirks()) {
+        Ok(mut machine) => {
+            machine.set_seed(Some(seed as u64));
+            MACHINE.with(|current| *current.borrow_mut() = Some(machine));
+            0
+        },
+        Err(_) => -1
+    }
+}
+
+/// Runs one 60 Hz frame. Returns 0, or -1 if the program faulted.
+#[cfg_attr(target_arch = "wasm32", no_mangle)]
+pub extern "C" fn run_frame() -> i32 {
+    with_machine(-1, |machine| match machine.run_frame() {
+        Ok(()) => 0,
+        Err(_) => -1
+    })
+}
+
+/// `framebuffer_width() * framebuffer_height()` bytes, one per pixel row by
+/// row, with bit 0 set for plane 0 and bit 1 for plane 1. The pointer is
+/// good until the next call.
+#[cfg_attr(target_arch = "wasm32", no_mangle)]
+pub extern "C" fn framebuffer() -> *const Byte {
+    with_machine(ptr::null(), |machine| machine.framebuffer().as_ptr())
+}
+
+#[cfg_attr(target_arch = "wasm32", no_mangle)]
+pub extern "C" fn framebuffer_width() -> u32 {
+    with_machine(0, |machine| machine.width() as u32)
+}
+
+#[cfg_attr(target_arch = "wasm32", no_mangle)]
+pub extern "C" fn framebuffer_height() -> u32 {
+    with_machine(0, |machine| machine.height() as u32)
+}
+
+#[cfg_attr(target_arch = "wasm32", no_mangle)]
+pub extern "C" fn key_down(key: Byte) {
+    with_machine((), |machine| machine.set_key(key, true))
+}
+
+#[cfg_attr(target_arch = "wasm32", no_mangle)]
+pub extern "C" fn key_up(key: Byte) {
+    with_machine((), |machine| machine.set_key(key, false))
+}
+
+/// 1 while the sound timer is running.
+#[cfg_attr(target_arch = "wasm32", no_mangle)]
+pub extern "C" fn beep() -> i32 {
+    with_machine(0, |machine| machine.beep() as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use machine::TEST_ROM;
+
+    fn load(rom: &[Byte], platform: Byte) -> i32 {
+        let ptr = alloc(rom.len());
+        unsafe {
+            ptr::copy_nonoverlapping(rom.as_ptr(), ptr, rom.len());
+            let result = load_rom(ptr, rom.len(), platform, 1);
+            dealloc(ptr, rom.len());
+            result
+        }
+    }
+
+    #[test]
+    fn nothing_runs_before_a_rom_is_loaded() {
+        assert_eq!(-1, run_frame());
+        assert!(framebuffer().is_null());
+        assert_eq!((0, 0), (framebuffer_width(), framebuffer_height()));
+        assert_eq!(0, beep());
+    }
+
+    #[test]
+    fn runs_a_loaded_rom() {
+        assert_eq!(0, load(&TEST_ROM, 0));
+        assert_eq!((64, 32), (framebuffer_width(), framebuffer_height()));
+        assert_eq!(0, run_frame());
+        assert!(!framebuffer().is_null());
+        assert_eq!(1, beep());
+    }
+
+    #[test]
+    fn keys_reach_the_machine() {
+        // Wait for a key into V0, then stop.
+        assert_eq!(0, load(&[0xF0, 0x0A, 0x12, 0x02], 0));
+        assert_eq!(0, run_frame());
+        key_down(0x9);
+        assert_eq!(0, run_frame());
+        key_up(0x9);
+        assert_eq!(0, run_frame());
+        MACHINE.with(|machine| {
+            let machine = machine.borrow();
+            assert_eq!(0x9, machine.as_ref().unwrap().read_register(0x0));
+        });
+    }
+
+    #[test]
+    fn bad_platform_or_rom_fails() {
+        assert_eq!(-1, load(&[0x00, 0xE0], 9));
+        assert_eq!(-1, load(&[0x0; 0x1000], 0));
+    }
+}
@@ -0,0 +1,249 @@
+//! Drives the libretro core the way RetroArch does: through the `retro_*`
+//! symbols of the shared library cargo builds before these tests.
+#![cfg(unix)]
+
+extern crate libc;
+extern crate rusty_chip;
+
+use std::cell::RefCell;
+use std::env;
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::os::raw::{c_uint, c_void};
+use std::path::PathBuf;
+use std::ptr;
+use std::slice;
+
+use rusty_chip::libretro::{
+    AudioSampleBatchFn, AudioSampleFn, EnvironmentFn, GameInfo, InputDescriptor, InputPollFn, InputStateFn,
+    SystemAvInfo, SystemInfo, VideoRefreshFn, SAMPLE_RATE
+};
+
+const RETRO_API_VERSION: c_uint = 1;
+const RETRO_ENVIRONMENT_SET_PIXEL_FORMAT: c_uint = 10;
+const RETRO_ENVIRONMENT_SET_INPUT_DESCRIPTORS: c_uint = 11;
+const RETRO_PIXEL_FORMAT_XRGB8888: c_uint = 1;
+const RETRO_DEVICE_JOYPAD: c_uint = 1;
+const SAMPLES_PER_FRAME: usize = 735;
+
+const JOYPAD_A: c_uint = 8;
+
+/// What the core has sent the frontend.
+#[derive(Default)]
+struct Received {
+    pixel_format: Option<c_uint>,
+    descriptors: usize,
+    video: Vec<u32>,
+    size: (c_uint, c_uint, usize),
+    audio: Vec<i16>,
+    buttons: u16
+}
+
+thread_local! {
+    static RECEIVED: RefCell<Received> = RefCell::new(Received::default());
+}
+
+unsafe extern "C" fn environment(cmd: c_uint, data: *mut c_void) -> bool {
+    RECEIVED.with(|received| {
+        let mut received = received.borrow_mut();
+        match cmd {
+            RETRO_ENVIRONMENT_SET_PIXEL_FORMAT => received.pixel_format = Some(*(data as *const c_uint)),
+            RETRO_ENVIRONMENT_SET_INPUT_DESCRIPTORS => {
+                let mut descriptor = data as *const InputDescriptor;
+                while !(*descriptor).description.is_null() {
+                    received.descriptors += 1;
+                    descriptor = descriptor.add(1);
+                }
+            },
+            _ => return false
+        }
+        true
+    })
+}
+
+unsafe extern "C" fn video_refresh(data: *const c_void, width: c_uint, height: c_uint, pitch: usize) {
+    RECEIVED.with(|received| {
+        let mut received = received.borrow_mut();
+        received.size = (width, height, pitch);
+        received.video = slice::from_raw_parts(data as *const u32, (width * height) as usize).to_vec();
+    });
+}
+
+unsafe extern "C" fn audio_sample(_left: i16, _right: i16) {}
+
+unsafe extern "C" fn audio_sample_batch(data: *const i16, frames: usize) -> usize {
+    RECEIVED.with(|received| received.borrow_mut().audio = slice::from_raw_parts(data, frames * 2).to_vec());
+    frames
+}
+
+unsafe extern "C" fn input_poll() {}
+
+unsafe extern "C" fn input_state(port: c_uint, device: c_uint, _index: c_uint, id: c_uint) -> i16 {
+    let buttons = RECEIVED.with(|received| received.borrow().buttons);
+    (port == 0 && device == RETRO_DEVICE_JOYPAD && buttons & 0b1 << id != 0) as i16
+}
+
+/// A stand-in for RetroArch: loads the core's shared library and drives
+/// it through the `retro_*` symbols only.
+struct Frontend {
+    library: *mut libc::c_void
+}
+
+impl Frontend {
+    /// The shared library cargo builds before the tests that use it.
+    fn core_path() -> PathBuf {
+        let exe = env::current_exe().unwrap();
+        let name = format!("{}rusty_chip{}", env::consts::DLL_PREFIX, env::consts::DLL_SUFFIX);
+        exe.parent().unwrap().join(&name)
+    }
+
+    fn load() -> Frontend {
+        let path = Frontend::core_path();
+        let path = CString::new(path.to_str().unwrap()).unwrap();
+        let library = unsafe { libc::dlopen(path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        assert!(!library.is_null(), "could not load {:?}", path);
+        let frontend = Frontend { library };
+        unsafe {
+            frontend.symbol::<extern "C" fn(EnvironmentFn)>("retro_set_environment")(environment);
+            frontend.symbol::<extern "C" fn(VideoRefreshFn)>("retro_set_video_refresh")(video_refresh);
+            frontend.symbol::<extern "C" fn(AudioSampleFn)>("retro_set_audio_sample")(audio_sample);
+            frontend.symbol::<extern "C" fn(AudioSampleBatchFn)>("retro_set_audio_sample_batch")(audio_sample_batch);
+            frontend.symbol::<extern "C" fn(InputPollFn)>("retro_set_input_poll")(input_poll);
+            frontend.symbol::<extern "C" fn(InputStateFn)>("retro_set_input_state")(input_state);
+            frontend.symbol::<extern "C" fn()>("retro_init")();
+        }
+        frontend
+    }
+
+    unsafe fn symbol<F: Copy>(&self, name: &str) -> F {
+        let name = CString::new(name).unwrap();
+        let symbol = libc::dlsym(self.library, name.as_ptr());
+        assert!(!symbol.is_null(), "core does not export {:?}", name);
+        mem::transmute_copy(&symbol)
+    }
+
+    fn load_game(&self, path: &str, rom: &[u8]) -> bool {
+        let path = CString::new(path).unwrap();
+        let game = GameInfo { path: path.as_ptr(), data: rom.as_ptr() as *const c_void, size: rom.len(), meta: ptr::null() };
+        unsafe { self.symbol::<unsafe extern "C" fn(*const GameInfo) -> bool>("retro_load_game")(&game) }
+    }
+
+    fn run(&self, frames: u32) {
+        for _ in 0..frames {
+            unsafe { self.symbol::<extern "C" fn()>("retro_run")(); }
+        }
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        unsafe {
+            let size = self.symbol::<extern "C" fn() -> usize>("retro_serialize_size")();
+            let mut state = vec![0xFF; size];
+            assert!(self.symbol::<unsafe extern "C" fn(*mut c_void, usize) -> bool>("retro_serialize")(state.as_mut_ptr() as *mut c_void, size));
+            state
+        }
+    }
+
+    fn unserialize(&self, state: &[u8]) -> bool {
+        unsafe { self.symbol::<unsafe extern "C" fn(*const c_void, usize) -> bool>("retro_unserialize")(state.as_ptr() as *const c_void, state.len()) }
+    }
+
+    fn hold(&self, buttons: u16) {
+        RECEIVED.with(|received| received.borrow_mut().buttons = buttons);
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> u32 {
+        RECEIVED.with(|received| {
+            let received = received.borrow();
+            received.video[y * received.size.0 as usize + x]
+        })
+    }
+}
+
+impl Drop for Frontend {
+    fn drop(&mut self) {
+        unsafe {
+            self.symbol::<extern "C" fn()>("retro_unload_game")();
+            self.symbol::<extern "C" fn()>("retro_deinit")();
+            libc::dlclose(self.library);
+        }
+    }
+}
+
+#[test]
+fn describes_the_core() {
+    let frontend = Frontend::load();
+    unsafe {
+        assert_eq!(RETRO_API_VERSION, frontend.symbol::<extern "C" fn() -> c_uint>("retro_api_version")());
+        let mut info: SystemInfo = mem::zeroed();
+        frontend.symbol::<unsafe extern "C" fn(*mut SystemInfo)>("retro_get_system_info")(&mut info);
+        assert_eq!("Rusty CHIP", CStr::from_ptr(info.library_name).to_str().unwrap());
+        assert!(CStr::from_ptr(info.valid_extensions).to_str().unwrap().contains("ch8"));
+        assert!(!info.need_fullpath);
+    }
+}
+
+#[test]
+fn runs_a_game_into_video_and_audio() {
+    let frontend = Frontend::load();
+    // Sets the sound timer and draws a digit, so both video and audio
+    // have something to convert.
+    assert!(frontend.load_game("/roms/seven.ch8", &[0x60, 0x07, 0xF0, 0x18, 0xF0, 0x29, 0xD1, 0x15, 0x12, 0x08]));
+    RECEIVED.with(|received| {
+        let received = received.borrow();
+        assert_eq!(Some(RETRO_PIXEL_FORMAT_XRGB8888), received.pixel_format);
+        assert_eq!(16, received.descriptors);
+    });
+
+    unsafe {
+        let mut info: SystemAvInfo = mem::zeroed();
+        frontend.symbol::<unsafe extern "C" fn(*mut SystemAvInfo)>("retro_get_system_av_info")(&mut info);
+        assert_eq!((64, 32, 128, 64), (info.geometry.base_width, info.geometry.base_height, info.geometry.max_width, info.geometry.max_height));
+        assert_eq!((60.0, SAMPLE_RATE as f64), (info.timing.fps, info.timing.sample_rate));
+    }
+
+    frontend.run(2);
+    RECEIVED.with(|received| {
+        let received = received.borrow();
+        assert_eq!((64, 32, 256), received.size);
+        assert_eq!(SAMPLES_PER_FRAME * 2, received.audio.len());
+        assert!(received.audio.iter().any(|&sample| sample > 0) && received.audio.iter().any(|&sample| sample < 0));
+    });
+    assert_eq!(0xFFFFFF, frontend.pixel(0, 0));
+    assert_eq!(0x000000, frontend.pixel(4, 0));
+
+    // The sound timer runs out after seven frames.
+    frontend.run(8);
+    RECEIVED.with(|received| assert!(received.borrow().audio.iter().all(|&sample| sample == 0)));
+}
+
+#[test]
+fn retropad_presses_keys() {
+    let frontend = Frontend::load();
+    // Wait for a key into V0, draw its digit, then stop.
+    assert!(frontend.load_game("keys.ch8", &[0xF0, 0x0A, 0xF0, 0x29, 0xD1, 0x15, 0x12, 0x06]));
+    frontend.run(2);
+    frontend.hold(0b1 << JOYPAD_A);
+    frontend.run(2);
+    frontend.hold(0);
+    frontend.run(3);
+    // The top two rows of the 5 sprite are 1111 and 1000.
+    assert_eq!((0xFFFFFF, 0xFFFFFF, 0x000000), (frontend.pixel(3, 0), frontend.pixel(0, 1), frontend.pixel(1, 1)));
+}
+
+#[test]
+fn states_rewind_to_the_same_frames() {
+    let frontend = Frontend::load();
+    // Draw a random byte as a sprite at the top left, forever.
+    let rom = [0xC0, 0xFF, 0xA3, 0x00, 0xF0, 0x55, 0xA3, 0x00, 0x00, 0xE0, 0xD1, 0x11, 0x12, 0x00];
+    assert!(frontend.load_game("random.ch8", &rom));
+    frontend.run(3);
+    let state = frontend.serialize();
+    frontend.run(5);
+    let later = RECEIVED.with(|received| received.borrow().video.clone());
+
+    assert!(frontend.unserialize(&state));
+    frontend.run(5);
+    RECEIVED.with(|received| assert_eq!(later, received.borrow().video));
+    assert_eq!(state.len(), frontend.serialize().len());
+    assert!(!frontend.unserialize(&state[..3]));
+}
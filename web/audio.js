@@ -4,6 +4,7 @@ class Audio {
     this.muted = true
     this.context = new (window.AudioContext || window.webkitAudioContext)()
     this.oscillator = this.context.createOscillator()
+    this.gain = this.context.createGain()
 
     this.startOscillator(frequency)
     this.button.onclick = (e) => { this.toggle(e.currentTarget) }
@@ -12,20 +13,28 @@ class Audio {
   startOscillator(frequency) {
     this.oscillator.type = 'sine'
     this.oscillator.frequency.value = frequency
+    this.gain.gain.value = 0
+    this.oscillator.connect(this.gain)
     this.oscillator.start()
   }
 
+  // Sounds the tone while the interpreter's sound timer runs.
+  beep(on) {
+    this.gain.gain.value = on ? 1 : 0
+  }
+
   mute() {
     if (!this.muted) {
       this.muted = true
-      this.oscillator.disconnect(this.context.destination)
+      this.gain.disconnect(this.context.destination)
     }
   }
 
   unmute() {
     if (this.muted) {
       this.muted = false
-      this.oscillator.connect(this.context.destination)
+      this.context.resume()
+      this.gain.connect(this.context.destination)
     }
   }
 
@@ -1,12 +1,12 @@
+// Pixel colours by the planes they are lit on: neither, plane 0, plane 1
+// and both.
+const COLOURS = ['black', 'white', 'gray', 'silver']
+
 class Display {
   constructor(canvas) {
     this.canvas = canvas
     this.context = canvas.getContext('2d')
-    this.numPixels = { x: 64, y: 32 }
-    this.pixelDimensions = { width: 10, height: 15 }
-
-    this.initContext()
-    this.initPixels()
+    this.resize(64, 32)
   }
 
   initContext() {
@@ -18,17 +18,41 @@ class Display {
 
   initPixels() {
     this.pixels = new Array(this.numPixels.x * this.numPixels.y)
-    this.pixels.fill(false)
+    this.pixels.fill(0)
+  }
+
+  resize(width, height) {
+    this.numPixels = { x: width, y: height }
+    this.pixelDimensions = {
+      width: this.canvas.width / width,
+      height: this.canvas.height / height
+    }
+    this.initContext()
+    this.initPixels()
+  }
+
+  // Draws the pixels that changed in a framebuffer from the interpreter,
+  // one byte per pixel with a bit for each plane.
+  render(framebuffer, width, height) {
+    if (width !== this.numPixels.x || height !== this.numPixels.y) {
+      this.resize(width, height)
+    }
+    for (let i = 0; i < framebuffer.length; i++) {
+      if (this.pixels[i] !== framebuffer[i]) {
+        this.pixels[i] = framebuffer[i]
+        this.drawPixel(i)
+      }
+    }
   }
 
   flipPixel(i) {
-    this.pixels[i] = !this.pixels[i]
+    this.pixels[i] = this.pixels[i] ? 0 : 1
   }
 
   drawPixel(i) {
     let x = i % this.numPixels.x
     let y = Math.floor(i / this.numPixels.x)
-    this.context.fillStyle = this.pixels[i] ? 'white' : 'black'
+    this.context.fillStyle = COLOURS[this.pixels[i] & 0b11]
     this.context.fillRect(
       x * this.pixelDimensions.width,
       y * this.pixelDimensions.height,
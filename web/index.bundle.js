@@ -78,7 +78,7 @@
 /*! no static exports found */
 /***/ (function(module, exports) {
 
-eval("class Audio {\n  constructor(button, frequency = 1000) {\n    this.button = button\n    this.muted = true\n    this.context = new (window.AudioContext || window.webkitAudioContext)()\n    this.oscillator = this.context.createOscillator()\n\n    this.startOscillator(frequency)\n    this.button.onclick = (e) => { this.toggle(e.currentTarget) }\n  }\n\n  startOscillator(frequency) {\n    this.oscillator.type = 'sine'\n    this.oscillator.frequency.value = frequency\n    this.oscillator.start()\n  }\n\n  mute() {\n    if (!this.muted) {\n      this.muted = true\n      this.oscillator.disconnect(this.context.destination)\n    }\n  }\n\n  unmute() {\n    if (this.muted) {\n      this.muted = false\n      this.oscillator.connect(this.context.destination)\n    }\n  }\n\n  toggle(button) {\n    let mutedIcon = button.querySelector('.muted')\n    let unmutedIcon = button.querySelector('.unmuted')\n    mutedIcon.hidden = !mutedIcon.hidden\n    unmutedIcon.hidden = !unmutedIcon.hidden\n    this.muted ? this.unmute() : this.mute()\n  }\n}\n\nmodule.exports = Audio;\n\n\n//# sourceURL=webpack:///./web/audio.js?");
+eval("class Audio {\n  constructor(button, frequency = 1000) {\n    this.button = button\n    this.muted = true\n    this.context = new (window.AudioContext || window.webkitAudioContext)()\n    this.oscillator = this.context.createOscillator()\n    this.gain = this.context.createGain()\n\n    this.startOscillator(frequency)\n    this.button.onclick = (e) => { this.toggle(e.currentTarget) }\n  }\n\n  startOscillator(frequency) {\n    this.oscillator.type = 'sine'\n    this.oscillator.frequency.value = frequency\n    this.gain.gain.value = 0\n    this.oscillator.connect(this.gain)\n    this.oscillator.start()\n  }\n\n  // Sounds the tone while the interpreter's sound timer runs.\n  beep(on) {\n    this.gain.gain.value = on ? 1 : 0\n  }\n\n  mute() {\n    if (!this.muted) {\n      this.muted = true\n      this.gain.disconnect(this.context.destination)\n    }\n  }\n\n  unmute() {\n    if (this.muted) {\n      this.muted = false\n      this.context.resume()\n      this.gain.connect(this.context.destination)\n    }\n  }\n\n  toggle(button) {\n    let mutedIcon = button.querySelector('.muted')\n    let unmutedIcon = button.querySelector('.unmuted')\n    mutedIcon.hidden = !mutedIcon.hidden\n    unmutedIcon.hidden = !unmutedIcon.hidden\n    this.muted ? this.unmute() : this.mute()\n  }\n}\n\nmodule.exports = Audio;\n\n\n//# sourceURL=webpack:///./web/audio.js?");
 
 /***/ }),
 
@@ -89,7 +89,7 @@ eval("class Audio {\n  constructor(button, frequency = 1000) {\n    this.button
 /*! no static exports found */
 /***/ (function(module, exports) {
 
-eval("class Display {\n  constructor(canvas) {\n    this.canvas = canvas\n    this.context = canvas.getContext('2d')\n    this.numPixels = { x: 64, y: 32 }\n    this.pixelDimensions = { width: 10, height: 15 }\n\n    this.initContext()\n    this.initPixels()\n  }\n\n  initContext() {\n    this.context.webkitImageSmoothingEnabled = false\n    this.context.msImageSmoothingEnabled = false\n    this.context.imageSmoothingEnabled = false\n    this.context.fillRect(0, 0, this.canvas.width, this.canvas.height);\n  }\n\n  initPixels() {\n    this.pixels = new Array(this.numPixels.x * this.numPixels.y)\n    this.pixels.fill(false)\n  }\n\n  flipPixel(i) {\n    this.pixels[i] = !this.pixels[i]\n  }\n\n  drawPixel(i) {\n    let x = i % this.numPixels.x\n    let y = Math.floor(i / this.numPixels.x)\n    this.context.fillStyle = this.pixels[i] ? 'white' : 'black'\n    this.context.fillRect(\n      x * this.pixelDimensions.width,\n      y * this.pixelDimensions.height,\n      this.pixelDimensions.width,\n      this.pixelDimensions.height\n    )\n  }\n}\n\nmodule.exports = Display;\n\n\n//# sourceURL=webpack:///./web/display.js?");
+eval("// Pixel colours by the planes they are lit on: neither, plane 0, plane 1\n// and both.\nconst COLOURS = ['black', 'white', 'gray', 'silver']\n\nclass Display {\n  constructor(canvas) {\n    this.canvas = canvas\n    this.context = canvas.getContext('2d')\n    this.resize(64, 32)\n  }\n\n  initContext() {\n    this.context.webkitImageSmoothingEnabled = false\n    this.context.msImageSmoothingEnabled = false\n    this.context.imageSmoothingEnabled = false\n    this.context.fillRect(0, 0, this.canvas.width, this.canvas.height);\n  }\n\n  initPixels() {\n    this.pixels = new Array(this.numPixels.x * this.numPixels.y)\n    this.pixels.fill(0)\n  }\n\n  resize(width, height) {\n    this.numPixels = { x: width, y: height }\n    this.pixelDimensions = {\n      width: this.canvas.width / width,\n      height: this.canvas.height / height\n    }\n    this.initContext()\n    this.initPixels()\n  }\n\n  // Draws the pixels that changed in a framebuffer from the interpreter,\n  // one byte per pixel with a bit for each plane.\n  render(framebuffer, width, height) {\n    if (width !== this.numPixels.x || height !== this.numPixels.y) {\n      this.resize(width, height)\n    }\n    for (let i = 0; i < framebuffer.length; i++) {\n      if (this.pixels[i] !== framebuffer[i]) {\n        this.pixels[i] = framebuffer[i]\n        this.drawPixel(i)\n      }\n    }\n  }\n\n  flipPixel(i) {\n    this.pixels[i] = this.pixels[i] ? 0 : 1\n  }\n\n  drawPixel(i) {\n    let x = i % this.numPixels.x\n    let y = Math.floor(i / this.numPixels.x)\n    this.context.fillStyle = COLOURS[this.pixels[i] & 0b11]\n    this.context.fillRect(\n      x * this.pixelDimensions.width,\n      y * this.pixelDimensions.height,\n      this.pixelDimensions.width,\n      this.pixelDimensions.height\n    )\n  }\n}\n\nmodule.exports = Display;\n\n\n//# sourceURL=webpack:///./web/display.js?");
 
 /***/ }),
 
@@ -100,7 +100,7 @@ eval("class Display {\n  constructor(canvas) {\n    this.canvas = canvas\n    th
 /*! no static exports found */
 /***/ (function(module, exports, __webpack_require__) {
 
-eval("const Audio = __webpack_require__(/*! ./audio.js */ \"./web/audio.js\")\nconst Display = __webpack_require__(/*! ./display.js */ \"./web/display.js\")\nconst Keypad = __webpack_require__(/*! ./keypad.js */ \"./web/keypad.js\")\n\nconst canvas = document.getElementById('screen')\nconst mute = document.getElementById('mute')\n\nlet display = new Display(canvas)\nlet audio = new Audio(mute)\nlet keypad = new Keypad()\n\n\n//# sourceURL=webpack:///./web/index.js?");
+eval("const Audio = __webpack_require__(/*! ./audio.js */ \"./web/audio.js\")\nconst Display = __webpack_require__(/*! ./display.js */ \"./web/display.js\")\nconst Keypad = __webpack_require__(/*! ./keypad.js */ \"./web/keypad.js\")\n\nconst FRAME_MS = 1000 / 60\n\nconst canvas = document.getElementById('screen')\nconst mute = document.getElementById('mute')\nconst romInput = document.getElementById('rom')\nconst platformSelect = document.getElementById('platform')\n\nlet display = new Display(canvas)\nlet audio = new Audio(mute)\n\nfetch('./web/rusty_chip.wasm')\n  .then(response => response.arrayBuffer())\n  .then(bytes => WebAssembly.instantiate(bytes))\n  .then(({ instance }) => start(instance.exports))\n\nfunction start(chip) {\n  let loaded = false\n  let owed = 0\n  let last = null\n\n  new Keypad(key => chip.key_down(key), key => chip.key_up(key))\n\n  romInput.onchange = () => {\n    let file = romInput.files[0]\n    if (file) { file.arrayBuffer().then(load) }\n  }\n\n  function load(buffer) {\n    let rom = new Uint8Array(buffer)\n    let ptr = chip.alloc(rom.length)\n    new Uint8Array(chip.memory.buffer, ptr, rom.length).set(rom)\n    let seed = Math.floor(Math.random() * 0xFFFFFFFF)\n    loaded = chip.load_rom(ptr, rom.length, Number(platformSelect.value), seed) === 0\n    chip.dealloc(ptr, rom.length)\n    if (!loaded) { console.error('Could not load ' + romInput.files[0].name) }\n  }\n\n  // Animation frames come at the monitor's rate, so run the machine's\n  // 60 Hz frames as they fall due.\n  function frame(now) {\n    owed = last === null ? 1 : Math.min(owed + (now - last) / FRAME_MS, 10)\n    last = now\n    while (loaded && owed >= 1) {\n      owed -= 1\n      if (chip.run_frame() !== 0) {\n        loaded = false\n        console.error('The program faulted')\n      }\n    }\n    if (loaded) {\n      let width = chip.framebuffer_width()\n      let height = chip.framebuffer_height()\n      display.render(new Uint8Array(chip.memory.buffer, chip.framebuffer(), width * height), width, height)\n      audio.beep(chip.beep() !== 0)\n    } else {\n      audio.beep(false)\n    }\n    requestAnimationFrame(frame)\n  }\n  requestAnimationFrame(frame)\n}\n\n\n//# sourceURL=webpack:///./web/index.js?");
 
 /***/ }),
 
@@ -111,7 +111,7 @@ eval("const Audio = __webpack_require__(/*! ./audio.js */ \"./web/audio.js\")\nc
 /*! no static exports found */
 /***/ (function(module, exports) {
 
-eval("class Keypad {\n  constructor() {\n    this.initListeners()\n  }\n\n  initListeners() {\n    document.addEventListener('keydown', e => {\n      switch(e.key) {\n        case \"ArrowUp\":\n          console.log('UP')\n          break\n        case \"ArrowDown\":\n          console.log('DOWN')\n          break\n        case \"ArrowLeft\":\n          console.log('LEFT')\n          break\n        case \"ArrowRight\":\n          console.log('RIGHT')\n          break\n        case \"0\":\n        case \"1\":\n        case \"2\":\n        case \"3\":\n        case \"4\":\n        case \"5\":\n        case \"6\":\n        case \"7\":\n        case \"8\":\n        case \"9\":\n        case \"a\":\n        case \"b\":\n        case \"c\":\n        case \"d\":\n        case \"e\":\n        case \"f\":\n          console.log(e.key)\n          break\n        default:\n          break\n      }\n    })\n\n    document.addEventListener('keyup', e => {\n      switch(e.key) {\n        case \"ArrowUp\":\n          console.log('UP')\n          break\n        case \"ArrowDown\":\n          console.log('DOWN')\n          break\n        case \"ArrowLeft\":\n          console.log('LEFT')\n          break\n        case \"ArrowRight\":\n          console.log('RIGHT')\n          break\n        case \"0\":\n        case \"1\":\n        case \"2\":\n        case \"3\":\n        case \"4\":\n        case \"5\":\n        case \"6\":\n        case \"7\":\n        case \"8\":\n        case \"9\":\n        case \"a\":\n        case \"b\":\n        case \"c\":\n        case \"d\":\n        case \"e\":\n        case \"f\":\n          console.log(e.key)\n          break\n        default:\n          break\n      }\n    })\n  }\n}\n\nmodule.exports = Keypad;\n\n\n//# sourceURL=webpack:///./web/keypad.js?");
+eval("// The left-hand block of the keyboard by position, so other layouts put\n// the keypad in the same place.\nconst KEYS = {\n  Digit1: 0x1, Digit2: 0x2, Digit3: 0x3, Digit4: 0xC,\n  KeyQ: 0x4, KeyW: 0x5, KeyE: 0x6, KeyR: 0xD,\n  KeyA: 0x7, KeyS: 0x8, KeyD: 0x9, KeyF: 0xE,\n  KeyZ: 0xA, KeyX: 0x0, KeyC: 0xB, KeyV: 0xF\n}\n\nclass Keypad {\n  constructor(onDown, onUp) {\n    this.onDown = onDown\n    this.onUp = onUp\n    this.initListeners()\n  }\n\n  initListeners() {\n    document.addEventListener('keydown', e => {\n      let key = KEYS[e.code]\n      if (key === undefined || e.repeat) { return }\n      e.preventDefault()\n      this.onDown(key)\n    })\n\n    document.addEventListener('keyup', e => {\n      let key = KEYS[e.code]\n      if (key === undefined) { return }\n      e.preventDefault()\n      this.onUp(key)\n    })\n  }\n}\n\nmodule.exports = Keypad;\n\n\n//# sourceURL=webpack:///./web/keypad.js?");
 
 /***/ })
 
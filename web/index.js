@@ -2,9 +2,64 @@ const Audio = require('./audio.js')
 const Display = require('./display.js')
 const Keypad = require('./keypad.js')
 
+const FRAME_MS = 1000 / 60
+
 const canvas = document.getElementById('screen')
 const mute = document.getElementById('mute')
+const romInput = document.getElementById('rom')
+const platformSelect = document.getElementById('platform')
 
 let display = new Display(canvas)
 let audio = new Audio(mute)
-let keypad = new Keypad()
+
+fetch('./web/rusty_chip.wasm')
+  .then(response => response.arrayBuffer())
+  .then(bytes => WebAssembly.instantiate(bytes))
+  .then(({ instance }) => start(instance.exports))
+
+function start(chip) {
+  let loaded = false
+  let owed = 0
+  let last = null
+
+  new Keypad(key => chip.key_down(key), key => chip.key_up(key))
+
+  romInput.onchange = () => {
+    let file = romInput.files[0]
+    if (file) { file.arrayBuffer().then(load) }
+  }
+
+  function load(buffer) {
+    let rom = new Uint8Array(buffer)
+    let ptr = chip.alloc(rom.length)
+    new Uint8Array(chip.memory.buffer, ptr, rom.length).set(rom)
+    let seed = Math.floor(Math.random() * 0xFFFFFFFF)
+    loaded = chip.load_rom(ptr, rom.length, Number(platformSelect.value), seed) === 0
+    chip.dealloc(ptr, rom.length)
+    if (!loaded) { console.error('Could not load ' + romInput.files[0].name) }
+  }
+
+  // Animation frames come at the monitor's rate, so run the machine's
+  // 60 Hz frames as they fall due.
+  function frame(now) {
+    owed = last === null ? 1 : Math.min(owed + (now - last) / FRAME_MS, 10)
+    last = now
+    while (loaded && owed >= 1) {
+      owed -= 1
+      if (chip.run_frame() !== 0) {
+        loaded = false
+        console.error('The program faulted')
+      }
+    }
+    if (loaded) {
+      let width = chip.framebuffer_width()
+      let height = chip.framebuffer_height()
+      display.render(new Uint8Array(chip.memory.buffer, chip.framebuffer(), width * height), width, height)
+      audio.beep(chip.beep() !== 0)
+    } else {
+      audio.beep(false)
+    }
+    requestAnimationFrame(frame)
+  }
+  requestAnimationFrame(frame)
+}
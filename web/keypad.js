@@ -1,81 +1,32 @@
+// The left-hand block of the keyboard by position, so other layouts put
+// the keypad in the same place.
+const KEYS = {
+  Digit1: 0x1, Digit2: 0x2, Digit3: 0x3, Digit4: 0xC,
+  KeyQ: 0x4, KeyW: 0x5, KeyE: 0x6, KeyR: 0xD,
+  KeyA: 0x7, KeyS: 0x8, KeyD: 0x9, KeyF: 0xE,
+  KeyZ: 0xA, KeyX: 0x0, KeyC: 0xB, KeyV: 0xF
+}
+
 class Keypad {
-  constructor() {
+  constructor(onDown, onUp) {
+    this.onDown = onDown
+    this.onUp = onUp
     this.initListeners()
   }
 
   initListeners() {
     document.addEventListener('keydown', e => {
-      switch(e.key) {
-        case "ArrowUp":
-          console.log('UP')
-          break
-        case "ArrowDown":
-          console.log('DOWN')
-          break
-        case "ArrowLeft":
-          console.log('LEFT')
-          break
-        case "ArrowRight":
-          console.log('RIGHT')
-          break
-        case "0":
-        case "1":
-        case "2":
-        case "3":
-        case "4":
-        case "5":
-        case "6":
-        case "7":
-        case "8":
-        case "9":
-        case "a":
-        case "b":
-        case "c":
-        case "d":
-        case "e":
-        case "f":
-          console.log(e.key)
-          break
-        default:
-          break
-      }
+      let key = KEYS[e.code]
+      if (key === undefined || e.repeat) { return }
+      e.preventDefault()
+      this.onDown(key)
     })
 
     document.addEventListener('keyup', e => {
-      switch(e.key) {
-        case "ArrowUp":
-          console.log('UP')
-          break
-        case "ArrowDown":
-          console.log('DOWN')
-          break
-        case "ArrowLeft":
-          console.log('LEFT')
-          break
-        case "ArrowRight":
-          console.log('RIGHT')
-          break
-        case "0":
-        case "1":
-        case "2":
-        case "3":
-        case "4":
-        case "5":
-        case "6":
-        case "7":
-        case "8":
-        case "9":
-        case "a":
-        case "b":
-        case "c":
-        case "d":
-        case "e":
-        case "f":
-          console.log(e.key)
-          break
-        default:
-          break
-      }
+      let key = KEYS[e.code]
+      if (key === undefined) { return }
+      e.preventDefault()
+      this.onUp(key)
     })
   }
 }